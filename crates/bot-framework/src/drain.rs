@@ -0,0 +1,88 @@
+//! Waiting for message handlers that are still running when a bot quits, so
+//! a deploy does not cut off a catch halfway through being written.
+
+use std::time::Duration;
+
+use log::{error, info, warn};
+use tokio::{task::JoinSet, time::timeout};
+
+/// How long running handlers get to finish by default
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Log handlers that panicked or were cancelled
+pub fn report(result: Result<(), tokio::task::JoinError>) {
+    if let Err(err) = result {
+        error!("Message handler failed: {err}");
+    }
+}
+
+/// Wait up to `drain_timeout` for the `handlers` to finish and abort the ones
+/// still running afterwards.
+///
+/// Returns how many handlers were aborted.
+pub async fn drain(handlers: &mut JoinSet<()>, drain_timeout: Duration) -> usize {
+    if handlers.is_empty() {
+        return 0;
+    }
+
+    info!("Waiting for {} running message handlers", handlers.len());
+
+    let finished = timeout(drain_timeout, async {
+        while let Some(result) = handlers.join_next().await {
+            report(result);
+        }
+    })
+    .await;
+
+    if finished.is_ok() {
+        return 0;
+    }
+
+    let aborted = handlers.len();
+    warn!("Aborting {aborted} message handlers still running after {drain_timeout:?}");
+    handlers.shutdown().await;
+
+    aborted
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn waits_for_running_handlers() {
+        let done = Arc::new(AtomicBool::new(false));
+        let mut handlers = JoinSet::new();
+        handlers.spawn({
+            let done = done.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                done.store(true, Ordering::SeqCst);
+            }
+        });
+
+        assert_eq!(drain(&mut handlers, Duration::from_secs(5)).await, 0);
+        assert!(done.load(Ordering::SeqCst));
+        assert!(handlers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn aborts_handlers_after_timeout() {
+        let mut handlers = JoinSet::new();
+        handlers.spawn(tokio::time::sleep(Duration::from_secs(60)));
+        handlers.spawn(async {});
+
+        assert_eq!(drain(&mut handlers, Duration::from_millis(50)).await, 1);
+        assert!(handlers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn nothing_to_drain() {
+        assert_eq!(drain(&mut JoinSet::new(), Duration::ZERO).await, 0);
+    }
+}
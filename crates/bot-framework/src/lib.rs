@@ -1,4 +1,6 @@
 mod account;
+pub mod drain;
+pub mod rate_limit;
 pub mod runner;
 
 pub use sea_orm::DatabaseConnection;
@@ -0,0 +1,158 @@
+//! Token bucket rate limiting for outgoing chat messages, so a busy channel
+//! does not get the bot timed out for spamming.
+
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+use log::debug;
+
+/// How many messages may be sent to a channel per period
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub messages: u32,
+    pub period: Duration,
+}
+
+impl Default for RateLimit {
+    /// Twitch's limit for users that are neither moderator nor VIP
+    fn default() -> Self {
+        Self {
+            messages: 20,
+            period: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RateLimit {
+    fn refill_rate(&self) -> f64 {
+        f64::from(self.messages) / self.period.as_secs_f64()
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn full(limit: &RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.messages),
+            updated: now,
+        }
+    }
+
+    /// Take a token or return how long to wait until one is available.
+    fn take(&mut self, limit: &RateLimit, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.refill_rate()).min(f64::from(limit.messages));
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / limit.refill_rate(),
+            ))
+        }
+    }
+}
+
+/// Keeps a token bucket per channel
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait until a token for `channel` is available and take it.
+    pub async fn acquire(&self, channel: &str) {
+        while let Err(wait) = self.take(channel, Instant::now()) {
+            debug!("Rate limited in #{channel}, waiting {wait:?}");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    fn take(&self, channel: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+
+        buckets
+            .entry(channel.to_owned())
+            .or_insert_with(|| Bucket::full(&self.limit, now))
+            .take(&self.limit, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimit {
+            messages: 2,
+            period: Duration::from_secs(10),
+        })
+    }
+
+    #[test]
+    fn allows_burst_up_to_limit() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        assert!(limiter.take("chan", now).is_ok());
+        assert!(limiter.take("chan", now).is_ok());
+
+        let wait = limiter.take("chan", now).unwrap_err();
+        assert!((wait.as_secs_f64() - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn refills_over_time() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        limiter.take("chan", now).unwrap();
+        limiter.take("chan", now).unwrap();
+
+        assert!(limiter.take("chan", now + Duration::from_secs(6)).is_ok());
+        assert!(limiter.take("chan", now + Duration::from_secs(6)).is_err());
+    }
+
+    #[test]
+    fn does_not_refill_above_limit() {
+        let limiter = limiter();
+        let now = Instant::now();
+        let later = now + Duration::from_secs(60);
+
+        limiter.take("chan", now).unwrap();
+
+        assert!(limiter.take("chan", later).is_ok());
+        assert!(limiter.take("chan", later).is_ok());
+        assert!(limiter.take("chan", later).is_err());
+    }
+
+    #[test]
+    fn channels_have_separate_buckets() {
+        let limiter = limiter();
+        let now = Instant::now();
+
+        limiter.take("first", now).unwrap();
+        limiter.take("first", now).unwrap();
+
+        assert!(limiter.take("first", now).is_err());
+        assert!(limiter.take("second", now).is_ok());
+    }
+}
@@ -1,19 +1,26 @@
-use std::{collections::HashSet, future::Future, pin::Pin, sync::Arc};
+use std::{collections::HashSet, future::Future, pin::Pin, sync::Arc, time::Duration};
 
-use database::connection;
+use database::{connection_with_retry, DB_CONNECT_ATTEMPTS, DB_CONNECT_DELAY};
 use log::{debug, error, info, trace};
 use miette::{Diagnostic, Result};
 use sea_orm::DatabaseConnection;
 use signal_hook::consts::signal::{SIGINT, SIGQUIT, SIGTERM};
 use signal_hook_tokio::Signals;
-use tokio::{select, sync::Notify, task::JoinHandle};
+use tokio::{
+    select,
+    sync::{mpsc::UnboundedReceiver, Notify},
+    task::{JoinHandle, JoinSet},
+};
 use tokio_stream::StreamExt;
 use twitch_irc::{
     login::RefreshingLoginCredentials, message::ServerMessage, ClientConfig, SecureTCPTransport,
     TwitchIRCClient,
 };
 
-use crate::account::{self, Account};
+use crate::{
+    account::{self, Account},
+    drain,
+};
 
 pub type Client = TwitchIRCClient<SecureTCPTransport, RefreshingLoginCredentials<Account>>;
 pub type IrcError = twitch_irc::Error<SecureTCPTransport, RefreshingLoginCredentials<Account>>;
@@ -55,6 +62,20 @@ pub struct Config {
     pub username: String,
     pub client_id: String,
     pub client_secret: String,
+    /// How long message handlers still running on quit get to finish
+    pub drain_timeout: Duration,
+    /// Handle messages while earlier ones are still being handled. Messages
+    /// are handled one after another in the order they arrive otherwise.
+    pub concurrent: bool,
+}
+
+/// Parse a comma separated list of channels
+pub fn parse_channels(channels: &str) -> HashSet<String> {
+    channels
+        .split(',')
+        .map(|channel| channel.trim().trim_start_matches('#').to_lowercase())
+        .filter(|channel| !channel.is_empty())
+        .collect()
 }
 
 pub async fn start_bot<I, H>(config: Config, init: I, handle_server_message: H) -> Result<()>
@@ -77,7 +98,9 @@ where
     let (quit, quit_handle, quit_task) = register_signals()?;
 
     info!("Connecting to database");
-    let conn = connection().await.map_err(Error::ConnectDatabase)?;
+    let conn = connection_with_retry(DB_CONNECT_ATTEMPTS, DB_CONNECT_DELAY)
+        .await
+        .map_err(Error::ConnectDatabase)?;
 
     start_twitch_bot(conn.clone(), config, quit, init, handle_server_message).await?;
 
@@ -130,6 +153,8 @@ where
         username,
         client_id,
         client_secret,
+        drain_timeout,
+        concurrent,
     } = bot_config;
 
     let client_config = create_client_config(&conn, username, client_id, client_secret).await?;
@@ -154,22 +179,14 @@ where
 
         async move {
             debug!("Starting message handler loop");
-            loop {
-                select! {
-                    channel_value = incoming_messages.recv() => {
-                        let Some(message) = channel_value else {
-                            break;
-                        };
-                        if let Err(err) = handle_server_message(conn.clone(), client.clone(), message).await {
-                            error!("Error handling message: {err}");
-                        }
-                    }
-                    _ = quit.notified() => {
-                        debug!("Received quitting twitch task");
-                        break;
-                    }
-                }
-            }
+            dispatch(
+                &mut incoming_messages,
+                |message| handle_server_message(conn.clone(), client.clone(), message),
+                concurrent,
+                quit,
+                drain_timeout,
+            )
+            .await;
         }
     });
 
@@ -193,6 +210,45 @@ where
     Ok(())
 }
 
+/// Pass every message to `handle` until the messages run out or `quit` is
+/// notified, then drain the handlers still running.
+///
+/// Unless `concurrent` is set the next message is only received once the
+/// handler of the last one finished.
+async fn dispatch<M, H>(
+    messages: &mut UnboundedReceiver<M>,
+    handle: H,
+    concurrent: bool,
+    quit: Arc<Notify>,
+    drain_timeout: Duration,
+) where
+    H: Fn(M) -> Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>>,
+{
+    let mut handlers = JoinSet::new();
+    loop {
+        select! {
+            channel_value = messages.recv(), if concurrent || handlers.is_empty() => {
+                let Some(message) = channel_value else {
+                    break;
+                };
+                let handler = handle(message);
+                handlers.spawn(async move {
+                    if let Err(err) = handler.await {
+                        error!("Error handling message: {err}");
+                    }
+                });
+            }
+            Some(result) = handlers.join_next() => drain::report(result),
+            _ = quit.notified() => {
+                debug!("Received quitting twitch task");
+                break;
+            }
+        }
+    }
+
+    drain::drain(&mut handlers, drain_timeout).await;
+}
+
 async fn create_client_config(
     conn: &DatabaseConnection,
     username: String,
@@ -228,3 +284,65 @@ async fn handle_signals(mut signals: Signals, quit_signal: Arc<Notify>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    /// Dispatch messages that take longer to handle the earlier they arrive
+    /// and return the order they were handled in.
+    async fn handled_order(concurrent: bool) -> Vec<u64> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        for message in 1..=3 {
+            sender.send(message).unwrap();
+        }
+        drop(sender);
+
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        dispatch(
+            &mut receiver,
+            |message: u64| {
+                let handled = handled.clone();
+                Box::pin(async move {
+                    tokio::time::sleep(Duration::from_millis(30 * (4 - message))).await;
+                    handled.lock().unwrap().push(message);
+                    Ok(())
+                })
+            },
+            concurrent,
+            Arc::new(Notify::new()),
+            Duration::from_secs(5),
+        )
+        .await;
+
+        let handled = handled.lock().unwrap().clone();
+        handled
+    }
+
+    #[test]
+    fn channels() {
+        let mut channels: Vec<_> = parse_channels(" #Forsen, pajlada ,,").into_iter().collect();
+        channels.sort_unstable();
+
+        assert_eq!(channels, ["forsen", "pajlada"]);
+    }
+
+    #[test]
+    fn no_channels() {
+        assert!(parse_channels(" , ").is_empty());
+    }
+
+    #[tokio::test]
+    async fn handles_messages_in_order() {
+        assert_eq!(handled_order(false).await, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn handles_messages_concurrently() {
+        assert_eq!(handled_order(true).await, vec![3, 2, 1]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Fishes as they are exported and imported, without database ids
+
+use serde::{Deserialize, Serialize};
+
+use crate::entities::fishes;
+
+/// Definition of a fish in a bundle
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FishDefinition {
+    pub name: String,
+    pub html_name: String,
+    pub count: i32,
+    pub base_value: f32,
+    pub min_weight: f32,
+    pub max_weight: f32,
+    pub is_trash: bool,
+    #[serde(default = "default_weight_distribution")]
+    pub weight_distribution: String,
+    #[serde(default)]
+    pub weight_mean: Option<f32>,
+    #[serde(default)]
+    pub weight_stddev: Option<f32>,
+}
+
+fn default_weight_distribution() -> String {
+    "uniform".to_string()
+}
+
+impl From<fishes::Model> for FishDefinition {
+    fn from(fish: fishes::Model) -> Self {
+        Self {
+            name: fish.name,
+            html_name: fish.html_name,
+            count: fish.count,
+            base_value: fish.base_value,
+            min_weight: fish.min_weight,
+            max_weight: fish.max_weight,
+            is_trash: fish.is_trash,
+            weight_distribution: fish.weight_distribution,
+            weight_mean: fish.weight_mean,
+            weight_stddev: fish.weight_stddev,
+        }
+    }
+}
+
+impl FishDefinition {
+    /// The fish stored under `id`
+    pub fn to_model(&self, id: i32) -> fishes::Model {
+        fishes::Model {
+            id,
+            name: self.name.clone(),
+            html_name: self.html_name.clone(),
+            count: self.count,
+            base_value: self.base_value,
+            max_weight: self.max_weight,
+            min_weight: self.min_weight,
+            is_trash: self.is_trash,
+            weight_distribution: self.weight_distribution.clone(),
+            weight_mean: self.weight_mean,
+            weight_stddev: self.weight_stddev,
+        }
+    }
+}
@@ -14,6 +14,9 @@ pub struct Model {
     pub max_weight: f32,
     pub min_weight: f32,
     pub is_trash: bool,
+    pub weight_distribution: String,
+    pub weight_mean: Option<f32>,
+    pub weight_stddev: Option<f32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
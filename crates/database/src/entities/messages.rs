@@ -10,6 +10,7 @@ pub struct Model {
     pub id: i32,
     pub text: String,
     pub r#type: MessageType,
+    pub channel: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
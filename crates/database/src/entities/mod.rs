@@ -7,6 +7,7 @@ pub mod bundle;
 pub mod catches;
 pub mod fish_bundle;
 pub mod fishes;
+pub mod inventory;
 pub mod messages;
 pub mod sea_orm_active_enums;
 pub mod season_data;
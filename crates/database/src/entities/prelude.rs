@@ -5,6 +5,7 @@ pub use super::bundle::Entity as Bundle;
 pub use super::catches::Entity as Catches;
 pub use super::fish_bundle::Entity as FishBundle;
 pub use super::fishes::Entity as Fishes;
+pub use super::inventory::Entity as Inventory;
 pub use super::messages::Entity as Messages;
 pub use super::season_data::Entity as SeasonData;
 pub use super::seasons::Entity as Seasons;
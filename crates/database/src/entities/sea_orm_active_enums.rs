@@ -7,4 +7,6 @@ use sea_orm::entity::prelude::*;
 pub enum MessageType {
     #[sea_orm(string_value = "cooldown")]
     Cooldown,
+    #[sea_orm(string_value = "catch")]
+    Catch,
 }
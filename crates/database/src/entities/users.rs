@@ -16,6 +16,8 @@ pub struct Model {
 pub enum Relation {
     #[sea_orm(has_many = "super::catches::Entity")]
     Catches,
+    #[sea_orm(has_many = "super::inventory::Entity")]
+    Inventory,
     #[sea_orm(has_many = "super::season_data::Entity")]
     SeasonData,
 }
@@ -26,6 +28,12 @@ impl Related<super::catches::Entity> for Entity {
     }
 }
 
+impl Related<super::inventory::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Inventory.def()
+    }
+}
+
 impl Related<super::season_data::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::SeasonData.def()
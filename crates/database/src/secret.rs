@@ -0,0 +1,84 @@
+//! Secrets can be read from files instead of the environment, e.g. from
+//! Docker secrets, so they don't show up in process listings.
+
+use std::{env, fs, io, path::PathBuf};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SecretError {
+    #[error("Environment variable {name} not set")]
+    NotSet { source: env::VarError, name: String },
+
+    #[error("Could not read {name} from {path:?}")]
+    ReadFile {
+        source: io::Error,
+        name: String,
+        path: PathBuf,
+    },
+}
+
+/// Read the secret `name` from the file named by `{name}_FILE` if that is
+/// set, or else from the environment variable `name`. Trailing newlines are
+/// trimmed from the file contents.
+pub fn secret_var(name: &str) -> Result<String, SecretError> {
+    if let Some(path) = env::var_os(format!("{name}_FILE")) {
+        let path = PathBuf::from(path);
+
+        return match fs::read_to_string(&path) {
+            Ok(secret) => Ok(secret.trim_end_matches(['\r', '\n']).to_owned()),
+            Err(source) => Err(SecretError::ReadFile {
+                source,
+                name: name.to_owned(),
+                path,
+            }),
+        };
+    }
+
+    env::var(name).map_err(|source| SecretError::NotSet {
+        source,
+        name: name.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every test uses its own variables, the environment is shared
+
+    #[test]
+    fn from_environment() {
+        env::set_var("SECRET_TEST_ENV", "hunter2");
+
+        assert_eq!(secret_var("SECRET_TEST_ENV").unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn from_file() {
+        let path = env::temp_dir().join("secret_test_file");
+        fs::write(&path, "hunter2\r\n").unwrap();
+        env::set_var("SECRET_TEST_FILE", "ignored");
+        env::set_var("SECRET_TEST_FILE_FILE", &path);
+
+        assert_eq!(secret_var("SECRET_TEST_FILE").unwrap(), "hunter2");
+
+        fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn missing_file() {
+        env::set_var("SECRET_TEST_MISSING_FILE", "/nonexistent/secret");
+
+        assert!(matches!(
+            secret_var("SECRET_TEST_MISSING"),
+            Err(SecretError::ReadFile { .. })
+        ));
+    }
+
+    #[test]
+    fn not_set() {
+        assert!(matches!(
+            secret_var("SECRET_TEST_NOT_SET"),
+            Err(SecretError::NotSet { .. })
+        ));
+    }
+}
@@ -0,0 +1,94 @@
+//! Rarity tiers of fishes, derived from their share of the population
+
+use std::fmt::Display;
+
+use serde::Serialize;
+
+/// Fishes with a chance below this are legendary
+pub const LEGENDARY_CHANCE: f32 = 0.01;
+/// Fishes with a chance below this are considered rare.
+pub const RARE_CHANCE: f32 = 0.05;
+/// Fishes with a chance below this are uncommon
+pub const UNCOMMON_CHANCE: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+    Common,
+    Uncommon,
+    Rare,
+    Legendary,
+}
+
+impl Tier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Common => "common",
+            Self::Uncommon => "uncommon",
+            Self::Rare => "rare",
+            Self::Legendary => "legendary",
+        }
+    }
+}
+
+impl Display for Tier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Tier of a fish with `count` out of `population` fishes. Fishes that can not
+/// be caught and fishes of an empty population are common.
+pub fn tier(count: i64, population: i64) -> Tier {
+    if count <= 0 || population <= 0 {
+        return Tier::Common;
+    }
+
+    let chance = count as f32 / population as f32;
+
+    if chance < LEGENDARY_CHANCE {
+        Tier::Legendary
+    } else if chance < RARE_CHANCE {
+        Tier::Rare
+    } else if chance < UNCOMMON_CHANCE {
+        Tier::Uncommon
+    } else {
+        Tier::Common
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiers() {
+        for (count, expected) in [
+            (1, Tier::Legendary),
+            (9, Tier::Legendary),
+            (10, Tier::Rare),
+            (49, Tier::Rare),
+            (50, Tier::Uncommon),
+            (149, Tier::Uncommon),
+            (150, Tier::Common),
+            (1000, Tier::Common),
+        ] {
+            assert_eq!(tier(count, 1000), expected, "{count}");
+        }
+    }
+
+    #[test]
+    fn empty_population() {
+        assert_eq!(tier(0, 0), Tier::Common);
+    }
+
+    #[test]
+    fn uncatchable_fish() {
+        assert_eq!(tier(0, 1000), Tier::Common);
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(Tier::Legendary.to_string(), "legendary");
+    }
+}
@@ -0,0 +1,176 @@
+//! Simulate catches from the active season's fishes and report the average
+//! value per fish.
+//!
+//! `CASTS` sets the number of casts (default 1,000,000). Setting `SEED` makes
+//! the simulation reproducible.
+//!
+//! With `--seasons N` the fishes of the last `N` seasons are simulated instead
+//! and compared to the average value of the catches made during each season.
+
+use std::env;
+
+use chrono::Utc;
+use database::{
+    connection,
+    entities::{catches, fishes, prelude::*, seasons},
+    GIFT_FISH,
+};
+use dotenvy::dotenv;
+use eyre::{eyre, Result, WrapErr};
+use fishinge_bot::{get_active_season, get_fishes, simulate_catches};
+use sea_orm::{
+    sea_query::{Expr, Func, SimpleExpr},
+    ColumnTrait, DatabaseConnection, DeriveColumn, EntityTrait, EnumIter, QueryFilter, QueryOrder,
+    QuerySelect,
+};
+
+const DEFAULT_CASTS: u32 = 1_000_000;
+const USAGE: &str = "Usage: monte_carlo [--seasons N]";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    pretty_env_logger::init();
+
+    let casts = match env::var("CASTS") {
+        Ok(casts) => casts.parse().wrap_err("CASTS is not a number")?,
+        Err(_) => DEFAULT_CASTS,
+    };
+    let seed = match env::var("SEED") {
+        Ok(seed) => Some(seed.parse().wrap_err("SEED is not a number")?),
+        Err(_) => None,
+    };
+
+    let mut seasons = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seasons" => {
+                let count = args.next().ok_or_else(|| eyre!(USAGE))?;
+                seasons = Some(
+                    count
+                        .parse::<u64>()
+                        .wrap_err_with(|| format!("Invalid number of seasons {count:?}"))?,
+                );
+            }
+            _ => return Err(eyre!(USAGE)),
+        }
+    }
+
+    let db = connection().await?;
+
+    match seasons {
+        Some(seasons) => compare_seasons(&db, seasons, casts, seed).await,
+        None => report_active_season(&db, casts, seed).await,
+    }
+}
+
+async fn report_active_season(
+    db: &DatabaseConnection,
+    casts: u32,
+    seed: Option<u64>,
+) -> Result<()> {
+    let season = get_active_season(db).await?;
+    let fishes = get_fishes(db, &season).await?;
+
+    let simulation = simulate_catches(&fishes, casts, seed);
+
+    // most valuable fishes first to spot fishes valued off their rarity
+    let mut report: Vec<_> = simulation.fishes.iter().collect();
+    report.sort_by(|a, b| b.average_value().total_cmp(&a.average_value()));
+
+    println!(
+        "{:<24} {:>10} {:>8} {:>12}",
+        "fish", "catches", "share", "avg value"
+    );
+
+    for fish in report {
+        println!(
+            "{:<24} {:>10} {:>7.3}% {:>12.2}",
+            fish.name,
+            fish.catches,
+            f64::from(fish.catches) / f64::from(simulation.casts.max(1)) * 100.0,
+            fish.average_value()
+        );
+    }
+
+    println!(
+        "{} casts worth ${:.2} on average",
+        simulation.casts,
+        simulation.average_value()
+    );
+
+    Ok(())
+}
+
+/// Simulate the bundles of the last `count` seasons that already started
+async fn compare_seasons(
+    db: &DatabaseConnection,
+    count: u64,
+    casts: u32,
+    seed: Option<u64>,
+) -> Result<()> {
+    let seasons = Seasons::find()
+        .filter(seasons::Column::Start.lte(Utc::now()))
+        .order_by_desc(seasons::Column::Start)
+        .limit(count)
+        .all(db)
+        .await
+        .wrap_err("Could not fetch seasons")?;
+
+    println!(
+        "{:<16} {:>8} {:>12} {:>14} {:>14}",
+        "season", "fishes", "population", "simulated avg", "actual avg"
+    );
+
+    for season in seasons {
+        let fishes = get_fishes(db, &season).await?;
+        let simulation = simulate_catches(&fishes, casts, seed);
+        let actual = actual_average_value(db, &season).await?;
+
+        println!(
+            "{:<16} {:>8} {:>12} {:>14.2} {:>14}",
+            season.name,
+            fishes.len(),
+            fishes.population(),
+            simulation.average_value(),
+            actual.map_or_else(|| "-".to_string(), |actual| format!("{actual:.2}"))
+        );
+    }
+
+    Ok(())
+}
+
+/// Average value of the catches made while `season` was running. Gifts are
+/// not catches and left out.
+async fn actual_average_value(
+    db: &DatabaseConnection,
+    season: &seasons::Model,
+) -> Result<Option<f64>> {
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+    enum QueryAs {
+        Average,
+    }
+
+    let mut query = Catches::find()
+        .inner_join(Fishes)
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .filter(catches::Column::CaughtAt.gte(season.start));
+
+    if let Some(end) = season.end {
+        query = query.filter(catches::Column::CaughtAt.lt(end));
+    }
+
+    let average = query
+        .select_only()
+        .column_as(
+            SimpleExpr::from(Func::avg(Expr::col((Catches, catches::Column::Value)))),
+            QueryAs::Average,
+        )
+        .into_values::<Option<f64>, QueryAs>()
+        .one(db)
+        .await
+        .wrap_err_with(|| format!("Could not fetch catches of season {}", season.name))?;
+
+    Ok(average.flatten())
+}
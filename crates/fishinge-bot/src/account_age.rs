@@ -0,0 +1,210 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{Mutex, PoisonError},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use fishinge_bot::Account;
+use log::debug;
+use lru::LruCache;
+use serde::Deserialize;
+use twitch_irc::login::{LoginCredentials, RefreshingLoginCredentials};
+
+use crate::{responder::HELIX_URL, Error};
+
+/// How many account creation dates are remembered
+const CAPACITY: usize = 4096;
+
+/// Looks up when twitch accounts were created
+#[async_trait]
+pub(crate) trait AccountLookup: Send + Sync {
+    /// Creation date of the account with the id `user_id`, `None` if there is
+    /// no such account
+    async fn created_at(&self, user_id: &str) -> Result<Option<DateTime<Utc>>, Error>;
+}
+
+/// Looks up accounts through the Helix users endpoint
+pub(crate) struct HelixAccountLookup {
+    http: reqwest::Client,
+    client_id: String,
+    credentials: RefreshingLoginCredentials<Account>,
+}
+
+impl HelixAccountLookup {
+    pub(crate) fn new(client_id: String, credentials: RefreshingLoginCredentials<Account>) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            client_id,
+            credentials,
+        }
+    }
+}
+
+#[async_trait]
+impl AccountLookup for HelixAccountLookup {
+    async fn created_at(&self, user_id: &str) -> Result<Option<DateTime<Utc>>, Error> {
+        #[derive(Deserialize)]
+        struct User {
+            created_at: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Users {
+            data: Vec<User>,
+        }
+
+        let token = self
+            .credentials
+            .get_credentials()
+            .await
+            .map_err(Error::GetCredentials)?
+            .token
+            .ok_or(Error::MissingToken)?;
+
+        let users: Users = self
+            .http
+            .get(format!("{HELIX_URL}/users"))
+            .query(&[("id", user_id)])
+            .bearer_auth(token)
+            .header("Client-Id", &self.client_id)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        users
+            .data
+            .into_iter()
+            .next()
+            .map(|user| {
+                DateTime::parse_from_rfc3339(&user.created_at)
+                    .map(|created_at| created_at.with_timezone(&Utc))
+                    .map_err(|_| Error::InvalidAccountCreation(user.created_at))
+            })
+            .transpose()
+    }
+}
+
+/// Keeps accounts younger than a minimum age from fishing for the first time.
+///
+/// Creation dates never change, so they are looked up once per account and
+/// remembered.
+pub(crate) struct AccountAgeGate {
+    min_age: Duration,
+    lookup: Box<dyn AccountLookup>,
+    created_at: Mutex<LruCache<String, DateTime<Utc>>>,
+}
+
+impl AccountAgeGate {
+    pub(crate) fn new(min_age: Duration, lookup: Box<dyn AccountLookup>) -> Self {
+        Self {
+            min_age,
+            lookup,
+            created_at: Mutex::new(LruCache::new(NonZeroUsize::new(CAPACITY).unwrap())),
+        }
+    }
+
+    /// Whether the account `user_id` is at least the minimum age at `now`.
+    /// Accounts twitch does not know are not.
+    pub(crate) async fn admits(&self, user_id: &str, now: DateTime<Utc>) -> Result<bool, Error> {
+        let cached = self
+            .created_at
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(user_id)
+            .copied();
+
+        let created_at = match cached {
+            Some(created_at) => created_at,
+            None => match self.lookup.created_at(user_id).await? {
+                Some(created_at) => {
+                    self.created_at
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .put(user_id.to_owned(), created_at);
+
+                    created_at
+                }
+                None => {
+                    debug!("Account {user_id} does not exist");
+
+                    return Ok(false);
+                }
+            },
+        };
+
+        Ok(now - created_at >= self.min_age)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Knows a single account and counts the lookups
+    struct FakeLookup {
+        created_at: DateTime<Utc>,
+        lookups: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl AccountLookup for FakeLookup {
+        async fn created_at(&self, user_id: &str) -> Result<Option<DateTime<Utc>>, Error> {
+            self.lookups.fetch_add(1, Ordering::SeqCst);
+
+            Ok((user_id == "1").then_some(self.created_at))
+        }
+    }
+
+    fn gate(lookups: Arc<AtomicUsize>) -> AccountAgeGate {
+        AccountAgeGate::new(
+            Duration::days(7),
+            Box::new(FakeLookup {
+                created_at: Utc.with_ymd_and_hms(2023, 6, 1, 12, 0, 0).unwrap(),
+                lookups,
+            }),
+        )
+    }
+
+    #[tokio::test]
+    async fn admits_old_enough_accounts() {
+        let gate = gate(Default::default());
+
+        assert!(!gate
+            .admits("1", Utc.with_ymd_and_hms(2023, 6, 8, 11, 59, 0).unwrap())
+            .await
+            .unwrap());
+        assert!(gate
+            .admits("1", Utc.with_ymd_and_hms(2023, 6, 8, 12, 0, 0).unwrap())
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn unknown_account_is_not_admitted() {
+        let gate = gate(Default::default());
+
+        assert!(!gate.admits("2", Utc::now()).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn creation_dates_are_cached() {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let gate = gate(lookups.clone());
+
+        for _ in 0..3 {
+            gate.admits("1", Utc::now()).await.unwrap();
+        }
+
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,82 @@
+//! Scale the base values of the active season's fishes so a catch is worth
+//! `target` on average. Trash keeps its base value.
+//!
+//! Usage: `balance <target> [--dry-run]`
+//!
+//! Fishes can be part of several bundles, so the new base values apply to
+//! every season using them.
+
+use database::{connection, entities::fishes};
+use dotenvy::dotenv;
+use eyre::{eyre, Result, WrapErr};
+use fishinge_bot::{balance_base_values, expected_value_per_catch, get_active_season, get_fishes};
+use sea_orm::{ActiveModelTrait, ActiveValue, TransactionTrait};
+
+const USAGE: &str = "Usage: balance <target> [--dry-run]";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    pretty_env_logger::init();
+
+    let mut target = None;
+    let mut dry_run = false;
+
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            _ => {
+                let value = arg
+                    .parse::<f64>()
+                    .wrap_err_with(|| format!("Invalid target {arg:?}"))?;
+                target = Some(value);
+            }
+        }
+    }
+
+    let target = target.ok_or_else(|| eyre!(USAGE))?;
+
+    let db = connection().await?;
+    let season = get_active_season(&db).await?;
+    let mut fishes = get_fishes(&db, &season).await?.into_fishes();
+
+    let base_values = balance_base_values(&fishes, target)
+        .ok_or_else(|| eyre!("Cannot reach an expected value of {target}"))?;
+
+    let before = expected_value_per_catch(&fishes);
+
+    for (fish, base_value) in fishes.iter_mut().zip(base_values) {
+        println!(
+            "{:<24} {:>8} -> {:>8}",
+            fish.name, fish.base_value, base_value
+        );
+        fish.base_value = base_value;
+    }
+
+    println!(
+        "expected value per catch: {before:.2} -> {:.2}",
+        expected_value_per_catch(&fishes)
+    );
+
+    if dry_run {
+        return Ok(());
+    }
+
+    let txn = db.begin().await?;
+
+    for fish in &fishes {
+        fishes::ActiveModel {
+            id: ActiveValue::Unchanged(fish.id),
+            base_value: ActiveValue::Set(fish.base_value as f32),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    println!("updated {} fishes in season {}", fishes.len(), season.name);
+
+    Ok(())
+}
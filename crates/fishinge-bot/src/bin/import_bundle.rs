@@ -0,0 +1,46 @@
+//! Load a bundle of fishes from a JSON file and use it for a season.
+//!
+//! Usage: `import_bundle <season id> <fishes.json>`
+//!
+//! The file holds an array of fish definitions in the format served by the
+//! website's `/api/fishes`. The whole bundle is validated before anything is
+//! written.
+
+use std::fs;
+
+use database::{connection, definition::FishDefinition};
+use dotenvy::dotenv;
+use eyre::{eyre, Result, WrapErr};
+use fishinge_bot::import_bundle;
+
+const USAGE: &str = "Usage: import_bundle <season id> <fishes.json>";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    pretty_env_logger::init();
+
+    let mut args = std::env::args().skip(1);
+    let season_id = args.next().ok_or_else(|| eyre!(USAGE))?;
+    let path = args.next().ok_or_else(|| eyre!(USAGE))?;
+
+    let season_id = season_id
+        .parse::<i32>()
+        .wrap_err_with(|| format!("Invalid season id {season_id:?}"))?;
+
+    let definitions: Vec<FishDefinition> = serde_json::from_str(
+        &fs::read_to_string(&path).wrap_err_with(|| format!("Could not read {path}"))?,
+    )
+    .wrap_err_with(|| format!("Could not parse fishes from {path}"))?;
+
+    let db = connection().await?;
+    let bundle = import_bundle(&db, &definitions, season_id).await?;
+
+    println!(
+        "Imported {} fishes as bundle {} for season {season_id}",
+        definitions.len(),
+        bundle.id
+    );
+
+    Ok(())
+}
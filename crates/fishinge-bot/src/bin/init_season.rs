@@ -0,0 +1,39 @@
+//! Create the first season of a fresh deployment, using the fishes of an
+//! existing bundle. The season is named after the current quarter unless a
+//! name is given.
+//!
+//! Usage: `init_season <bundle id> [name]`
+
+use chrono::{Offset, Utc};
+use database::connection;
+use dotenvy::dotenv;
+use eyre::{eyre, Result, WrapErr};
+use fishinge_bot::{init_first_season, YearAndQuarter};
+
+const USAGE: &str = "Usage: init_season <bundle id> [name]";
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenv().ok();
+    pretty_env_logger::init();
+
+    let mut args = std::env::args().skip(1);
+
+    let bundle_id = args.next().ok_or_else(|| eyre!(USAGE))?;
+    let bundle_id = bundle_id
+        .parse::<i32>()
+        .wrap_err_with(|| format!("Invalid bundle id {bundle_id:?}"))?;
+    let name = args.next().unwrap_or_else(|| {
+        YearAndQuarter::from_start(Utc::now().with_timezone(&Utc.fix())).to_string()
+    });
+
+    let db = connection().await?;
+    let season = init_first_season(&db, name, bundle_id).await?;
+
+    println!(
+        "created season {} with bundle {}",
+        season.name, season.bundle_id
+    );
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Per-user cooldowns of chat commands, kept in memory. Entries are dropped
+/// once their cooldown is over, so users who leave are forgotten.
+#[derive(Debug)]
+pub(crate) struct CommandCooldowns {
+    window: Duration,
+    last_used: Mutex<HashMap<(String, String), Instant>>,
+}
+
+impl CommandCooldowns {
+    /// A user can use each command once per `window`. A zero `window`
+    /// disables the cooldowns.
+    pub(crate) fn new(window: Duration) -> Self {
+        Self {
+            window,
+            last_used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record `user` using `command` at `now`. Returns whether the command is
+    /// off cooldown; uses on cooldown don't extend it.
+    pub(crate) fn try_use(&self, user: &str, command: &str, now: Instant) -> bool {
+        if self.window.is_zero() {
+            return true;
+        }
+
+        let mut last_used = self
+            .last_used
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        last_used.retain(|_, used_at| now.saturating_duration_since(*used_at) < self.window);
+
+        let key = (user.to_owned(), command.to_owned());
+        if last_used.contains_key(&key) {
+            return false;
+        }
+
+        last_used.insert(key, now);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn repeated_command() {
+        let cooldowns = CommandCooldowns::new(WINDOW);
+        let now = Instant::now();
+
+        assert!(cooldowns.try_use("alice", "🔍", now));
+        assert!(!cooldowns.try_use("alice", "🔍", now + Duration::from_secs(4)));
+        assert!(cooldowns.try_use("alice", "🔍", now + WINDOW));
+    }
+
+    #[test]
+    fn separate_users_and_commands() {
+        let cooldowns = CommandCooldowns::new(WINDOW);
+        let now = Instant::now();
+
+        assert!(cooldowns.try_use("alice", "🔍", now));
+        assert!(cooldowns.try_use("alice", "🏆", now));
+        assert!(cooldowns.try_use("bob", "🔍", now));
+    }
+
+    #[test]
+    fn forgets_expired_entries() {
+        let cooldowns = CommandCooldowns::new(WINDOW);
+        let now = Instant::now();
+
+        cooldowns.try_use("alice", "🔍", now);
+        cooldowns.try_use("bob", "🏆", now + WINDOW);
+
+        assert_eq!(cooldowns.last_used.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn disabled() {
+        let cooldowns = CommandCooldowns::new(Duration::ZERO);
+        let now = Instant::now();
+
+        assert!(cooldowns.try_use("alice", "🔍", now));
+        assert!(cooldowns.try_use("alice", "🔍", now));
+    }
+}
@@ -0,0 +1,238 @@
+use std::{collections::HashMap, str::FromStr};
+
+/// Chat commands triggered by `<emote> <trigger>`. Fishing itself has no emote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Command {
+    Catfishing,
+    Fishes,
+    Leaderboard,
+    DesignateBot,
+    Population,
+    Recount,
+    Messages,
+    Help,
+    MostValuable,
+    Luckiest,
+    Aquarium,
+    Bait,
+    AverageWeight,
+    Score,
+    AverageCatch,
+    NextSeason,
+    Gift,
+    TopCatch,
+    WhatIf,
+    Gap,
+}
+
+impl Command {
+    const ALL: [Self; 20] = [
+        Self::Catfishing,
+        Self::Fishes,
+        Self::Leaderboard,
+        Self::DesignateBot,
+        Self::Population,
+        Self::Recount,
+        Self::Messages,
+        Self::Help,
+        Self::MostValuable,
+        Self::Luckiest,
+        Self::Aquarium,
+        Self::Bait,
+        Self::AverageWeight,
+        Self::Score,
+        Self::AverageCatch,
+        Self::NextSeason,
+        Self::Gift,
+        Self::TopCatch,
+        Self::WhatIf,
+        Self::Gap,
+    ];
+
+    /// Name used to configure the command
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Catfishing => "catfishing",
+            Self::Fishes => "fishes",
+            Self::Leaderboard => "leaderboard",
+            Self::DesignateBot => "bot",
+            Self::Population => "population",
+            Self::Recount => "recount",
+            Self::Messages => "messages",
+            Self::Help => "help",
+            Self::MostValuable => "most-valuable",
+            Self::Luckiest => "luckiest",
+            Self::Aquarium => "aquarium",
+            Self::Bait => "bait",
+            Self::AverageWeight => "average-weight",
+            Self::Score => "score",
+            Self::AverageCatch => "average-catch",
+            Self::NextSeason => "next-season",
+            Self::Gift => "gift",
+            Self::TopCatch => "top-catch",
+            Self::WhatIf => "what-if",
+            Self::Gap => "gap",
+        }
+    }
+
+    fn default_emotes(self) -> &'static [&'static str] {
+        match self {
+            Self::Catfishing => &["🐱"],
+            Self::Fishes => &["🔍", "🔎"],
+            Self::Leaderboard => &["🏆"],
+            Self::DesignateBot => &["🤖"],
+            Self::Population => &["🌊"],
+            Self::Recount => &["🧮🔄"],
+            Self::Messages => &["💬"],
+            Self::Help => &["❓"],
+            Self::MostValuable => &["💎"],
+            Self::Luckiest => &["🍀"],
+            Self::Aquarium => &["🐟"],
+            Self::Bait => &["🪱"],
+            Self::AverageWeight => &["⚖️", "⚖"],
+            Self::Score => &["💰"],
+            Self::AverageCatch => &["📊"],
+            Self::NextSeason => &["⏭️", "⏭"],
+            Self::Gift => &["🎁"],
+            Self::TopCatch => &["🥇"],
+            Self::WhatIf => &["✨"],
+            Self::Gap => &["🥈"],
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_lowercase();
+
+        Self::ALL
+            .into_iter()
+            .find(|command| command.name() == name)
+            .ok_or(())
+    }
+}
+
+/// Which emote triggers which command
+#[derive(Debug, Clone)]
+pub(crate) struct Commands {
+    by_emote: HashMap<String, Command>,
+}
+
+impl Default for Commands {
+    fn default() -> Self {
+        let by_emote = Command::ALL
+            .into_iter()
+            .flat_map(|command| {
+                command
+                    .default_emotes()
+                    .iter()
+                    .map(move |emote| (emote.to_string(), command))
+            })
+            .collect();
+
+        Self { by_emote }
+    }
+}
+
+impl Commands {
+    /// The default emotes, with the emotes of the commands in `overrides`
+    /// replaced.
+    ///
+    /// `overrides` is a comma separated list of `command=emote` where several
+    /// emotes are separated by `|`, e.g. `score=💵,fishes=🐠|🔍`. Returns the
+    /// first invalid entry as error, including emotes used by two commands.
+    pub(crate) fn with_overrides(overrides: &str) -> Result<Self, String> {
+        let mut commands = Self::default();
+        let mut overridden = Vec::new();
+
+        for entry in overrides
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (name, emotes) = entry.split_once('=').ok_or_else(|| entry.to_string())?;
+            let command: Command = name.parse().map_err(|_| entry.to_string())?;
+
+            if !overridden.contains(&command) {
+                commands.by_emote.retain(|_, existing| *existing != command);
+                overridden.push(command);
+            }
+
+            for emote in emotes.split('|').map(str::trim) {
+                if emote.is_empty() || emote.contains(char::is_whitespace) {
+                    return Err(entry.to_string());
+                }
+
+                match commands.by_emote.get(emote) {
+                    Some(existing) if *existing != command => return Err(entry.to_string()),
+                    _ => commands.by_emote.insert(emote.to_string(), command),
+                };
+            }
+        }
+
+        Ok(commands)
+    }
+
+    pub(crate) fn get(&self, emote: &str) -> Option<Command> {
+        self.by_emote.get(emote).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+
+    use super::*;
+
+    #[test]
+    fn every_command_has_a_default_emote() {
+        let commands = Commands::default();
+
+        for command in Command::ALL {
+            assert!(!command.default_emotes().is_empty());
+            for emote in command.default_emotes() {
+                assert_eq!(commands.get(emote), Some(command));
+            }
+        }
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for command in Command::ALL {
+            assert_eq!(command.name().parse(), Ok(command));
+        }
+    }
+
+    #[test]
+    fn override_replaces_default_emotes() {
+        let commands = Commands::with_overrides("score=💵, fishes=🐠|🔍").unwrap();
+
+        assert_eq!(commands.get("💵"), Some(Command::Score));
+        assert_eq!(commands.get("💰"), None);
+        assert_eq!(commands.get("🐠"), Some(Command::Fishes));
+        assert_eq!(commands.get("🔍"), Some(Command::Fishes));
+        assert_eq!(commands.get("🔎"), None);
+        assert_eq!(commands.get("🏆"), Some(Command::Leaderboard));
+    }
+
+    #[test]
+    fn emote_can_move_to_another_command() {
+        let commands = Commands::with_overrides("catfishing=😿,fishes=🐱").unwrap();
+
+        assert_eq!(commands.get("🐱"), Some(Command::Fishes));
+    }
+
+    #[test_case("score" ; "without emote")]
+    #[test_case("fly=🪰" ; "unknown command")]
+    #[test_case("score=" ; "empty emote")]
+    #[test_case("score=💵 💰" ; "emote with whitespace")]
+    #[test_case("score=🏆" ; "emote of another command")]
+    fn invalid_override(overrides: &str) {
+        assert_eq!(
+            Commands::with_overrides(overrides).unwrap_err(),
+            overrides.trim()
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use std::{
+    num::NonZeroUsize,
+    sync::{Mutex, PoisonError},
+};
+
+use lru::LruCache;
+
+/// How many message ids are remembered
+const CAPACITY: usize = 4096;
+
+/// Remembers the ids of recently handled messages, so messages Twitch
+/// redelivers after a reconnect are only handled once.
+#[derive(Debug)]
+pub(crate) struct SeenMessages {
+    ids: Mutex<LruCache<String, ()>>,
+}
+
+impl SeenMessages {
+    /// Remember at most `capacity` ids, forgetting the least recent first.
+    pub(crate) fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            ids: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Remember `id`. Returns whether it was not seen before.
+    pub(crate) fn insert(&self, id: &str) -> bool {
+        self.ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .put(id.to_owned(), ())
+            .is_none()
+    }
+}
+
+impl Default for SeenMessages {
+    fn default() -> Self {
+        Self::new(NonZeroUsize::new(CAPACITY).unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_id() {
+        let seen = SeenMessages::default();
+
+        assert!(seen.insert("a"));
+        assert!(!seen.insert("a"));
+        assert!(seen.insert("b"));
+    }
+
+    #[test]
+    fn forgets_least_recent() {
+        let seen = SeenMessages::new(NonZeroUsize::new(2).unwrap());
+
+        seen.insert("a");
+        seen.insert("b");
+        // "a" is now more recent than "b"
+        seen.insert("a");
+        seen.insert("c");
+
+        assert!(!seen.insert("a"));
+        assert!(seen.insert("b"));
+    }
+}
@@ -0,0 +1,112 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveTime, Utc};
+
+/// Daily window in UTC, written as `HH:MM-HH:MM`. Windows ending before they
+/// start wrap past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HappyHour {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl HappyHour {
+    /// Whether `time` lies within the window. The end is exclusive.
+    pub(crate) fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
+
+impl FromStr for HappyHour {
+    type Err = chrono::ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once('-').unwrap_or((s, ""));
+
+        Ok(Self {
+            start: NaiveTime::parse_from_str(start.trim(), "%H:%M")?,
+            end: NaiveTime::parse_from_str(end.trim(), "%H:%M")?,
+        })
+    }
+}
+
+/// Time-limited events during which rare fishes are more likely
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HappyHours {
+    pub(crate) windows: Vec<HappyHour>,
+    /// Fishes with a chance below this are boosted
+    pub(crate) threshold: f32,
+    /// Multiplier for the count of boosted fishes
+    pub(crate) factor: f64,
+}
+
+impl HappyHours {
+    pub(crate) fn is_active(&self, now: DateTime<Utc>) -> bool {
+        let time = now.time();
+
+        self.windows.iter().any(|window| window.contains(time))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test_case(17, 59, false ; "before")]
+    #[test_case(18, 0, true ; "start")]
+    #[test_case(18, 30, true ; "within")]
+    #[test_case(19, 0, false ; "end")]
+    fn window(hour: u32, minute: u32, expected: bool) {
+        let window: HappyHour = "18:00-19:00".parse().unwrap();
+
+        assert_eq!(window.contains(time(hour, minute)), expected);
+    }
+
+    #[test_case(23, 0, false ; "before")]
+    #[test_case(23, 30, true ; "before midnight")]
+    #[test_case(0, 0, true ; "midnight")]
+    #[test_case(0, 15, true ; "after midnight")]
+    #[test_case(0, 30, false ; "end")]
+    #[test_case(12, 0, false ; "noon")]
+    fn window_past_midnight(hour: u32, minute: u32, expected: bool) {
+        let window: HappyHour = "23:30-00:30".parse().unwrap();
+
+        assert_eq!(window.contains(time(hour, minute)), expected);
+    }
+
+    #[test_case("18:00" ; "no end")]
+    #[test_case("18:00-25:00" ; "invalid hour")]
+    #[test_case("evening" ; "not a time")]
+    fn invalid_window(window: &str) {
+        assert!(window.parse::<HappyHour>().is_err());
+    }
+
+    #[test]
+    fn active_in_any_window() {
+        let happy_hours = HappyHours {
+            windows: vec![
+                "08:00-09:00".parse().unwrap(),
+                "23:30-00:30".parse().unwrap(),
+            ],
+            ..Default::default()
+        };
+
+        assert!(happy_hours.is_active(Utc.with_ymd_and_hms(2023, 6, 1, 0, 10, 0).unwrap()));
+        assert!(happy_hours.is_active(Utc.with_ymd_and_hms(2023, 6, 1, 8, 10, 0).unwrap()));
+        assert!(!happy_hours.is_active(Utc.with_ymd_and_hms(2023, 6, 1, 12, 0, 0).unwrap()));
+        assert!(
+            !HappyHours::default().is_active(Utc.with_ymd_and_hms(2023, 6, 1, 8, 10, 0).unwrap())
+        );
+    }
+}
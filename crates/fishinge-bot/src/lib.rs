@@ -1,20 +1,92 @@
 #![forbid(unsafe_code)]
 
-use std::{fmt::Display, ops::Range, sync::RwLock};
+use std::{
+    collections::HashSet,
+    fmt::Display,
+    ops::{Deref, Range},
+    str::FromStr,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock},
+    time::{Duration as StdDuration, Instant},
+};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Datelike, FixedOffset, Offset, TimeZone, Utc};
-use database::entities::{accounts, bundle, prelude::*, seasons};
+use database::{
+    definition::FishDefinition,
+    entities::{
+        accounts, bundle, catches, fish_bundle, fishes, inventory, messages, prelude::*,
+        season_data, seasons, users,
+    },
+};
 use eyre::{eyre, Result, WrapErr};
-use log::{debug, info};
-use rand::Rng;
+use log::{debug, info, warn};
+use once_cell::sync::Lazy;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::{Distribution, Normal, Triangular};
+use regex::Regex;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult,
-    ModelTrait, QueryFilter, QueryOrder, QuerySelect,
+    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection,
+    EntityTrait, FromQueryResult, IntoActiveModel, ModelTrait, QueryFilter, QueryOrder,
+    QuerySelect, TransactionTrait,
 };
 use twitch_irc::login::{TokenStorage, UserAccessToken};
 
-pub static FISH_POPULATION: RwLock<i32> = RwLock::new(0);
+pub use database::tier::RARE_CHANCE;
+
+/// Word that triggers commands if no other trigger is configured
+pub const DEFAULT_TRIGGER: &str = "Fishinge";
+
+/// Build the regex matching `[emote] <trigger> [args]` chat commands.
+///
+/// The trigger is matched literally, so it can be any word.
+pub fn command_regex(trigger: &str) -> Regex {
+    Regex::new(&format!(
+        r"^((?P<emote>\S+)\s+)?{}( (?P<args>.*))?$",
+        regex::escape(trigger)
+    ))
+    .expect("an escaped trigger is a valid regex")
+}
+
+#[cfg(test)]
+mod command_regex_tests {
+    use test_case::test_case;
+
+    use crate::{command_regex, DEFAULT_TRIGGER};
+
+    #[test_case("Fishinge", None, None ; "fish")]
+    #[test_case("🔍 Fishinge", Some("🔍"), None ; "emote")]
+    #[test_case("🎁 Fishinge @alice 5", Some("🎁"), Some("@alice 5") ; "emote with args")]
+    fn default_trigger(text: &str, emote: Option<&str>, args: Option<&str>) {
+        let captures = command_regex(DEFAULT_TRIGGER).captures(text).unwrap();
+
+        assert_eq!(captures.name("emote").map(|m| m.as_str()), emote);
+        assert_eq!(captures.name("args").map(|m| m.as_str()), args);
+    }
+
+    #[test_case("Fishingee" ; "longer word")]
+    #[test_case("hello there" ; "other message")]
+    fn default_trigger_mismatch(text: &str) {
+        assert!(!command_regex(DEFAULT_TRIGGER).is_match(text));
+    }
+
+    #[test]
+    fn custom_trigger() {
+        let regex = command_regex("Ribbit");
+        let captures = regex.captures("⚖️ Ribbit 🐟").unwrap();
+
+        assert_eq!(captures.name("emote").unwrap().as_str(), "⚖️");
+        assert_eq!(captures.name("args").unwrap().as_str(), "🐟");
+        assert!(!regex.is_match("Fishinge"));
+    }
+
+    #[test]
+    fn trigger_is_escaped() {
+        let regex = command_regex("F1sh.+");
+
+        assert!(regex.is_match("F1sh.+"));
+        assert!(!regex.is_match("F1shing"));
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct Fish {
@@ -23,285 +95,2059 @@ pub struct Fish {
     pub count: u32,
     pub base_value: i32,
     pub weight_range: Option<Range<f32>>,
+    pub weight_distribution: WeightDistribution,
+    /// Summed count of all fishes in the bundle this fish was loaded from
+    pub population: u32,
+}
+
+/// How the weight of a caught fish is distributed over its weight range
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WeightDistribution {
+    /// Every weight is equally likely
+    #[default]
+    Uniform,
+    /// Light catches are most likely, heavier ones get linearly rarer
+    Triangular,
+    /// Weights cluster around `mean`
+    Normal { mean: f32, stddev: f32 },
+}
+
+impl WeightDistribution {
+    /// Draw a weight from `range`. Weights outside the range are clamped.
+    pub fn sample<R: Rng + ?Sized>(&self, range: &Range<f32>, rng: &mut R) -> f32 {
+        let weight = match *self {
+            Self::Uniform => return rng.gen_range(range.clone()),
+            Self::Triangular => match Triangular::new(range.start, range.end, range.start) {
+                Ok(distribution) => distribution.sample(rng),
+                Err(_) => rng.gen_range(range.clone()),
+            },
+            Self::Normal { mean, stddev } => match Normal::new(mean, stddev) {
+                Ok(distribution) => distribution.sample(rng),
+                Err(_) => rng.gen_range(range.clone()),
+            },
+        };
+
+        weight.clamp(range.start, range.end)
+    }
+
+    fn from_model(fish: &database::entities::fishes::Model) -> Option<Self> {
+        match fish.weight_distribution.as_str() {
+            "uniform" => Some(Self::Uniform),
+            "triangular" => Some(Self::Triangular),
+            "normal" => match (fish.weight_mean, fish.weight_stddev) {
+                (Some(mean), Some(stddev))
+                    if mean.is_finite() && stddev.is_finite() && stddev > 0.0 =>
+                {
+                    Some(Self::Normal { mean, stddev })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 }
 
 impl Fish {
+    /// Display this fish with its weight range in `unit`
+    pub fn in_unit(&self, unit: WeightUnit) -> InUnit<'_, Self> {
+        InUnit { value: self, unit }
+    }
+
     pub fn catch(&self) -> Catch {
-        let mut rng = rand::thread_rng();
+        self.catch_with(&mut rand::thread_rng())
+    }
 
+    /// Catch this fish with a weight drawn from `rng`
+    pub fn catch_with<R: Rng + ?Sized>(&self, rng: &mut R) -> Catch {
         let weight = self
             .weight_range
-            .clone()
-            .map(|weight| rng.gen_range(weight));
+            .as_ref()
+            .map(|range| self.weight_distribution.sample(range, rng));
 
         Catch::new(self, weight)
     }
-}
 
-impl From<database::entities::fishes::Model> for Fish {
-    fn from(fish: database::entities::fishes::Model) -> Self {
-        Self {
-            id: fish.id,
-            name: fish.name,
-            count: fish.count as u32,
-            base_value: fish.base_value as i32,
-            weight_range: if fish.min_weight > f32::EPSILON && fish.max_weight > f32::EPSILON {
-                Some(fish.min_weight..fish.max_weight)
-            } else {
-                None
-            },
+    /// Chance of catching this fish compared to the rest of its bundle
+    pub fn chance(&self) -> f32 {
+        self.chance_in(self.population)
+    }
+
+    /// Chance of catching this fish out of `population` fishes. An empty
+    /// population has no chance of catching anything.
+    pub fn chance_in(&self, population: u32) -> f32 {
+        if population == 0 {
+            return 0.0;
         }
+
+        self.count as f32 / population as f32
     }
-}
 
-impl Display for Fish {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} ({:.1}%)",
-            self.name,
-            self.count as f32 / *FISH_POPULATION.read().unwrap() as f32 * 100.0
-        )?;
+    pub fn is_rare(&self) -> bool {
+        self.chance() < RARE_CHANCE
+    }
 
-        if let Some(weight) = &self.weight_range {
-            write!(f, " ({:.1}kg - {:.1}kg)", weight.start, weight.end)?;
+    /// Convert a database model, rejecting weight ranges that would panic or
+    /// produce garbage values when catching the fish.
+    ///
+    /// The population is left at zero and has to be filled in by the caller.
+    pub fn try_from_model(fish: database::entities::fishes::Model) -> Result<Self, FishError> {
+        let (min, max) = (fish.min_weight, fish.max_weight);
+
+        if !min.is_finite() || !max.is_finite() {
+            return Err(FishError::NonFiniteWeightRange {
+                name: fish.name,
+                min,
+                max,
+            });
         }
 
-        Ok(())
-    }
-}
+        if min > max {
+            return Err(FishError::InvertedWeightRange {
+                name: fish.name,
+                min,
+                max,
+            });
+        }
 
-pub async fn get_active_season(db: &DatabaseConnection) -> Result<seasons::Model> {
-    let season = Seasons::find()
-        .filter(seasons::Column::Start.lt(chrono::Utc::now()))
-        .filter(
-            seasons::Column::End
-                .gt(chrono::Utc::now())
-                .or(seasons::Column::End.is_null()),
-        )
-        .order_by_desc(seasons::Column::Start)
-        .one(db)
-        .await
-        .wrap_err("Could not fetch seasons")?;
+        let Some(weight_distribution) = WeightDistribution::from_model(&fish) else {
+            return Err(FishError::InvalidWeightDistribution {
+                name: fish.name,
+                distribution: fish.weight_distribution,
+            });
+        };
 
-    if let Some(season) = season {
-        Ok(season)
-    } else {
-        Err(eyre!("No active season found"))
+        let weight_range = if min > f32::EPSILON && max > f32::EPSILON {
+            if max - min <= f32::EPSILON {
+                return Err(FishError::EmptyWeightRange {
+                    name: fish.name,
+                    weight: min,
+                });
+            }
+
+            Some(min..max)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            id: fish.id,
+            name: fish.name,
+            count: fish.count as u32,
+            base_value: fish.base_value as i32,
+            weight_range,
+            weight_distribution,
+            population: 0,
+        })
     }
 }
 
-pub async fn has_next_season(db: &DatabaseConnection) -> Result<bool> {
-    let season = Seasons::find()
-        .filter(seasons::Column::Start.gt(chrono::Utc::now()))
-        .one(db)
-        .await
-        .wrap_err("Could not fetch seasons")?;
+#[derive(Debug, thiserror::Error)]
+pub enum FishError {
+    #[error("Fish {name} has a weight range that is not finite ({min} - {max})")]
+    NonFiniteWeightRange { name: String, min: f32, max: f32 },
 
-    Ok(season.is_some())
-}
+    #[error("Fish {name} has an inverted weight range ({min} - {max})")]
+    InvertedWeightRange { name: String, min: f32, max: f32 },
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct YearAndQuarter {
-    year: i32,
-    quarter: Quarter,
+    #[error("Fish {name} has an empty weight range ({weight})")]
+    EmptyWeightRange { name: String, weight: f32 },
+
+    #[error("Fish {name} has an invalid weight distribution {distribution:?}")]
+    InvalidWeightDistribution { name: String, distribution: String },
 }
 
-impl YearAndQuarter {
-    pub fn from_start(start: DateTime<FixedOffset>) -> Self {
-        let year = start.year();
-        let (year, quarter) = match start.month() {
-            12 => (year, Quarter::Winter),
-            1 | 2 => (year - 1, Quarter::Winter),
-            3 | 4 | 5 => (year, Quarter::Spring),
-            6 | 7 | 8 => (year, Quarter::Summer),
-            9 | 10 | 11 => (year, Quarter::Autumn),
-            _ => unreachable!(),
-        };
+#[cfg(test)]
+mod fish_model_tests {
+    use database::entities::fishes;
 
-        Self { year, quarter }
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use test_case::test_case;
+
+    use crate::{Fish, FishError, WeightDistribution};
+
+    fn model(min_weight: f32, max_weight: f32) -> fishes::Model {
+        fishes::Model {
+            id: 1,
+            name: "fish".to_string(),
+            html_name: "fish".to_string(),
+            count: 10,
+            base_value: 5.0,
+            max_weight,
+            min_weight,
+            is_trash: false,
+            weight_distribution: "uniform".to_string(),
+            weight_mean: None,
+            weight_stddev: None,
+        }
     }
 
-    pub fn start(&self) -> DateTime<FixedOffset> {
-        let month = match self.quarter {
-            Quarter::Winter => 1,
-            Quarter::Spring => 4,
-            Quarter::Summer => 7,
-            Quarter::Autumn => 10,
-        };
+    #[test_case("uniform", None, None, WeightDistribution::Uniform ; "uniform")]
+    #[test_case("triangular", None, None, WeightDistribution::Triangular ; "triangular")]
+    #[test_case("normal", Some(2.0), Some(0.5), WeightDistribution::Normal { mean: 2.0, stddev: 0.5 } ; "normal")]
+    fn weight_distribution(
+        distribution: &str,
+        mean: Option<f32>,
+        stddev: Option<f32>,
+        expected: WeightDistribution,
+    ) {
+        let fish = Fish::try_from_model(fishes::Model {
+            weight_distribution: distribution.to_string(),
+            weight_mean: mean,
+            weight_stddev: stddev,
+            ..model(1.5, 3.0)
+        })
+        .unwrap();
 
-        Utc.with_ymd_and_hms(self.year, month, 1, 12, 0, 0)
-            .unwrap()
-            .with_timezone(&Utc.fix())
+        assert_eq!(fish.weight_distribution, expected);
     }
 
-    pub fn next(&self) -> Self {
-        let (year, quarter) = match self.quarter {
-            Quarter::Winter => (self.year + 1, Quarter::Spring),
-            Quarter::Spring => (self.year, Quarter::Summer),
-            Quarter::Summer => (self.year, Quarter::Autumn),
-            Quarter::Autumn => (self.year, Quarter::Winter),
-        };
+    #[test_case("normal", None, Some(0.5) ; "normal without mean")]
+    #[test_case("normal", Some(2.0), Some(0.0) ; "normal without spread")]
+    #[test_case("normal", Some(2.0), Some(f32::NAN) ; "normal with nan spread")]
+    #[test_case("exponential", None, None ; "unknown")]
+    fn invalid_weight_distribution(distribution: &str, mean: Option<f32>, stddev: Option<f32>) {
+        assert!(matches!(
+            Fish::try_from_model(fishes::Model {
+                weight_distribution: distribution.to_string(),
+                weight_mean: mean,
+                weight_stddev: stddev,
+                ..model(1.5, 3.0)
+            }),
+            Err(FishError::InvalidWeightDistribution { .. })
+        ));
+    }
 
-        Self { year, quarter }
+    #[test_case(WeightDistribution::Uniform ; "uniform")]
+    #[test_case(WeightDistribution::Triangular ; "triangular")]
+    #[test_case(WeightDistribution::Normal { mean: 2.0, stddev: 0.5 } ; "normal")]
+    #[test_case(WeightDistribution::Normal { mean: 10.0, stddev: 5.0 } ; "normal outside range")]
+    fn samples_stay_in_range(distribution: WeightDistribution) {
+        let mut rng = StdRng::seed_from_u64(0);
+        let range = 1.5..3.0;
+
+        for _ in 0..10_000 {
+            let weight = distribution.sample(&range, &mut rng);
+
+            assert!((range.start..=range.end).contains(&weight), "{weight}");
+        }
     }
 
-    pub fn end(&self) -> DateTime<FixedOffset> {
-        self.next().start()
+    #[test]
+    fn uniform_is_gen_range() {
+        let range = 1.5..3.0;
+        let mut sampled = StdRng::seed_from_u64(0);
+        let mut drawn = StdRng::seed_from_u64(0);
+
+        for _ in 0..1_000 {
+            assert_eq!(
+                WeightDistribution::Uniform
+                    .sample(&range, &mut sampled)
+                    .to_bits(),
+                drawn.gen_range(range.clone()).to_bits()
+            );
+        }
     }
-}
 
-impl Display for YearAndQuarter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{} {}", self.quarter, self.year)
+    #[test]
+    fn triangular_favors_light_catches() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let range = 0.0..1.0;
+        let mean = (0..10_000)
+            .map(|_| WeightDistribution::Triangular.sample(&range, &mut rng))
+            .sum::<f32>()
+            / 10_000.0;
+
+        // the mean of a triangle with its peak at the minimum is a third
+        assert!((mean - 1.0 / 3.0).abs() < 0.02, "{mean}");
     }
-}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum Quarter {
-    Winter,
-    Spring,
-    Summer,
-    Autumn,
-}
+    #[test]
+    fn valid_range() {
+        let fish = Fish::try_from_model(model(1.5, 3.0)).unwrap();
 
-impl Display for Quarter {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let name = match self {
-            Quarter::Winter => "Winter",
-            Quarter::Spring => "Spring",
-            Quarter::Summer => "Summer",
-            Quarter::Autumn => "Autumn",
-        };
-        write!(f, "{name}")
+        assert_eq!(fish.weight_range, Some(1.5..3.0));
     }
-}
 
-#[cfg(test)]
-mod year_and_quarter_tests {
-    use chrono::{DateTime, Offset, Utc};
+    #[test]
+    fn inverted_range() {
+        assert!(matches!(
+            Fish::try_from_model(model(3.0, 1.5)),
+            Err(FishError::InvertedWeightRange { .. })
+        ));
+    }
 
-    use crate::{Quarter, YearAndQuarter};
+    #[test]
+    fn nan_range() {
+        assert!(matches!(
+            Fish::try_from_model(model(f32::NAN, 1.5)),
+            Err(FishError::NonFiniteWeightRange { .. })
+        ));
+        assert!(matches!(
+            Fish::try_from_model(model(1.5, f32::NAN)),
+            Err(FishError::NonFiniteWeightRange { .. })
+        ));
+    }
 
     #[test]
-    fn test_from_start() {
-        let date = DateTime::parse_from_rfc3339("2020-01-01T12:00:00Z")
-            .unwrap()
-            .with_timezone(&Utc.fix());
+    fn zero_range() {
+        let fish = Fish::try_from_model(model(0.0, 0.0)).unwrap();
 
-        let year_and_quarter = YearAndQuarter::from_start(date);
+        assert_eq!(fish.weight_range, None);
+    }
 
-        assert_eq!(year_and_quarter.year, 2019);
-        assert_eq!(year_and_quarter.quarter, Quarter::Winter);
+    #[test]
+    fn empty_range() {
+        assert!(matches!(
+            Fish::try_from_model(model(2.0, 2.0)),
+            Err(FishError::EmptyWeightRange { .. })
+        ));
     }
 }
 
-async fn create_season(
-    db: &DatabaseConnection,
-    name: String,
-    start: DateTime<FixedOffset>,
-    end: DateTime<FixedOffset>,
-    bundle: bundle::Model,
-) -> Result<()> {
-    info!(
-        "Creating season {name} ({start:?} - {end:?}) Bundle {}",
-        bundle.id
-    );
+/// Inventory item that biases casts towards rare fishes
+pub const BAIT: &str = "bait";
+/// Bias towards rare fishes of casts with bait
+pub const BAIT_BIAS: f64 = 0.5;
 
-    Seasons::insert(seasons::ActiveModel {
-        name: ActiveValue::set(name),
-        start: ActiveValue::set(start),
-        end: ActiveValue::set(Some(end)),
-        bundle_id: ActiveValue::set(bundle.id),
-        ..Default::default()
-    })
-    .exec(db)
-    .await?;
+/// Weight of `fish` when casting with a `bias` between 0 and 1 towards rare
+/// fishes. Without bias fishes are weighted by their count, a bias of 1 makes
+/// all fishes with a count equally likely.
+pub fn biased_weight(fish: &Fish, bias: f64) -> f64 {
+    f64::from(fish.count).powf(1.0 - bias)
+}
 
-    Ok(())
+/// Choose a fish weighted by its count.
+///
+/// The counts are summed up as `f64`, so large counts can not overflow. Fishes
+/// with a count of zero are never chosen. Returns `None` if no fish has a
+/// count above zero.
+pub fn choose_fish<'a, R: Rng + ?Sized>(fishes: &'a [Fish], rng: &mut R) -> Option<&'a Fish> {
+    choose_fish_biased(fishes, rng, 0.0)
 }
 
-pub async fn create_next_season(db: &DatabaseConnection) -> Result<()> {
-    let Some(latest_season) = Seasons::find()
-        .order_by_desc(seasons::Column::Start)
-        .one(db)
-        .await? else {
-        return Err(eyre!("No season found"))
-    };
-    let Some(last_used_bundle) = latest_season.find_related(Bundle).one(db).await? else {
-        return Err(eyre!("No bundle found for season {}", latest_season.name))
-    };
+/// Like [`choose_fish`], but weighted by [`biased_weight`].
+pub fn choose_fish_biased<'a, R: Rng + ?Sized>(
+    fishes: &'a [Fish],
+    rng: &mut R,
+    bias: f64,
+) -> Option<&'a Fish> {
+    let total: f64 = fishes
+        .iter()
+        .filter(|fish| fish.count > 0)
+        .map(|fish| biased_weight(fish, bias))
+        .sum();
 
-    debug!("Latest season: {:?}", latest_season.name);
+    if total <= 0.0 {
+        return None;
+    }
 
-    // handle legacy season
-    let start = if latest_season.end.is_none() {
-        Utc::now().with_timezone(&Utc.fix())
-    } else {
-        latest_season.start
-    };
+    let mut target = rng.gen_range(0.0..total);
 
-    let quarter = YearAndQuarter::from_start(start).next();
+    for fish in fishes.iter().filter(|fish| fish.count > 0) {
+        let weight = biased_weight(fish, bias);
 
-    create_season(
-        db,
-        quarter.to_string(),
-        quarter.start(),
-        quarter.end(),
-        last_used_bundle,
-    )
-    .await?;
+        if target < weight {
+            return Some(fish);
+        }
 
-    Ok(())
+        target -= weight;
+    }
+
+    // rounding errors can leave a tiny remainder after the last fish
+    fishes.iter().rev().find(|fish| fish.count > 0)
 }
 
-pub async fn get_fishes(db: &DatabaseConnection, season: &seasons::Model) -> Result<Vec<Fish>> {
-    let Some(bundle) = season.find_related(Bundle).one(db).await? else {
-        return Err(eyre!("No bundle found for season {}", season.name))
-    };
+/// Copy of `fishes` where every fish with a chance below `threshold` has its
+/// count multiplied by `factor`.
+///
+/// The population is left as it is, so the boosted fishes are only meant for
+/// choosing a fish and not for telling how rare it is.
+pub fn boost_rare_fishes(fishes: &[Fish], threshold: f32, factor: f64) -> Vec<Fish> {
+    fishes
+        .iter()
+        .map(|fish| {
+            let mut fish = fish.clone();
 
-    let fishes = bundle.find_related(Fishes).all(db).await?;
+            if fish.chance() < threshold {
+                fish.count = (f64::from(fish.count) * factor).round() as u32;
+            }
 
-    let population = fishes.iter().map(|fish| fish.count).sum();
+            fish
+        })
+        .collect()
+}
 
-    *FISH_POPULATION.write().unwrap() = population;
+/// Choose a fish weighted by its count, biased towards rare fishes by `bias`.
+///
+/// If `avoid_repeat_rare` is set and the chosen fish is a rare fish with the id
+/// `previous_fish_id`, the choice is rolled once more.
+pub fn fish_once<'a, R: Rng + ?Sized>(
+    fishes: &'a [Fish],
+    rng: &mut R,
+    previous_fish_id: Option<i32>,
+    avoid_repeat_rare: bool,
+    bias: f64,
+) -> Result<&'a Fish> {
+    let fish =
+        choose_fish_biased(fishes, rng, bias).ok_or_else(|| eyre!("No fish can be caught"))?;
 
-    Ok(fishes.into_iter().map(Fish::from).collect())
-}
+    if avoid_repeat_rare && previous_fish_id == Some(fish.id) && fish.is_rare() {
+        debug!("Re-rolling repeated rare catch {}", fish.name);
 
-#[derive(Debug, Clone)]
-pub struct Catch {
-    pub fish_name: String,
-    pub weight: Option<f32>,
-    pub value: f32,
+        return choose_fish_biased(fishes, rng, bias).ok_or_else(|| eyre!("No fish can be caught"));
+    }
+
+    Ok(fish)
 }
 
-impl Catch {
-    pub fn new(fish: &Fish, weight: Option<f32>) -> Self {
-        let multiplier = fish
-            .weight_range
-            .as_ref()
-            .and_then(|range| {
-                weight.map(|weight| (weight - range.start) / (range.end - range.start))
-            })
-            .map_or(1.0, |x| (x * 1.36 - 0.48).powi(3) + 1.01 + x * 0.11);
+/// Cast once. With a chance of `double_catch_chance` a second fish is caught
+/// as well. A `bias` above zero favours rare fishes, see [`biased_weight`].
+pub fn cast<'a, R: Rng + ?Sized>(
+    fishes: &'a [Fish],
+    rng: &mut R,
+    previous_fish_id: Option<i32>,
+    avoid_repeat_rare: bool,
+    double_catch_chance: f32,
+    bias: f64,
+) -> Result<Vec<&'a Fish>> {
+    let mut caught = vec![fish_once(
+        fishes,
+        rng,
+        previous_fish_id,
+        avoid_repeat_rare,
+        bias,
+    )?];
 
-        Self {
-            fish_name: fish.name.clone(),
-            weight,
-            value: fish.base_value as f32 * multiplier,
-        }
+    if double_catch_chance > 0.0 && rng.gen::<f32>() < double_catch_chance {
+        debug!("Double catch");
+
+        caught.push(fish_once(
+            fishes,
+            rng,
+            previous_fish_id,
+            avoid_repeat_rare,
+            bias,
+        )?);
     }
+
+    Ok(caught)
 }
 
-impl Display for Catch {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.fish_name)?;
-        if let Some(weight) = self.weight {
-            write!(f, " ({:.1}kg)", weight)?;
-        }
-        if self.value.abs() > f32::EPSILON {
-            write!(f, " worth ${:.2}", self.value)?;
+#[cfg(test)]
+mod fish_once_tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use approx::assert_relative_eq;
+
+    use crate::{
+        biased_weight, boost_rare_fishes, cast, choose_fish, choose_fish_biased, fish_once, Fish,
+        WeightDistribution, BAIT_BIAS, RARE_CHANCE,
+    };
+
+    fn fishes() -> Vec<Fish> {
+        vec![
+            Fish {
+                id: 1,
+                name: "common".to_string(),
+                count: 99,
+                base_value: 1,
+                weight_range: None,
+                weight_distribution: WeightDistribution::Uniform,
+                population: 100,
+            },
+            Fish {
+                id: 2,
+                name: "rare".to_string(),
+                count: 1,
+                base_value: 100,
+                weight_range: None,
+                weight_distribution: WeightDistribution::Uniform,
+                population: 100,
+            },
+        ]
+    }
+
+    /// Find a seed whose first draw is the fish with `id`.
+    fn seed_drawing(fishes: &[Fish], id: i32) -> u64 {
+        (0..)
+            .find(|&seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                fish_once(fishes, &mut rng, None, false, 0.0).unwrap().id == id
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn rerolls_repeated_rare_catch() {
+        let fishes = fishes();
+        let seed = seed_drawing(&fishes, 2);
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        fish_once(&fishes, &mut rng, None, false, 0.0).unwrap();
+        let second_draw = fish_once(&fishes, &mut rng, None, false, 0.0).unwrap().id;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let fish = fish_once(&fishes, &mut rng, Some(2), true, 0.0).unwrap();
+
+        assert_eq!(fish.id, second_draw);
+    }
+
+    #[test]
+    fn keeps_repeated_rare_catch_when_disabled() {
+        let fishes = fishes();
+        let seed = seed_drawing(&fishes, 2);
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let fish = fish_once(&fishes, &mut rng, Some(2), false, 0.0).unwrap();
+
+        assert_eq!(fish.id, 2);
+    }
+
+    #[test]
+    fn keeps_repeated_common_catch() {
+        let fishes = fishes();
+        let seed = seed_drawing(&fishes, 1);
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let fish = fish_once(&fishes, &mut rng, Some(1), true, 0.0).unwrap();
+
+        assert_eq!(fish.id, 1);
+    }
+
+    #[test]
+    fn choose_from_all_zero_counts() {
+        let mut fishes = fishes();
+        for fish in &mut fishes {
+            fish.count = 0;
+        }
+
+        let mut rng = StdRng::seed_from_u64(0);
+
+        assert!(choose_fish(&fishes, &mut rng).is_none());
+        assert!(choose_fish(&[], &mut rng).is_none());
+    }
+
+    #[test]
+    fn choose_single_fish() {
+        let fishes = &fishes()[..1];
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            assert_eq!(choose_fish(fishes, &mut rng).unwrap().id, 1);
+        }
+    }
+
+    #[test]
+    fn never_choose_zero_count() {
+        let mut fishes = fishes();
+        fishes[0].count = 0;
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            assert_eq!(choose_fish(&fishes, &mut rng).unwrap().id, 2);
+        }
+    }
+
+    #[test]
+    fn no_bias_is_choose_fish() {
+        let fishes = fishes();
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let unbiased = choose_fish(&fishes, &mut rng).unwrap().id;
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let biased = choose_fish_biased(&fishes, &mut rng, 0.0).unwrap().id;
+
+            assert_eq!(unbiased, biased);
+        }
+    }
+
+    #[test]
+    fn bait_favours_rare_fishes() {
+        let fishes = fishes();
+
+        assert_relative_eq!(biased_weight(&fishes[0], 0.0), 99.0);
+        assert_relative_eq!(biased_weight(&fishes[1], 0.0), 1.0);
+        assert_relative_eq!(biased_weight(&fishes[0], BAIT_BIAS), 99f64.sqrt());
+        assert_relative_eq!(biased_weight(&fishes[1], BAIT_BIAS), 1.0);
+
+        let rare_catches = |bias| {
+            let mut rng = StdRng::seed_from_u64(0);
+            (0..10_000)
+                .filter(|_| choose_fish_biased(&fishes, &mut rng, bias).unwrap().id == 2)
+                .count()
+        };
+
+        assert!(rare_catches(BAIT_BIAS) > rare_catches(0.0) * 5);
+    }
+
+    #[test]
+    fn boost_only_rare_fishes() {
+        let boosted = boost_rare_fishes(&fishes(), RARE_CHANCE, 3.0);
+
+        assert_eq!(boosted[0].count, 99);
+        assert_eq!(boosted[1].count, 3);
+    }
+
+    #[test]
+    fn never_double_catches_without_chance() {
+        let fishes = fishes();
+
+        for seed in 0..100 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let caught = cast(&fishes, &mut rng, None, false, 0.0, 0.0).unwrap();
+
+            assert_eq!(caught.len(), 1);
+        }
+    }
+
+    #[test]
+    fn double_catch() {
+        let fishes = fishes();
+        let seed = (0..)
+            .find(|&seed| {
+                let mut rng = StdRng::seed_from_u64(seed);
+                cast(&fishes, &mut rng, None, false, 0.5, 0.0)
+                    .unwrap()
+                    .len()
+                    == 2
+            })
+            .unwrap();
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let first_draw = fish_once(&fishes, &mut rng, None, false, 0.0).unwrap().id;
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let caught = cast(&fishes, &mut rng, None, false, 0.5, 0.0).unwrap();
+
+        assert_eq!(caught.len(), 2);
+        assert_eq!(caught[0].id, first_draw);
+    }
+}
+
+/// Aggregated catches of a single fish in a [`Simulation`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedFish {
+    pub id: i32,
+    pub name: String,
+    pub catches: u32,
+    pub total_value: f64,
+}
+
+impl SimulatedFish {
+    pub fn average_value(&self) -> f64 {
+        if self.catches == 0 {
+            return 0.0;
+        }
+
+        self.total_value / f64::from(self.catches)
+    }
+}
+
+/// Result of [`simulate_catches`], with one entry per fish in the order they
+/// were passed in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+    pub casts: u32,
+    pub fishes: Vec<SimulatedFish>,
+}
+
+impl Simulation {
+    pub fn total_value(&self) -> f64 {
+        self.fishes.iter().map(|fish| fish.total_value).sum()
+    }
+
+    pub fn average_value(&self) -> f64 {
+        if self.casts == 0 {
+            return 0.0;
+        }
+
+        self.total_value() / f64::from(self.casts)
+    }
+}
+
+/// Catch `casts` fishes and aggregate them per fish.
+///
+/// With a `seed` the simulation is reproducible, otherwise the rng is seeded
+/// from entropy.
+pub fn simulate_catches(fishes: &[Fish], casts: u32, seed: Option<u64>) -> Simulation {
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut simulation = Simulation {
+        casts: 0,
+        fishes: fishes
+            .iter()
+            .map(|fish| SimulatedFish {
+                id: fish.id,
+                name: fish.name.clone(),
+                catches: 0,
+                total_value: 0.0,
+            })
+            .collect(),
+    };
+
+    for _ in 0..casts {
+        let Some(fish) = choose_fish(fishes, &mut rng) else {
+            break;
+        };
+
+        // fishes and simulation.fishes share their order
+        let index = fishes
+            .iter()
+            .position(|other| std::ptr::eq(other, fish))
+            .expect("fish is part of fishes");
+        let catch = fish.catch_with(&mut rng);
+
+        let entry = &mut simulation.fishes[index];
+        entry.catches += 1;
+        entry.total_value += f64::from(catch.value);
+        simulation.casts += 1;
+    }
+
+    simulation
+}
+
+#[cfg(test)]
+mod simulation_tests {
+    use crate::{simulate_catches, Fish, WeightDistribution};
+
+    fn fishes() -> Vec<Fish> {
+        vec![
+            Fish {
+                id: 1,
+                name: "common".to_string(),
+                count: 90,
+                base_value: 10,
+                weight_range: Some(1.0..2.0),
+                weight_distribution: WeightDistribution::Uniform,
+                population: 100,
+            },
+            Fish {
+                id: 2,
+                name: "rare".to_string(),
+                count: 10,
+                base_value: 100,
+                weight_range: None,
+                weight_distribution: WeightDistribution::Uniform,
+                population: 100,
+            },
+        ]
+    }
+
+    #[test]
+    fn same_seed_same_result() {
+        let fishes = fishes();
+
+        assert_eq!(
+            simulate_catches(&fishes, 1_000, Some(42)),
+            simulate_catches(&fishes, 1_000, Some(42))
+        );
+    }
+
+    #[test]
+    fn aggregates_every_cast() {
+        let simulation = simulate_catches(&fishes(), 1_000, Some(7));
+        let catches: u32 = simulation.fishes.iter().map(|fish| fish.catches).sum();
+
+        assert_eq!(simulation.casts, 1_000);
+        assert_eq!(catches, 1_000);
+        assert_eq!(simulation.fishes[0].name, "common");
+        assert_eq!(
+            simulation.fishes[1].total_value,
+            f64::from(simulation.fishes[1].catches) * 100.0
+        );
+    }
+
+    #[test]
+    fn nothing_to_catch() {
+        let mut fishes = fishes();
+        fishes.iter_mut().for_each(|fish| fish.count = 0);
+
+        let simulation = simulate_catches(&fishes, 10, Some(1));
+
+        assert_eq!(simulation.casts, 0);
+        assert_eq!(simulation.average_value(), 0.0);
+    }
+}
+
+impl Display for Fish {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.in_unit(WeightUnit::default()).fmt(f)
+    }
+}
+
+impl Display for InUnit<'_, Fish> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fish = self.value;
+        write!(f, "{} ({:.1}%)", fish.name, fish.chance() * 100.0)?;
+
+        if let Some(weight) = &fish.weight_range {
+            write!(
+                f,
+                " ({} - {})",
+                format_weight(weight.start, self.unit),
+                format_weight(weight.end, self.unit)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Unit weights are shown in. Weights are always stored in kilograms.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WeightUnit {
+    #[default]
+    Kilograms,
+    Pounds,
+}
+
+impl FromStr for WeightUnit {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "kg" | "kilograms" => Ok(Self::Kilograms),
+            "lb" | "lbs" | "pounds" => Ok(Self::Pounds),
+            _ => Err(eyre!("Unknown weight unit {s:?}")),
+        }
+    }
+}
+
+/// Pounds in one kilogram
+pub const POUNDS_PER_KILOGRAM: f64 = 2.204_622_621_8;
+
+/// Format a `value` in kilograms with one decimal in `unit`, like `12.3kg` or
+/// `27.1lb`.
+pub fn format_weight(value: f32, unit: WeightUnit) -> String {
+    match unit {
+        WeightUnit::Kilograms => format!("{value:.1}kg"),
+        WeightUnit::Pounds => format!("{:.1}lb", f64::from(value) * POUNDS_PER_KILOGRAM),
+    }
+}
+
+/// Displays a [`Fish`] or [`Catch`] with its weight in `unit`
+#[derive(Debug, Clone, Copy)]
+pub struct InUnit<'a, T> {
+    value: &'a T,
+    unit: WeightUnit,
+}
+
+/// Format a number with `,` as thousands separator.
+pub fn format_thousands(value: u64) -> String {
+    let digits = value.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+
+    formatted
+}
+
+/// Format an amount of money with two decimals and `,` as thousands
+/// separator, like `$1,234.56` or `-$1,234.56`.
+pub fn format_money(value: f32) -> String {
+    let cents = (f64::from(value).abs() * 100.0).round() as u64;
+    let sign = if value < 0.0 && cents > 0 { "-" } else { "" };
+
+    format!(
+        "{sign}${}.{:02}",
+        format_thousands(cents / 100),
+        cents % 100
+    )
+}
+
+#[cfg(test)]
+mod format_tests {
+    use test_case::test_case;
+
+    use crate::{format_money, format_thousands, format_weight, WeightUnit};
+
+    #[test_case(0, "0" ; "zero")]
+    #[test_case(999, "999" ; "below one thousand")]
+    #[test_case(1230, "1,230" ; "one thousand")]
+    #[test_case(123456, "123,456" ; "six digits")]
+    #[test_case(1234567, "1,234,567" ; "seven digits")]
+    fn thousands(value: u64, expected: &str) {
+        assert_eq!(format_thousands(value), expected);
+    }
+
+    #[test_case(0.0, "$0.00" ; "zero")]
+    #[test_case(-0.0, "$0.00" ; "negative zero")]
+    #[test_case(-0.001, "$0.00" ; "negative rounding to zero")]
+    #[test_case(0.5, "$0.50" ; "cents")]
+    #[test_case(1234.56, "$1,234.56" ; "thousands")]
+    #[test_case(-1234.56, "-$1,234.56" ; "negative thousands")]
+    #[test_case(-50.0, "-$50.00" ; "negative")]
+    #[test_case(999.999, "$1,000.00" ; "rounding up to thousands")]
+    #[test_case(1_234_567.0, "$1,234,567.00" ; "large")]
+    fn money(value: f32, expected: &str) {
+        assert_eq!(format_money(value), expected);
+    }
+
+    #[test_case(0.0, WeightUnit::Kilograms, "0.0kg" ; "zero kilograms")]
+    #[test_case(0.0, WeightUnit::Pounds, "0.0lb" ; "zero pounds")]
+    #[test_case(1.23, WeightUnit::Kilograms, "1.2kg" ; "kilograms")]
+    #[test_case(1.0, WeightUnit::Pounds, "2.2lb" ; "one kilogram in pounds")]
+    #[test_case(12.5, WeightUnit::Pounds, "27.6lb" ; "pounds")]
+    #[test_case(150_000.0, WeightUnit::Kilograms, "150000.0kg" ; "whale in kilograms")]
+    #[test_case(150_000.0, WeightUnit::Pounds, "330693.4lb" ; "whale in pounds")]
+    fn weight(value: f32, unit: WeightUnit, expected: &str) {
+        assert_eq!(format_weight(value, unit), expected);
+    }
+
+    #[test_case("kg", WeightUnit::Kilograms ; "kg")]
+    #[test_case(" LB ", WeightUnit::Pounds ; "lb")]
+    #[test_case("pounds", WeightUnit::Pounds ; "pounds")]
+    fn weight_unit(unit: &str, expected: WeightUnit) {
+        assert_eq!(unit.parse::<WeightUnit>().unwrap(), expected);
+    }
+
+    #[test]
+    fn unknown_weight_unit() {
+        assert!("stone".parse::<WeightUnit>().is_err());
+    }
+}
+
+pub async fn get_active_season(db: &DatabaseConnection) -> Result<seasons::Model> {
+    find_active_season(db)
+        .await?
+        .ok_or_else(|| eyre!("No active season found"))
+}
+
+/// The season running right now, if any
+pub async fn find_active_season(db: &DatabaseConnection) -> Result<Option<seasons::Model>> {
+    Seasons::find()
+        .filter(seasons::Column::Start.lt(chrono::Utc::now()))
+        .filter(
+            seasons::Column::End
+                .gt(chrono::Utc::now())
+                .or(seasons::Column::End.is_null()),
+        )
+        .order_by_desc(seasons::Column::Start)
+        .one(db)
+        .await
+        .wrap_err("Could not fetch seasons")
+}
+
+pub async fn has_next_season(db: &DatabaseConnection) -> Result<bool> {
+    let season = Seasons::find()
+        .filter(seasons::Column::Start.gt(chrono::Utc::now()))
+        .one(db)
+        .await
+        .wrap_err("Could not fetch seasons")?;
+
+    Ok(season.is_some())
+}
+
+/// Whether a season ending at `end` ends within `window` after `now`.
+pub fn is_near_season_end(
+    end: Option<DateTime<FixedOffset>>,
+    now: DateTime<FixedOffset>,
+    window: StdDuration,
+) -> bool {
+    end.filter(|end| *end > now)
+        .and_then(|end| (end - now).to_std().ok())
+        .map_or(false, |remaining| remaining <= window)
+}
+
+/// Whether a season needs a snapshot of its scores. Each season is only
+/// snapshotted once.
+pub fn needs_season_snapshot(
+    end: Option<DateTime<FixedOffset>>,
+    now: DateTime<FixedOffset>,
+    window: StdDuration,
+    has_snapshot: bool,
+) -> bool {
+    !has_snapshot && is_near_season_end(end, now, window)
+}
+
+pub async fn has_season_snapshot(db: &DatabaseConnection, season: &seasons::Model) -> Result<bool> {
+    let snapshot = SeasonData::find()
+        .filter(season_data::Column::SeasonId.eq(season.id))
+        .one(db)
+        .await
+        .wrap_err("Could not fetch season data")?;
+
+    Ok(snapshot.is_some())
+}
+
+/// Store the current score of every user in `season_data`.
+pub async fn snapshot_season_scores(
+    db: &DatabaseConnection,
+    season: &seasons::Model,
+) -> Result<()> {
+    #[derive(FromQueryResult)]
+    struct UserScore {
+        user_id: i32,
+        score: f32,
+    }
+
+    let scores = Catches::find()
+        .filter(catches::Column::SeasonId.eq(season.id))
+        .select_only()
+        .column(catches::Column::UserId)
+        .column_as(catches::Column::Value.sum(), "score")
+        .group_by(catches::Column::UserId)
+        .into_model::<UserScore>()
+        .all(db)
+        .await
+        .wrap_err("Could not fetch season scores")?;
+
+    info!(
+        "Snapshotting {} scores of season {}",
+        scores.len(),
+        season.name
+    );
+
+    if scores.is_empty() {
+        return Ok(());
+    }
+
+    SeasonData::insert_many(scores.into_iter().map(|score| season_data::ActiveModel {
+        season_id: ActiveValue::set(season.id),
+        user_id: ActiveValue::set(score.user_id),
+        score: ActiveValue::set(score.score),
+        ..Default::default()
+    }))
+    .exec(db)
+    .await
+    .wrap_err("Could not insert season data")?;
+
+    Ok(())
+}
+
+/// Snapshot the active season if it ends within `window` and was not
+/// snapshotted yet. Returns whether a snapshot was taken.
+pub async fn snapshot_ending_season(db: &DatabaseConnection, window: StdDuration) -> Result<bool> {
+    let season = get_active_season(db).await?;
+    let now = Utc::now().with_timezone(&Utc.fix());
+
+    let has_snapshot = has_season_snapshot(db, &season).await?;
+
+    if !needs_season_snapshot(season.end, now, window, has_snapshot) {
+        return Ok(false);
+    }
+
+    snapshot_season_scores(db, &season).await?;
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod season_snapshot_tests {
+    use std::time::Duration as StdDuration;
+
+    use chrono::{DateTime, Duration, FixedOffset};
+
+    use crate::{is_near_season_end, needs_season_snapshot};
+
+    const HOUR: StdDuration = StdDuration::from_secs(60 * 60);
+
+    fn now() -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2023-06-30T11:30:00Z").unwrap()
+    }
+
+    #[test]
+    fn near_end() {
+        assert!(is_near_season_end(
+            Some(now() + Duration::minutes(30)),
+            now(),
+            HOUR
+        ));
+    }
+
+    #[test]
+    fn far_from_end() {
+        assert!(!is_near_season_end(
+            Some(now() + Duration::hours(2)),
+            now(),
+            HOUR
+        ));
+    }
+
+    #[test]
+    fn already_ended() {
+        assert!(!is_near_season_end(
+            Some(now() - Duration::minutes(1)),
+            now(),
+            HOUR
+        ));
+    }
+
+    #[test]
+    fn open_ended() {
+        assert!(!is_near_season_end(None, now(), HOUR));
+    }
+
+    #[test]
+    fn snapshots_only_once() {
+        let end = Some(now() + Duration::minutes(30));
+
+        assert!(needs_season_snapshot(end, now(), HOUR, false));
+        assert!(!needs_season_snapshot(end, now(), HOUR, true));
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct YearAndQuarter {
+    year: i32,
+    quarter: Quarter,
+}
+
+impl YearAndQuarter {
+    pub fn from_start(start: DateTime<FixedOffset>) -> Self {
+        let year = start.year();
+        let (year, quarter) = match start.month() {
+            12 => (year, Quarter::Winter),
+            1 | 2 => (year - 1, Quarter::Winter),
+            3 | 4 | 5 => (year, Quarter::Spring),
+            6 | 7 | 8 => (year, Quarter::Summer),
+            9 | 10 | 11 => (year, Quarter::Autumn),
+            _ => unreachable!(),
+        };
+
+        Self { year, quarter }
+    }
+
+    pub fn start(&self) -> DateTime<FixedOffset> {
+        // the months `from_start` assigns to the quarter
+        let month = match self.quarter {
+            Quarter::Winter => 12,
+            Quarter::Spring => 3,
+            Quarter::Summer => 6,
+            Quarter::Autumn => 9,
+        };
+
+        Utc.with_ymd_and_hms(self.year, month, 1, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc.fix())
+    }
+
+    pub fn next(&self) -> Self {
+        let (year, quarter) = match self.quarter {
+            Quarter::Winter => (self.year + 1, Quarter::Spring),
+            Quarter::Spring => (self.year, Quarter::Summer),
+            Quarter::Summer => (self.year, Quarter::Autumn),
+            Quarter::Autumn => (self.year, Quarter::Winter),
+        };
+
+        Self { year, quarter }
+    }
+
+    pub fn end(&self) -> DateTime<FixedOffset> {
+        self.next().start()
+    }
+}
+
+impl Display for YearAndQuarter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.quarter, self.year)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Quarter {
+    Winter,
+    Spring,
+    Summer,
+    Autumn,
+}
+
+impl Display for Quarter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Quarter::Winter => "Winter",
+            Quarter::Spring => "Spring",
+            Quarter::Summer => "Summer",
+            Quarter::Autumn => "Autumn",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod year_and_quarter_tests {
+    use chrono::{DateTime, Offset, Utc};
+
+    use crate::{Quarter, YearAndQuarter};
+
+    #[test]
+    fn test_from_start() {
+        let date = DateTime::parse_from_rfc3339("2020-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc.fix());
+
+        let year_and_quarter = YearAndQuarter::from_start(date);
+
+        assert_eq!(year_and_quarter.year, 2019);
+        assert_eq!(year_and_quarter.quarter, Quarter::Winter);
+    }
+
+    #[test]
+    fn quarters_follow_each_other() {
+        let mut quarter = YearAndQuarter::from_start(
+            DateTime::parse_from_rfc3339("2023-02-10T12:00:00Z").unwrap(),
+        );
+
+        for _ in 0..8 {
+            let next = quarter.next();
+
+            assert!(quarter.start() < quarter.end());
+            assert_eq!(quarter.end(), next.start());
+            assert_eq!(YearAndQuarter::from_start(next.start()), next);
+
+            quarter = next;
+        }
+    }
+}
+
+async fn create_season(
+    db: &DatabaseConnection,
+    name: String,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    bundle: bundle::Model,
+) -> Result<seasons::Model> {
+    info!(
+        "Creating season {name} ({start:?} - {end:?}) Bundle {}",
+        bundle.id
+    );
+
+    let season = seasons::ActiveModel {
+        name: ActiveValue::set(name),
+        start: ActiveValue::set(start),
+        end: ActiveValue::set(Some(end)),
+        bundle_id: ActiveValue::set(bundle.id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(season)
+}
+
+/// Create the season following the latest one, using the same bundle. If that
+/// season would be over already, the season of the current quarter is created
+/// instead.
+pub async fn create_next_season(db: &DatabaseConnection) -> Result<seasons::Model> {
+    let Some(latest_season) = Seasons::find()
+        .order_by_desc(seasons::Column::Start)
+        .one(db)
+        .await? else {
+        return Err(eyre!("No season found"))
+    };
+    let Some(last_used_bundle) = latest_season.find_related(Bundle).one(db).await? else {
+        return Err(eyre!("No bundle found for season {}", latest_season.name))
+    };
+
+    debug!("Latest season: {:?}", latest_season.name);
+
+    let now = Utc::now().with_timezone(&Utc.fix());
+
+    // handle legacy season
+    let start = if latest_season.end.is_none() {
+        now
+    } else {
+        latest_season.start
+    };
+
+    let mut quarter = YearAndQuarter::from_start(start).next();
+
+    // seasons lapsed for more than a quarter, skip the quarters that are over
+    if quarter.end() <= now {
+        quarter = YearAndQuarter::from_start(now);
+    }
+
+    let season = create_season(
+        db,
+        quarter.to_string(),
+        quarter.start(),
+        quarter.end(),
+        last_used_bundle,
+    )
+    .await?;
+
+    // the legacy season would stay active next to the new one
+    if latest_season.end.is_none() {
+        info!(
+            "Ending season {} when {} starts",
+            latest_season.name, season.name
+        );
+
+        let mut legacy_season: seasons::ActiveModel = latest_season.into();
+        legacy_season.end = ActiveValue::set(Some(season.start));
+        legacy_season.update(db).await?;
+    }
+
+    Ok(season)
+}
+
+/// Held while rolling over to the next season, so concurrent casts create it
+/// only once
+static SEASON_ROLLOVER: Lazy<tokio::sync::Mutex<()>> = Lazy::new(Default::default);
+
+/// The active season. If seasons lapsed and none is scheduled, the next season
+/// is created once and looked up again.
+///
+/// Returns `None` if there still is no active season, e.g. while waiting for a
+/// scheduled season to start.
+pub async fn active_season_or_rollover(db: &DatabaseConnection) -> Result<Option<seasons::Model>> {
+    if let Some(season) = find_active_season(db).await? {
+        return Ok(Some(season));
+    }
+
+    let _rollover = SEASON_ROLLOVER.lock().await;
+
+    // another cast might have rolled over while we waited for the lock
+    if let Some(season) = find_active_season(db).await? {
+        return Ok(Some(season));
+    }
+
+    if has_next_season(db).await? {
+        debug!("No active season, waiting for the next season to start");
+        return Ok(None);
+    }
+
+    warn!("No active season, creating the next season");
+    create_next_season(db).await?;
+
+    find_active_season(db).await
+}
+
+#[cfg(test)]
+mod season_rollover_tests {
+    use chrono::{Duration, Utc};
+    use database::entities::{prelude::*, seasons};
+    use sea_orm::{
+        ActiveModelTrait, ActiveValue, DatabaseConnection, EntityTrait, IntoActiveModel,
+        PaginatorTrait,
+    };
+
+    use crate::{active_season_or_rollover, create_next_season, YearAndQuarter};
+
+    /// A database whose legacy season ended long ago, followed by a season
+    /// for each of `quarters`
+    async fn database(quarters: &[YearAndQuarter]) -> DatabaseConnection {
+        let db = database::memory_connection().await.unwrap();
+        let legacy = Seasons::find().one(&db).await.unwrap().unwrap();
+        let bundle_id = legacy.bundle_id;
+
+        let mut legacy = legacy.into_active_model();
+        legacy.start = ActiveValue::set((Utc::now() - Duration::days(1000)).into());
+        legacy.end = ActiveValue::set(Some((Utc::now() - Duration::days(900)).into()));
+        legacy.update(&db).await.unwrap();
+
+        for quarter in quarters {
+            seasons::ActiveModel {
+                name: ActiveValue::set(quarter.to_string()),
+                start: ActiveValue::set(quarter.start()),
+                end: ActiveValue::set(Some(quarter.end())),
+                bundle_id: ActiveValue::set(bundle_id),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        db
+    }
+
+    fn current_quarter() -> YearAndQuarter {
+        YearAndQuarter::from_start(Utc::now().into())
+    }
+
+    fn previous_quarter() -> YearAndQuarter {
+        YearAndQuarter::from_start(current_quarter().start() - Duration::days(1))
+    }
+
+    #[tokio::test]
+    async fn creates_next_season_and_retries() {
+        let db = database(&[previous_quarter()]).await;
+
+        let season = active_season_or_rollover(&db).await.unwrap().unwrap();
+
+        assert_eq!(season.name, current_quarter().to_string());
+        assert_eq!(Seasons::find().count(&db).await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn skips_lapsed_quarters() {
+        let lapsed = YearAndQuarter::from_start(previous_quarter().start() - Duration::days(1));
+        let db = database(&[lapsed]).await;
+
+        let season = active_season_or_rollover(&db).await.unwrap().unwrap();
+
+        assert_eq!(season.name, current_quarter().to_string());
+    }
+
+    #[tokio::test]
+    async fn waits_for_scheduled_season() {
+        let db = database(&[current_quarter().next()]).await;
+
+        assert!(active_season_or_rollover(&db).await.unwrap().is_none());
+        assert_eq!(Seasons::find().count(&db).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn ends_legacy_season() {
+        let db = database::memory_connection().await.unwrap();
+
+        let legacy = Seasons::find().one(&db).await.unwrap().unwrap();
+
+        let season = create_next_season(&db).await.unwrap();
+
+        assert_eq!(season.name, current_quarter().next().to_string());
+        let legacy = Seasons::find_by_id(legacy.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(legacy.end, Some(season.start));
+    }
+
+    #[tokio::test]
+    async fn ended_season_is_not_updated() {
+        let db = database(&[current_quarter()]).await;
+        let before = Seasons::find().all(&db).await.unwrap();
+
+        create_next_season(&db).await.unwrap();
+
+        let after = Seasons::find().all(&db).await.unwrap();
+        assert_eq!(after[..before.len()], before);
+    }
+
+    #[tokio::test]
+    async fn active_season_is_not_rolled_over() {
+        let db = database(&[current_quarter()]).await;
+
+        let season = active_season_or_rollover(&db).await.unwrap().unwrap();
+
+        assert_eq!(season.name, current_quarter().to_string());
+        assert_eq!(Seasons::find().count(&db).await.unwrap(), 2);
+    }
+}
+
+/// Create the very first season, using the fishes of bundle `bundle_id`. It
+/// starts now and ends with the current quarter, after which
+/// [`create_next_season`] takes over.
+///
+/// Fails if any season already exists.
+pub async fn init_first_season(
+    db: &DatabaseConnection,
+    name: String,
+    bundle_id: i32,
+) -> Result<seasons::Model> {
+    let txn = db.begin().await?;
+
+    if let Some(season) = Seasons::find().one(&txn).await? {
+        return Err(eyre!(
+            "Season {} already exists, refusing to create a first season",
+            season.name
+        ));
+    }
+
+    if Bundle::find_by_id(bundle_id).one(&txn).await?.is_none() {
+        return Err(eyre!("No bundle found with id {bundle_id}"));
+    }
+
+    let start = Utc::now().with_timezone(&Utc.fix());
+    let end = YearAndQuarter::from_start(start).end();
+
+    info!("Creating first season {name} ({start:?} - {end:?}) Bundle {bundle_id}");
+
+    let season = seasons::ActiveModel {
+        name: ActiveValue::set(name),
+        start: ActiveValue::set(start),
+        end: ActiveValue::set(Some(end)),
+        bundle_id: ActiveValue::set(bundle_id),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(season)
+}
+
+#[cfg(test)]
+mod init_first_season_tests {
+    use database::entities::prelude::*;
+    use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+
+    use crate::init_first_season;
+
+    /// A database without seasons and the bundle the legacy season had
+    async fn database() -> (DatabaseConnection, i32) {
+        let db = database::memory_connection().await.unwrap();
+        let legacy = Seasons::find().one(&db).await.unwrap().unwrap();
+        Seasons::delete_many().exec(&db).await.unwrap();
+
+        (db, legacy.bundle_id)
+    }
+
+    #[tokio::test]
+    async fn creates_season() {
+        let (db, bundle_id) = database().await;
+
+        let season = init_first_season(&db, "2023Q2".to_string(), bundle_id)
+            .await
+            .unwrap();
+
+        assert_eq!(season.name, "2023Q2");
+        assert_eq!(Seasons::find().count(&db).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_with_existing_season() {
+        let db = database::memory_connection().await.unwrap();
+
+        let legacy = Seasons::find().one(&db).await.unwrap().unwrap();
+
+        assert!(
+            init_first_season(&db, "2023Q2".to_string(), legacy.bundle_id)
+                .await
+                .is_err()
+        );
+        assert_eq!(Seasons::find().count(&db).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_unknown_bundle() {
+        let (db, bundle_id) = database().await;
+
+        assert!(init_first_season(&db, "2023Q2".to_string(), bundle_id + 1)
+            .await
+            .is_err());
+        assert_eq!(Seasons::find().count(&db).await.unwrap(), 0);
+    }
+}
+
+/// Check imported fish definitions before they touch the database.
+///
+/// Names have to be unique within the bundle, counts must not be negative and
+/// the weights have to pass the same checks as fishes loaded for a season.
+pub fn validate_definitions(definitions: &[FishDefinition]) -> Result<()> {
+    if definitions.is_empty() {
+        return Err(eyre!("Bundle has no fishes"));
+    }
+
+    let mut names = HashSet::new();
+
+    for definition in definitions {
+        if !names.insert(definition.name.as_str()) {
+            return Err(eyre!("Fish {} is defined twice", definition.name));
+        }
+
+        if definition.count < 0 {
+            return Err(eyre!(
+                "Fish {} has a negative count ({})",
+                definition.name,
+                definition.count
+            ));
+        }
+
+        Fish::try_from_model(definition.to_model(0))?;
+    }
+
+    Ok(())
+}
+
+/// Create a new bundle from `definitions` and use it for season `season_id`.
+///
+/// Fishes that already exist with exactly the same definition are reused, so
+/// their catches stay together. Everything else is inserted as a new fish,
+/// leaving the fishes of older bundles untouched.
+pub async fn import_bundle(
+    db: &DatabaseConnection,
+    definitions: &[FishDefinition],
+    season_id: i32,
+) -> Result<bundle::Model> {
+    validate_definitions(definitions)?;
+
+    let txn = db.begin().await?;
+
+    let Some(season) = Seasons::find_by_id(season_id).one(&txn).await? else {
+        return Err(eyre!("No season found with id {season_id}"));
+    };
+
+    let bundle = bundle::ActiveModel {
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    info!(
+        "Importing {} fishes into bundle {} for season {}",
+        definitions.len(),
+        bundle.id,
+        season.name
+    );
+
+    let mut links = Vec::with_capacity(definitions.len());
+
+    for definition in definitions {
+        let existing = Fishes::find()
+            .filter(fishes::Column::Name.eq(definition.name.as_str()))
+            .all(&txn)
+            .await?
+            .into_iter()
+            .find(|fish| FishDefinition::from(fish.clone()) == *definition);
+
+        let fish_id = match existing {
+            Some(fish) => {
+                debug!("Reusing fish {} ({})", fish.name, fish.id);
+                fish.id
+            }
+            None => {
+                let mut fish = definition.to_model(0).into_active_model();
+                fish.id = ActiveValue::NotSet;
+                fish.insert(&txn).await?.id
+            }
+        };
+
+        links.push(fish_bundle::ActiveModel {
+            fish_id: ActiveValue::set(fish_id),
+            bundle_id: ActiveValue::set(bundle.id),
+        });
+    }
+
+    FishBundle::insert_many(links)
+        .on_conflict(
+            // names are unique within the bundle, so a fish is linked only once
+            OnConflict::columns([fish_bundle::Column::FishId, fish_bundle::Column::BundleId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .exec(&txn)
+        .await?;
+
+    let mut season = season.into_active_model();
+    season.bundle_id = ActiveValue::set(bundle.id);
+    season.update(&txn).await?;
+
+    txn.commit().await?;
+
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod import_bundle_tests {
+    use database::{definition::FishDefinition, entities::prelude::*};
+    use sea_orm::{DatabaseConnection, EntityTrait, PaginatorTrait};
+    use test_case::test_case;
+
+    use crate::{import_bundle, validate_definitions};
+
+    fn definition(name: &str, count: i32, min_weight: f32, max_weight: f32) -> FishDefinition {
+        FishDefinition {
+            name: name.to_string(),
+            html_name: name.to_string(),
+            count,
+            base_value: 5.0,
+            min_weight,
+            max_weight,
+            is_trash: false,
+            weight_distribution: "uniform".to_string(),
+            weight_mean: None,
+            weight_stddev: None,
+        }
+    }
+
+    #[test]
+    fn accepts_valid_bundle() {
+        let definitions = [
+            definition("fish", 10, 1.0, 2.0),
+            definition("boot", 5, 0.0, 0.0),
+        ];
+
+        assert!(validate_definitions(&definitions).is_ok());
+    }
+
+    #[test_case(vec![] ; "empty")]
+    #[test_case(vec![definition("fish", -1, 1.0, 2.0)] ; "negative count")]
+    #[test_case(vec![definition("fish", 10, 2.0, 1.0)] ; "inverted weight")]
+    #[test_case(vec![definition("fish", 10, 1.0, 2.0), definition("fish", 5, 1.0, 2.0)] ; "duplicate name")]
+    fn rejects_invalid_bundle(definitions: Vec<FishDefinition>) {
+        assert!(validate_definitions(&definitions).is_err());
+    }
+
+    /// Number of bundles and fishes in `db`
+    async fn counts(db: &DatabaseConnection) -> (u64, u64) {
+        (
+            Bundle::find().count(db).await.unwrap(),
+            Fishes::find().count(db).await.unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn refuses_unknown_season() {
+        let db = database::memory_connection().await.unwrap();
+        let before = counts(&db).await;
+
+        assert!(import_bundle(&db, &[definition("fish", 10, 1.0, 2.0)], 1)
+            .await
+            .is_err());
+        assert_eq!(counts(&db).await, before);
+    }
+
+    #[tokio::test]
+    async fn invalid_bundle_does_not_touch_the_database() {
+        let db = database::memory_connection().await.unwrap();
+        let before = counts(&db).await;
+
+        assert!(import_bundle(&db, &[definition("fish", -1, 1.0, 2.0)], 0)
+            .await
+            .is_err());
+        assert_eq!(counts(&db).await, before);
+    }
+
+    #[tokio::test]
+    async fn imports_bundle_for_season() {
+        let db = database::memory_connection().await.unwrap();
+        let legacy = Seasons::find().one(&db).await.unwrap().unwrap();
+
+        let bundle = import_bundle(&db, &[definition("fish", 10, 1.0, 2.0)], legacy.id)
+            .await
+            .unwrap();
+
+        let season = Seasons::find_by_id(legacy.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(season.bundle_id, bundle.id);
+    }
+}
+
+/// Fishes of a bundle together with their summed count
+#[derive(Debug, Clone, Default)]
+pub struct FishSet {
+    fishes: Vec<Fish>,
+    population: u32,
+}
+
+impl FishSet {
+    /// Collect `fishes`, recounting the population stored in each of them.
+    pub fn new(mut fishes: Vec<Fish>) -> Self {
+        let population = recount_population(&mut fishes);
+
+        Self { fishes, population }
+    }
+
+    /// Summed count of all fishes in the set
+    pub fn population(&self) -> u32 {
+        self.population
+    }
+
+    /// Chance of catching `fish` out of this set
+    pub fn chance(&self, fish: &Fish) -> f32 {
+        fish.chance_in(self.population)
+    }
+
+    pub fn into_fishes(self) -> Vec<Fish> {
+        self.fishes
+    }
+}
+
+impl Deref for FishSet {
+    type Target = [Fish];
+
+    fn deref(&self) -> &Self::Target {
+        &self.fishes
+    }
+}
+
+pub async fn get_fishes(db: &DatabaseConnection, season: &seasons::Model) -> Result<FishSet> {
+    let Some(bundle) = season.find_related(Bundle).one(db).await? else {
+        return Err(eyre!("No bundle found for season {}", season.name))
+    };
+
+    let models = bundle.find_related(Fishes).all(db).await?;
+
+    let fishes: Vec<Fish> = models
+        .into_iter()
+        .filter_map(|fish| match Fish::try_from_model(fish) {
+            Ok(fish) => Some(fish),
+            Err(err) => {
+                warn!("Skipping invalid fish: {err}");
+                None
+            }
+        })
+        .collect();
+
+    Ok(FishSet::new(fishes))
+}
+
+/// Caches the fishes of a season so they are not loaded on every cast.
+#[derive(Debug)]
+pub struct FishCache {
+    ttl: StdDuration,
+    cached: RwLock<Option<CachedFishes>>,
+}
+
+#[derive(Debug)]
+struct CachedFishes {
+    season_id: i32,
+    fishes: Arc<FishSet>,
+    loaded_at: Instant,
+}
+
+impl FishCache {
+    /// Cached fishes are reloaded after `ttl`. A zero `ttl` disables caching.
+    pub fn new(ttl: StdDuration) -> Self {
+        Self {
+            ttl,
+            cached: RwLock::new(None),
+        }
+    }
+
+    /// Get the fishes of `season`, loading them if they are not cached or the
+    /// cache belongs to another season.
+    pub async fn get(
+        &self,
+        db: &DatabaseConnection,
+        season: &seasons::Model,
+    ) -> Result<Arc<FishSet>> {
+        if let Some(fishes) = self.cached(season.id, Instant::now()) {
+            return Ok(fishes);
+        }
+
+        debug!("Loading fishes of season {}", season.name);
+        let fishes = Arc::new(get_fishes(db, season).await?);
+        self.store(season.id, fishes.clone(), Instant::now());
+
+        Ok(fishes)
+    }
+
+    /// Drop the cached fishes so they are reloaded on the next access.
+    pub fn invalidate(&self) {
+        *self.cached.write().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    fn cached(&self, season_id: i32, now: Instant) -> Option<Arc<FishSet>> {
+        self.cached
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_ref()
+            .filter(|cached| {
+                cached.season_id == season_id && now.duration_since(cached.loaded_at) < self.ttl
+            })
+            .map(|cached| cached.fishes.clone())
+    }
+
+    fn store(&self, season_id: i32, fishes: Arc<FishSet>, now: Instant) {
+        *self.cached.write().unwrap_or_else(PoisonError::into_inner) = Some(CachedFishes {
+            season_id,
+            fishes,
+            loaded_at: now,
+        });
+    }
+}
+
+#[cfg(test)]
+mod fish_cache_tests {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use crate::{Fish, FishCache, FishSet, WeightDistribution};
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn fishes() -> Arc<FishSet> {
+        Arc::new(FishSet::new(vec![Fish {
+            id: 1,
+            name: "fish".to_string(),
+            count: 1,
+            base_value: 1,
+            weight_range: None,
+            weight_distribution: WeightDistribution::Uniform,
+            population: 1,
+        }]))
+    }
+
+    #[test]
+    fn cached_within_ttl() {
+        let cache = FishCache::new(TTL);
+        let now = Instant::now();
+        cache.store(1, fishes(), now);
+
+        assert!(cache.cached(1, now + Duration::from_secs(59)).is_some());
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let cache = FishCache::new(TTL);
+        let now = Instant::now();
+        cache.store(1, fishes(), now);
+
+        assert!(cache.cached(1, now + TTL).is_none());
+    }
+
+    #[test]
+    fn invalidate() {
+        let cache = FishCache::new(TTL);
+        let now = Instant::now();
+        cache.store(1, fishes(), now);
+
+        cache.invalidate();
+
+        assert!(cache.cached(1, now).is_none());
+    }
+
+    #[test]
+    fn other_season() {
+        let cache = FishCache::new(TTL);
+        let now = Instant::now();
+        cache.store(1, fishes(), now);
+
+        assert!(cache.cached(2, now).is_none());
+    }
+
+    #[test]
+    fn disabled() {
+        let cache = FishCache::new(Duration::ZERO);
+        let now = Instant::now();
+        cache.store(1, fishes(), now);
+
+        assert!(cache.cached(1, now).is_none());
+    }
+}
+
+/// Sum the counts of `fishes` and store the result as their population.
+/// Returns the new population.
+pub fn recount_population(fishes: &mut [Fish]) -> u32 {
+    let population = fishes.iter().map(|fish| fish.count).sum();
+
+    for fish in fishes {
+        fish.population = population;
+    }
+
+    population
+}
+
+#[cfg(test)]
+mod population_tests {
+    use crate::{recount_population, Fish, FishSet, WeightDistribution};
+
+    fn fish(id: i32, count: u32) -> Fish {
+        Fish {
+            id,
+            name: format!("fish {id}"),
+            count,
+            base_value: 1,
+            weight_range: None,
+            weight_distribution: WeightDistribution::Uniform,
+            population: 0,
+        }
+    }
+
+    #[test]
+    fn recount_after_edit() {
+        let mut fishes = vec![fish(1, 30), fish(2, 10)];
+        assert_eq!(recount_population(&mut fishes), 40);
+
+        fishes[1].count = 70;
+        let population = recount_population(&mut fishes);
+
+        assert_eq!(
+            population,
+            fishes.iter().map(|fish| fish.count).sum::<u32>()
+        );
+        assert_eq!(population, 100);
+        assert!(fishes.iter().all(|fish| fish.population == 100));
+        assert_eq!(fishes[1].chance(), 0.7);
+    }
+
+    #[test]
+    fn fish_set_counts_population() {
+        let fishes = FishSet::new(vec![fish(1, 30), fish(2, 10)]);
+
+        assert_eq!(fishes.population(), 40);
+        assert_eq!(fishes.chance(&fishes[0]), 0.75);
+        assert!(fishes.iter().all(|fish| fish.population == 40));
+    }
+
+    #[test]
+    fn chance_without_population() {
+        assert_eq!(fish(1, 30).chance(), 0.0);
+        assert_eq!(FishSet::default().population(), 0);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Catch {
+    pub fish_name: String,
+    pub weight: Option<f32>,
+    pub value: f32,
+}
+
+impl Catch {
+    pub fn new(fish: &Fish, weight: Option<f32>) -> Self {
+        let multiplier = fish
+            .weight_range
+            .as_ref()
+            .and_then(|range| {
+                weight.map(|weight| (weight - range.start) / (range.end - range.start))
+            })
+            .map_or(1.0, |x| (x * 1.36 - 0.48).powi(3) + 1.01 + x * 0.11);
+
+        Self {
+            fish_name: fish.name.clone(),
+            weight,
+            value: fish.base_value as f32 * multiplier,
+        }
+    }
+
+    /// A stored catch of the fish named `fish_name`
+    pub fn from_model(catch: catches::Model, fish_name: String) -> Self {
+        Self {
+            fish_name,
+            weight: catch.weight,
+            value: catch.value,
+        }
+    }
+
+    /// Display this catch with its weight in `unit`
+    pub fn in_unit(&self, unit: WeightUnit) -> InUnit<'_, Self> {
+        InUnit { value: self, unit }
+    }
+
+    /// Lower the loss of a negative catch so `score` does not drop below zero.
+    pub fn clamp_to_score_floor(&mut self, score: f32) {
+        self.value = self.value.max((-score).min(0.0));
+    }
+}
+
+impl Display for Catch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.in_unit(WeightUnit::default()).fmt(f)
+    }
+}
+
+impl Display for InUnit<'_, Catch> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let catch = self.value;
+        write!(f, "{}", catch.fish_name)?;
+        if let Some(weight) = catch.weight {
+            write!(f, " ({})", format_weight(weight, self.unit))?;
+        }
+        if catch.value.abs() > f32::EPSILON {
+            write!(f, " worth {}", format_money(catch.value))?;
         } else {
             write!(f, " worth nothing")?;
         }
@@ -310,7 +2156,596 @@ impl Display for Catch {
     }
 }
 
+/// A catch about to be stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewCatch {
+    pub fish_id: i32,
+    pub weight: Option<f32>,
+    pub value: f32,
+    pub caught_at: DateTime<FixedOffset>,
+}
+
+/// A cast about to be stored
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewCast {
+    pub user_id: i32,
+    pub season_id: i32,
+    /// One catch, or two for a double catch
+    pub catches: Vec<NewCatch>,
+    /// Bait the cast used up
+    pub bait_id: Option<i32>,
+    /// Whether to rank the user once the cast is stored
+    pub rank: bool,
+}
+
+/// A user who fishes for the first time
 #[derive(Debug)]
+pub enum NewUser {
+    Created(users::Model),
+    /// The user was created concurrently
+    Existing(users::Model),
+}
+
+/// Everything a cast reads and writes, so casting can be tested without a
+/// database
+#[async_trait]
+pub trait CatchRepository: Send + Sync {
+    /// The season catches go to, `None` while fishing is closed
+    async fn active_season(&self) -> Result<Option<seasons::Model>>;
+
+    /// The fishes that can be caught in `season`
+    async fn fishes(&self, season: &seasons::Model) -> Result<Arc<FishSet>>;
+
+    async fn find_user(&self, login: &str) -> Result<Option<users::Model>>;
+
+    /// Create the user `login` fishing for the first time at `now`.
+    async fn create_user(&self, login: &str, now: DateTime<FixedOffset>) -> Result<NewUser>;
+
+    /// Start the cooldown of `user` at `now`. Returns `false` if another cast
+    /// of the user started it since `user` was looked up.
+    async fn start_cooldown(&self, user: &users::Model, now: DateTime<FixedOffset>)
+        -> Result<bool>;
+
+    /// Messages to reply with while a user is on cooldown
+    async fn cooldown_messages(&self) -> Result<Vec<String>>;
+
+    /// Catch messages of `channel` and those shared by all channels
+    async fn catch_messages(&self, channel: &str) -> Result<Vec<messages::Model>>;
+
+    /// The fish `user` caught last
+    async fn previous_fish(&self, user: &users::Model) -> Result<Option<i32>>;
+
+    /// Bait `user` has left
+    async fn bait(&self, user: &users::Model) -> Result<Option<inventory::Model>>;
+
+    /// Summed value of the catches of `user` in `season`
+    async fn season_score(&self, user: &users::Model, season: &seasons::Model) -> Result<f32>;
+
+    /// Store `cast` and use up its bait, either all of it or nothing. Returns
+    /// the rank of the user in the season including this cast if
+    /// [`NewCast::rank`] is set.
+    async fn record(&self, cast: &NewCast) -> Result<Option<u64>>;
+}
+
+/// Keeps everything a cast needs in memory, e.g. to test fishing without a
+/// database
+#[derive(Debug, Default)]
+pub struct MemoryCatchRepository {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    season: Option<seasons::Model>,
+    fishes: Arc<FishSet>,
+    users: Vec<users::Model>,
+    bait: Vec<inventory::Model>,
+    cooldown_messages: Vec<String>,
+    casts: Vec<NewCast>,
+}
+
+impl MemoryCatchRepository {
+    /// Fish for `fishes` in `season`
+    pub fn new(season: seasons::Model, fishes: Vec<Fish>) -> Self {
+        Self {
+            state: Mutex::new(MemoryState {
+                season: Some(season),
+                fishes: Arc::new(FishSet::new(fishes)),
+                ..Default::default()
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn with_user(self, user: users::Model) -> Self {
+        self.state().users.push(user);
+        self
+    }
+
+    pub fn with_bait(self, bait: inventory::Model) -> Self {
+        self.state().bait.push(bait);
+        self
+    }
+
+    pub fn with_cooldown_message(self, text: &str) -> Self {
+        self.state().cooldown_messages.push(text.to_string());
+        self
+    }
+
+    /// The user `login` as it is stored now
+    pub fn user(&self, login: &str) -> Option<users::Model> {
+        self.state()
+            .users
+            .iter()
+            .find(|user| user.name == login)
+            .cloned()
+    }
+
+    /// All casts recorded so far
+    pub fn casts(&self) -> Vec<NewCast> {
+        self.state().casts.clone()
+    }
+}
+
+impl MemoryState {
+    fn season_score(&self, user_id: i32, season_id: i32) -> f32 {
+        self.casts
+            .iter()
+            .filter(|cast| cast.user_id == user_id && cast.season_id == season_id)
+            .flat_map(|cast| &cast.catches)
+            .map(|catch| catch.value)
+            .sum()
+    }
+}
+
+#[async_trait]
+impl CatchRepository for MemoryCatchRepository {
+    async fn active_season(&self) -> Result<Option<seasons::Model>> {
+        Ok(self.state().season.clone())
+    }
+
+    async fn fishes(&self, _season: &seasons::Model) -> Result<Arc<FishSet>> {
+        Ok(self.state().fishes.clone())
+    }
+
+    async fn find_user(&self, login: &str) -> Result<Option<users::Model>> {
+        Ok(self.user(login))
+    }
+
+    async fn create_user(&self, login: &str, now: DateTime<FixedOffset>) -> Result<NewUser> {
+        let mut state = self.state();
+
+        if let Some(user) = state.users.iter().find(|user| user.name == login) {
+            return Ok(NewUser::Existing(user.clone()));
+        }
+
+        let user = users::Model {
+            id: state.users.iter().map(|user| user.id).max().unwrap_or(0) + 1,
+            name: login.to_string(),
+            last_fished: now,
+            is_bot: false,
+        };
+        state.users.push(user.clone());
+
+        Ok(NewUser::Created(user))
+    }
+
+    async fn start_cooldown(
+        &self,
+        user: &users::Model,
+        now: DateTime<FixedOffset>,
+    ) -> Result<bool> {
+        let mut state = self.state();
+
+        match state
+            .users
+            .iter_mut()
+            .find(|stored| stored.id == user.id && stored.last_fished == user.last_fished)
+        {
+            Some(stored) => {
+                stored.last_fished = now;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn cooldown_messages(&self) -> Result<Vec<String>> {
+        Ok(self.state().cooldown_messages.clone())
+    }
+
+    async fn catch_messages(&self, _channel: &str) -> Result<Vec<messages::Model>> {
+        Ok(Vec::new())
+    }
+
+    async fn previous_fish(&self, user: &users::Model) -> Result<Option<i32>> {
+        Ok(self
+            .state()
+            .casts
+            .iter()
+            .filter(|cast| cast.user_id == user.id)
+            .flat_map(|cast| &cast.catches)
+            .last()
+            .map(|catch| catch.fish_id))
+    }
+
+    async fn bait(&self, user: &users::Model) -> Result<Option<inventory::Model>> {
+        Ok(self
+            .state()
+            .bait
+            .iter()
+            .find(|bait| bait.user_id == user.id && bait.item == BAIT && bait.amount > 0)
+            .cloned())
+    }
+
+    async fn season_score(&self, user: &users::Model, season: &seasons::Model) -> Result<f32> {
+        Ok(self.state().season_score(user.id, season.id))
+    }
+
+    async fn record(&self, cast: &NewCast) -> Result<Option<u64>> {
+        let mut state = self.state();
+
+        if let Some(bait_id) = cast.bait_id {
+            if let Some(bait) = state
+                .bait
+                .iter_mut()
+                .find(|bait| bait.id == bait_id && bait.amount > 0)
+            {
+                bait.amount -= 1;
+            }
+        }
+
+        state.casts.push(cast.clone());
+
+        if !cast.rank {
+            return Ok(None);
+        }
+
+        let score = state.season_score(cast.user_id, cast.season_id);
+        let ahead = state
+            .users
+            .iter()
+            .filter(|user| !user.is_bot)
+            .filter(|user| state.season_score(user.id, cast.season_id) > score)
+            .count();
+
+        Ok(Some(ahead as u64 + 1))
+    }
+}
+
+#[cfg(test)]
+mod catch_tests {
+    use std::ops::Range;
+
+    use approx::assert_ulps_eq;
+    use chrono::Utc;
+    use database::entities::catches;
+    use test_case::test_case;
+
+    use crate::{Catch, Fish, WeightDistribution, WeightUnit};
+
+    #[test_case(Some(0.0..1.0), 100, 0.0, 89.940796 ; "range 0.0 to 1.0 with base value 100 and weight 0.0")]
+    #[test_case(Some(0.0..1.0), 100, 0.5, 107.299995 ; "range 0.0 to 1.0 with base value 100 and weight 0.5")]
+    #[test_case(Some(0.0..1.0), 100, 1.0, 180.1472 ; "range 0.0 to 1.0 with base value 100 and weight 1.0")]
+    #[test_case(Some(0.0..1.0), 100, 1.1, 217.97722 ; "range 0.0 to 1.0 with base value 100 and over weight 1.1")]
+    #[test_case(Some(0.0..1.0), -100, 0.0, -89.940796 ; "range 0.0 to 1.0 with negative base value -100 and weight 0.0")]
+    #[test_case(Some(0.0..1.0), -100, 0.5, -107.299995 ; "range 0.0 to 1.0 with negative base value -100 and weight 0.5")]
+    #[test_case(Some(0.0..1.0), -100, 1.0, -180.1472 ; "range 0.0 to 1.0 with negative base value -100 and weight 1.0")]
+    #[test_case(Some(5.3..12.6), 123, 5.3, 110.62718 ; "range 5.3 to 12.6 with base value 123 and weight 5.3")]
+    #[test_case(Some(5.3..12.6), 123, 8.95, 131.97899 ; "range 5.3 to 12.6 with base value 123 and weight 8.95")]
+    #[test_case(Some(5.3..12.6), 123, 12.6, 221.58107 ; "range 5.3 to 12.6 with base value 123 and weight 12.6")]
+    #[test_case(Some(88000.0..130000.0), 800, 91961.3 , 781.4889 ; "range 88000.0 to 130000.0 with base value 800 and weight 91961.3")]
+    #[test_case(None, -50, 0.0, -50.0 ; "without range with base value -50 and weight 0.0")]
+    #[test_case(None, -50, 100.0, -50.0 ; "without range with base value -50 and weight 100.0")]
+    fn catch_value(
+        weight_range: Option<Range<f32>>,
+        base_value: i32,
+        weight: f32,
+        expected_value: f32,
+    ) {
+        let fish = Fish {
+            id: 0,
+            name: String::new(),
+            count: 0,
+            base_value,
+            weight_range,
+            weight_distribution: WeightDistribution::Uniform,
+            population: 0,
+        };
+        let catch = Catch::new(&fish, Some(weight));
+        assert_ulps_eq!(catch.value, expected_value, max_ulps = 4);
+    }
+
+    #[test_case(0.2..5.0, 20 ; "small fish")]
+    #[test_case(2.0..5.0, 30 ; "duck")]
+    #[test_case(1.0..10.0, 1000 ; "money bag")]
+    #[test_case(10.0..500.0, 30 ; "turtle")]
+    #[test_case(522.0..771.0, 150 ; "shark")]
+    #[test_case(88000.0..130000.0, 800 ; "whale")]
+    fn heavier_catches_are_worth_more(weight_range: Range<f32>, base_value: i32) {
+        const STEPS: u16 = 1000;
+
+        let fish = Fish {
+            id: 0,
+            name: String::new(),
+            count: 0,
+            base_value,
+            weight_range: Some(weight_range.clone()),
+            weight_distribution: WeightDistribution::Uniform,
+            population: 0,
+        };
+        let values: Vec<(f32, f32)> = (0..=STEPS)
+            .map(|step| {
+                let weight = weight_range.start
+                    + (weight_range.end - weight_range.start) * f32::from(step) / f32::from(STEPS);
+
+                (weight, Catch::new(&fish, Some(weight)).value)
+            })
+            .collect();
+
+        for pair in values.windows(2) {
+            let (lighter, lighter_value) = pair[0];
+            let (heavier, heavier_value) = pair[1];
+
+            assert!(
+                heavier_value > lighter_value,
+                "{heavier}kg is worth {heavier_value} but {lighter}kg is worth {lighter_value}"
+            );
+        }
+    }
+
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: 0.0 }, "fish worth nothing" ; "without weight worth nothing")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: -50.0 }, "fish worth -$50.00" ; "without weight with negative worth")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: 50.0 }, "fish worth $50.00" ; "without weight with positive worth")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: Some(1.23), value: 0.0 }, "fish (1.2kg) worth nothing" ; "with weight worth nothing")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: Some(1.23), value: -50.0 }, "fish (1.2kg) worth -$50.00" ; "with weight with negative worth")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: Some(1.23), value: 50.0 }, "fish (1.2kg) worth $50.00" ; "with weight with positive worth")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: f32::EPSILON / 2.0 }, "fish worth nothing" ; "tiny positive worth")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: -f32::EPSILON / 2.0 }, "fish worth nothing" ; "tiny negative worth")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: 0.01 }, "fish worth $0.01" ; "small positive worth")]
+    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: -0.01 }, "fish worth -$0.01" ; "small negative worth")]
+    fn catch_format(catch: Catch, expected: &str) {
+        assert_eq!(catch.to_string(), expected);
+    }
+
+    #[test_case(WeightUnit::Kilograms, "🐋 (120000.0kg) worth $800.00" ; "kilograms")]
+    #[test_case(WeightUnit::Pounds, "🐋 (264554.7lb) worth $800.00" ; "pounds")]
+    fn catch_format_in_unit(unit: WeightUnit, expected: &str) {
+        let catch = Catch {
+            fish_name: "🐋".to_string(),
+            weight: Some(120_000.0),
+            value: 800.0,
+        };
+
+        assert_eq!(catch.in_unit(unit).to_string(), expected);
+    }
+
+    #[test_case(WeightUnit::Kilograms, "🐋 (50.0%) (88000.0kg - 130000.0kg)" ; "kilograms")]
+    #[test_case(WeightUnit::Pounds, "🐋 (50.0%) (194006.8lb - 286600.9lb)" ; "pounds")]
+    fn fish_format_in_unit(unit: WeightUnit, expected: &str) {
+        let fish = Fish {
+            id: 0,
+            name: "🐋".to_string(),
+            count: 1,
+            base_value: 800,
+            weight_range: Some(88_000.0..130_000.0),
+            weight_distribution: WeightDistribution::Uniform,
+            population: 2,
+        };
+
+        assert_eq!(fish.in_unit(unit).to_string(), expected);
+    }
+
+    #[test_case(Some(1.23), 50.0, "fish (1.2kg) worth $50.00" ; "with weight")]
+    #[test_case(None, f32::EPSILON / 2.0, "fish worth nothing" ; "near zero")]
+    fn stored_catch_format(weight: Option<f32>, value: f32, expected: &str) {
+        let model = catches::Model {
+            id: 1,
+            user_id: 1,
+            fish_id: 1,
+            weight,
+            caught_at: Utc::now().into(),
+            value,
+            season_id: 1,
+        };
+
+        assert_eq!(
+            Catch::from_model(model, "fish".to_string()).to_string(),
+            expected
+        );
+    }
+
+    #[test_case(30.0, 20.0, 20.0 ; "positive catch")]
+    #[test_case(30.0, -10.0, -10.0 ; "negative catch above floor")]
+    #[test_case(30.0, -30.0, -30.0 ; "negative catch reaching floor")]
+    #[test_case(30.0, -50.0, -30.0 ; "negative catch crossing floor")]
+    #[test_case(0.0, -50.0, 0.0 ; "negative catch at floor")]
+    #[test_case(-20.0, -50.0, 0.0 ; "negative catch below floor")]
+    fn catch_score_floor(score: f32, value: f32, expected: f32) {
+        let mut catch = Catch {
+            fish_name: "fish".to_string(),
+            weight: None,
+            value,
+        };
+        catch.clamp_to_score_floor(score);
+        assert_eq!(catch.value, expected);
+    }
+}
+
+/// Average value multiplier of a fish with weights drawn as in [`Fish::catch`].
+///
+/// For uniform weights this integrates the polynomial used in [`Catch::new`]
+/// over `0..1`. Other distributions are averaged over a fixed-seed sample.
+pub fn expected_multiplier(fish: &Fish) -> f64 {
+    const SAMPLES: u32 = 10_000;
+
+    if fish.weight_range.is_none() {
+        return 1.0;
+    }
+
+    if fish.weight_distribution != WeightDistribution::Uniform {
+        let fish = Fish {
+            base_value: 1,
+            ..fish.clone()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+
+        return (0..SAMPLES)
+            .map(|_| f64::from(fish.catch_with(&mut rng).value))
+            .sum::<f64>()
+            / f64::from(SAMPLES);
+    }
+
+    let (a, b) = (1.36_f64, -0.48_f64);
+
+    ((a + b).powi(4) - b.powi(4)) / (4.0 * a) + 1.01 + 0.11 / 2.0
+}
+
+/// Expected value of a single catch from `fishes`.
+pub fn expected_value_per_catch(fishes: &[Fish]) -> f64 {
+    fishes
+        .iter()
+        .map(|fish| expected_value_share(fishes, fish))
+        .sum()
+}
+
+/// Part of the expected value of a catch from `fishes` contributed by `fish`.
+fn expected_value_share(fishes: &[Fish], fish: &Fish) -> f64 {
+    let population: f64 = fishes.iter().map(|fish| f64::from(fish.count)).sum();
+
+    if population == 0.0 {
+        return 0.0;
+    }
+
+    f64::from(fish.count) / population * f64::from(fish.base_value) * expected_multiplier(fish)
+}
+
+/// Find base values so a catch from `fishes` is worth `target` on average.
+///
+/// Only fishes with a positive base value are scaled, trash keeps its
+/// penalty. As the expected value is linear in the base values the scale
+/// factor can be solved for directly, leaving only the rounding error.
+///
+/// Returns `None` if there is no valuable fish to scale or the target cannot
+/// be reached without turning valuable fishes into trash.
+pub fn balance_base_values(fishes: &[Fish], target: f64) -> Option<Vec<i32>> {
+    let (valuable, trash) = fishes.iter().fold((0.0, 0.0), |(valuable, trash), fish| {
+        let share = expected_value_share(fishes, fish);
+
+        if fish.base_value > 0 {
+            (valuable + share, trash)
+        } else {
+            (valuable, trash + share)
+        }
+    });
+
+    let factor = (target - trash) / valuable;
+
+    if factor <= 0.0 || !factor.is_finite() {
+        return None;
+    }
+
+    Some(
+        fishes
+            .iter()
+            .map(|fish| {
+                if fish.base_value > 0 {
+                    ((f64::from(fish.base_value) * factor).round() as i32).max(1)
+                } else {
+                    fish.base_value
+                }
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod balance_tests {
+    use approx::assert_abs_diff_eq;
+    use test_case::test_case;
+
+    use crate::{
+        balance_base_values, expected_multiplier, expected_value_per_catch, Catch, Fish,
+        WeightDistribution,
+    };
+
+    fn fish(count: u32, base_value: i32, weight_range: Option<std::ops::Range<f32>>) -> Fish {
+        Fish {
+            id: 0,
+            name: String::new(),
+            count,
+            base_value,
+            weight_range,
+            weight_distribution: WeightDistribution::Uniform,
+            population: 0,
+        }
+    }
+
+    fn bundle() -> Vec<Fish> {
+        vec![
+            fish(500, -20, None),
+            fish(300, 10, Some(0.2..1.5)),
+            fish(150, 40, Some(1.0..8.0)),
+            fish(45, 150, Some(20.0..90.0)),
+            fish(5, 1200, Some(300.0..700.0)),
+        ]
+    }
+
+    #[test]
+    fn expected_multiplier_matches_catches() {
+        let fish = fish(1, 100, Some(0.0..1.0));
+        let steps = 100_000;
+        let mean = (0..steps)
+            .map(|step| (step as f32 + 0.5) / steps as f32)
+            .map(|weight| f64::from(Catch::new(&fish, Some(weight)).value) / 100.0)
+            .sum::<f64>()
+            / f64::from(steps);
+
+        assert_abs_diff_eq!(expected_multiplier(&fish), mean, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn expected_multiplier_follows_distribution() {
+        let uniform = fish(1, 100, Some(0.0..1.0));
+        let triangular = Fish {
+            weight_distribution: WeightDistribution::Triangular,
+            ..uniform.clone()
+        };
+
+        assert!(expected_multiplier(&triangular) < expected_multiplier(&uniform));
+    }
+
+    #[test]
+    fn expected_multiplier_without_weight() {
+        assert_eq!(expected_multiplier(&fish(1, 100, None)), 1.0);
+    }
+
+    #[test_case(5.0 ; "low target")]
+    #[test_case(25.0 ; "medium target")]
+    #[test_case(250.0 ; "high target")]
+    fn balance_converges(target: f64) {
+        let mut fishes = bundle();
+        let base_values = balance_base_values(&fishes, target).unwrap();
+
+        for (fish, base_value) in fishes.iter_mut().zip(base_values) {
+            fish.base_value = base_value;
+        }
+
+        assert_abs_diff_eq!(expected_value_per_catch(&fishes), target, epsilon = 1.0);
+        assert_eq!(fishes[0].base_value, -20);
+    }
+
+    #[test]
+    fn balance_unreachable_target() {
+        assert!(balance_base_values(&bundle(), -50.0).is_none());
+        assert!(balance_base_values(&[fish(1, -20, None)], 10.0).is_none());
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Account {
     id: i32,
     db: DatabaseConnection,
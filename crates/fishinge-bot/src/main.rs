@@ -1,43 +1,80 @@
 #![forbid(unsafe_code)]
 
+mod account_age;
+mod command_cooldown;
+mod commands;
+mod dedup;
+mod happy_hour;
+mod responder;
+mod webhook;
+
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     env,
+    str::FromStr,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration as StdDuration,
+    time::{Duration as StdDuration, Instant},
 };
 
-use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use account_age::{AccountAgeGate, HelixAccountLookup};
+use async_trait::async_trait;
+use bot_framework::{
+    drain::{self, DEFAULT_DRAIN_TIMEOUT},
+    rate_limit::{RateLimit, RateLimiter},
+    runner::parse_channels,
+};
+use chrono::{DateTime, Duration, FixedOffset, NaiveDateTime, Utc};
+use command_cooldown::CommandCooldowns;
+use commands::{Command, Commands};
 use database::{
-    connection,
-    entities::{catches, fishes, messages, prelude::*, sea_orm_active_enums::MessageType, users},
-    migrate,
+    connection_with_retry,
+    entities::{
+        catches, fish_bundle, fishes, inventory, messages, prelude::*,
+        sea_orm_active_enums::MessageType, seasons, users,
+    },
+    maintenance, migrate,
+    secret::{secret_var, SecretError},
+    DB_CONNECT_ATTEMPTS, DB_CONNECT_DELAY, GIFT_FISH,
 };
+use dedup::SeenMessages;
 use dotenvy::dotenv;
 use eyre::{eyre, Result, WrapErr};
 use fishinge_bot::{
-    create_next_season, get_active_season, get_fishes, has_next_season, Account, Catch,
+    active_season_or_rollover, boost_rare_fishes, cast, command_regex, create_next_season,
+    fish_once, format_money, format_thousands, format_weight, get_active_season, has_next_season,
+    snapshot_ending_season, Account, Catch, CatchRepository, Fish, FishCache, FishSet, NewCast,
+    NewCatch, NewUser, WeightUnit, BAIT, BAIT_BIAS, DEFAULT_TRIGGER, RARE_CHANCE,
 };
 use futures_lite::stream::StreamExt;
+use happy_hour::{HappyHour, HappyHours};
 use log::{debug, error, info, trace, warn};
 use once_cell::sync::Lazy;
-use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, SeedableRng};
+use rand::{rngs::StdRng, seq::SliceRandom, thread_rng, Rng, SeedableRng};
 use regex::Regex;
+use responder::{failed_replies, DryRunResponder, Responder, TwitchResponder};
 use sea_orm::{
-    sea_query::OnConflict, ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection,
-    DeriveColumn, EntityTrait, EnumIter, QueryFilter, QueryOrder, QuerySelect,
+    sea_query::{Expr, Func, OnConflict, SimpleExpr},
+    ActiveModelTrait, ActiveValue, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection,
+    DeriveColumn, EntityTrait, EnumIter, FromQueryResult, JoinType, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, QueryTrait, RelationTrait, Select, TransactionTrait,
 };
 use signal_hook::consts::*;
 use signal_hook_tokio::Signals;
-use tokio::{select, sync::Notify};
+use tokio::{
+    select,
+    sync::{oneshot, Notify},
+    task::JoinSet,
+    time::timeout,
+};
 use twitch_irc::{
-    login::RefreshingLoginCredentials,
-    message::{PrivmsgMessage, ServerMessage},
+    login::{RefreshingLoginCredentials, RefreshingLoginError},
+    message::{Badge, PrivmsgMessage, ServerMessage, TwitchUserBasics, WhisperMessage},
     ClientConfig, SecureTCPTransport, TwitchIRCClient,
 };
+use webhook::{CatchNotification, CatchWebhook};
 
 #[derive(Debug, thiserror::Error)]
 enum Error {
@@ -51,9 +88,25 @@ enum Error {
     Database(#[from] sea_orm::DbErr),
 
     #[error("Could not reply to message")]
-    ReplyToMessage(
-        #[from] twitch_irc::Error<SecureTCPTransport, RefreshingLoginCredentials<Account>>,
-    ),
+    ReplyToMessage(#[from] ClientError),
+
+    #[error("Could not get login credentials")]
+    GetCredentials(#[source] RefreshingLoginError<Account>),
+
+    #[error("Login credentials have no access token")]
+    MissingToken,
+
+    #[error("Could not find the user the bot is logged in as")]
+    UnknownBotUser,
+
+    #[error("Account {0} has no client, it is only connected on restart")]
+    UnknownAccount(String),
+
+    #[error("Helix request failed")]
+    Helix(#[from] reqwest::Error),
+
+    #[error("Helix returned invalid account creation date {0:?}")]
+    InvalidAccountCreation(String),
 
     #[error("Could not join thread")]
     JoinThread(#[from] tokio::task::JoinError),
@@ -64,15 +117,32 @@ enum Error {
         name: &'static str,
     },
 
+    #[error("Could not read secret")]
+    Secret(#[from] SecretError),
+
+    #[error("Environent variable {name} has invalid value {value:?}")]
+    InvalidEnvar { name: &'static str, value: String },
+
+    #[error("Environent variable {name} is not a valid duration")]
+    InvalidDuration {
+        source: humantime::DurationError,
+        name: &'static str,
+    },
+
     #[error("Signal hooking error")]
     Signals(#[source] std::io::Error),
 }
 
 type Client = TwitchIRCClient<SecureTCPTransport, RefreshingLoginCredentials<Account>>;
+type ClientError = twitch_irc::Error<SecureTCPTransport, RefreshingLoginCredentials<Account>>;
 
 static QUITTING: AtomicBool = AtomicBool::new(false);
 
-async fn handle_signals(mut signals: Signals, quit_signal: Arc<Notify>) {
+async fn handle_signals(
+    mut signals: Signals,
+    quit_signal: Arc<Notify>,
+    reload_signal: Arc<Notify>,
+) {
     info!("Starting signal handler");
     while let Some(signal) = signals.next().await {
         match signal {
@@ -82,6 +152,10 @@ async fn handle_signals(mut signals: Signals, quit_signal: Arc<Notify>) {
                 quit_signal.notify_waiters();
                 break;
             }
+            SIGHUP => {
+                info!("Reloading channels");
+                reload_signal.notify_one();
+            }
             _ => unreachable!(),
         }
     }
@@ -92,30 +166,469 @@ async fn main() -> Result<()> {
     pretty_env_logger::init_timed();
     dotenv().ok();
 
+    if env::args().skip(1).any(|arg| arg == "--migrate-only") {
+        return migrate_only().await.wrap_err("failed to migrate database");
+    }
+
     run().await.wrap_err("failed to run bot")
 }
 
+/// Apply pending migrations without starting the bot
+async fn migrate_only() -> Result<()> {
+    let db = connection_with_retry(DB_CONNECT_ATTEMPTS, DB_CONNECT_DELAY).await?;
+    let applied = migrate(&db).await?;
+
+    if applied.is_empty() {
+        println!("database is up to date");
+    }
+
+    for name in applied {
+        println!("applied {name}");
+    }
+
+    Ok(())
+}
+
 #[inline]
 fn env_var(name: &'static str) -> Result<String, Error> {
     env::var(name).map_err(|source| Error::EnvarNotSet { source, name })
 }
 
+/// Read a duration like `1h 30m` from the environment.
+fn env_duration(name: &'static str, default: StdDuration) -> Result<StdDuration, Error> {
+    match env::var(name) {
+        Ok(value) => humantime::parse_duration(&value)
+            .map_err(|source| Error::InvalidDuration { source, name }),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Parse a variable from the environment, falling back to `default` if unset.
+fn env_parse<T: FromStr>(name: &'static str, default: T) -> Result<T, Error> {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| Error::InvalidEnvar { name, value }),
+        Err(_) => Ok(default),
+    }
+}
+
+/// Read a factor between 0 and 1 from the environment.
+fn env_factor(name: &'static str, default: f32) -> Result<f32, Error> {
+    let factor = env_parse(name, default)?;
+
+    if (0.0..=1.0).contains(&factor) {
+        Ok(factor)
+    } else {
+        Err(Error::InvalidEnvar {
+            name,
+            value: factor.to_string(),
+        })
+    }
+}
+
+/// Read the happy hours from the environment. `HAPPY_HOURS` is a comma
+/// separated list of `HH:MM-HH:MM` windows in UTC.
+fn env_happy_hours() -> Result<HappyHours, Error> {
+    let windows = match env::var("HAPPY_HOURS") {
+        Ok(value) => value
+            .split(',')
+            .map(str::trim)
+            .filter(|window| !window.is_empty())
+            .map(|window| {
+                window
+                    .parse::<HappyHour>()
+                    .map_err(|_| Error::InvalidEnvar {
+                        name: "HAPPY_HOURS",
+                        value: window.to_string(),
+                    })
+            })
+            .collect::<Result<_, _>>()?,
+        Err(_) => Vec::new(),
+    };
+
+    let factor: f64 = env_parse("HAPPY_HOUR_BOOST", 2.0)?;
+    if !factor.is_finite() || factor < 1.0 {
+        return Err(Error::InvalidEnvar {
+            name: "HAPPY_HOUR_BOOST",
+            value: factor.to_string(),
+        });
+    }
+
+    Ok(HappyHours {
+        windows,
+        threshold: env_factor("HAPPY_HOUR_THRESHOLD", RARE_CHANCE)?,
+        factor,
+    })
+}
+
+/// Read the command emotes from the environment. `COMMAND_EMOTES` replaces
+/// the emotes of single commands, see [`Commands::with_overrides`].
+fn env_commands() -> Result<Commands, Error> {
+    match env::var("COMMAND_EMOTES") {
+        Ok(value) => Commands::with_overrides(&value).map_err(|entry| Error::InvalidEnvar {
+            name: "COMMAND_EMOTES",
+            value: entry,
+        }),
+        Err(_) => Ok(Commands::default()),
+    }
+}
+
+/// Parse a comma separated list of `channel=unit`, e.g. `forsen=lb,xqc=kg`.
+/// Returns the first invalid entry as error.
+fn parse_weight_units(value: &str) -> Result<HashMap<String, WeightUnit>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (channel, unit) = entry.split_once('=').ok_or_else(|| entry.to_string())?;
+            let channel = channel.trim().trim_start_matches('#').to_lowercase();
+            let unit = unit.parse().map_err(|_| entry.to_string())?;
+
+            if channel.is_empty() {
+                return Err(entry.to_string());
+            }
+
+            Ok((channel, unit))
+        })
+        .collect()
+}
+
+fn env_weight_units() -> Result<HashMap<String, WeightUnit>, Error> {
+    match env::var("CHANNEL_WEIGHT_UNITS") {
+        Ok(value) => parse_weight_units(&value).map_err(|entry| Error::InvalidEnvar {
+            name: "CHANNEL_WEIGHT_UNITS",
+            value: entry,
+        }),
+        Err(_) => Ok(HashMap::new()),
+    }
+}
+
+/// Read the minimum age of accounts fishing for the first time from the
+/// environment. Unset disables the check.
+fn env_min_account_age() -> Result<Option<Duration>, Error> {
+    let name = "REQUIRE_ACCOUNT_AGE";
+
+    match env::var(name) {
+        Ok(value) => {
+            let min_age = humantime::parse_duration(&value)
+                .map_err(|source| Error::InvalidDuration { source, name })?;
+
+            Duration::from_std(min_age)
+                .map(Some)
+                .map_err(|_| Error::InvalidEnvar { name, value })
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Read what fishing for someone else costs from the environment.
+fn env_fish_gift_cost() -> Result<f32, Error> {
+    let cost: f32 = env_parse("FISH_GIFT_COST", 10.0)?;
+
+    if cost.is_finite() && cost >= 0.0 {
+        Ok(cost)
+    } else {
+        Err(Error::InvalidEnvar {
+            name: "FISH_GIFT_COST",
+            value: cost.to_string(),
+        })
+    }
+}
+
+/// Read the outgoing message rate limit from the environment.
+fn env_rate_limit() -> Result<RateLimit, Error> {
+    let default = RateLimit::default();
+    let messages: u32 = env_parse("RATE_LIMIT_MESSAGES", default.messages)?;
+    let period = env_duration("RATE_LIMIT_PERIOD", default.period)?;
+
+    if messages == 0 {
+        return Err(Error::InvalidEnvar {
+            name: "RATE_LIMIT_MESSAGES",
+            value: messages.to_string(),
+        });
+    }
+
+    if period.is_zero() {
+        return Err(Error::InvalidEnvar {
+            name: "RATE_LIMIT_PERIOD",
+            value: humantime::format_duration(period).to_string(),
+        });
+    }
+
+    Ok(RateLimit { messages, period })
+}
+
+/// Read the catch webhook from the environment. It is disabled unless
+/// `CATCH_WEBHOOK_URL` is set.
+fn env_catch_webhook() -> Result<Option<CatchWebhook>, Error> {
+    let url = match env::var("CATCH_WEBHOOK_URL") {
+        Ok(url) => url,
+        Err(_) => return Ok(None),
+    };
+    let url = url.parse().map_err(|_| Error::InvalidEnvar {
+        name: "CATCH_WEBHOOK_URL",
+        value: url,
+    })?;
+    let min_value = match env::var("CATCH_WEBHOOK_MIN_VALUE") {
+        Ok(value) => Some(value.parse().map_err(|_| Error::InvalidEnvar {
+            name: "CATCH_WEBHOOK_MIN_VALUE",
+            value,
+        })?),
+        Err(_) => None,
+    };
+
+    Ok(Some(CatchWebhook::new(
+        url,
+        env_parse("CATCH_WEBHOOK_RARE", true)?,
+        min_value,
+    )))
+}
+
+/// Read a boolean flag from the environment. Unset variables are `false`.
+fn env_flag(name: &'static str) -> bool {
+    env::var(name)
+        .map(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+#[derive(Debug, Clone, Default)]
+struct Config {
+    /// Re-roll once when a user would catch the same rare fish twice in a row
+    avoid_repeat_rare: bool,
+    /// Log replies instead of sending them and do not write to the database
+    dry_run: bool,
+    /// Snapshot the scores of the active season once it ends within this
+    /// window. Zero disables snapshots.
+    season_snapshot_window: StdDuration,
+    /// How to handle users designated as bots trying to fish
+    bot_fishing: BotFishing,
+    /// Keep the season score of users from dropping below zero
+    score_floor: bool,
+    /// Multiplier for the fishing cooldown of subscribers, VIPs and moderators
+    subscriber_cooldown_factor: f32,
+    /// How to seed the choice of cooldown message
+    cooldown_seed: CooldownSeed,
+    /// Chance of a cast catching a second fish
+    double_catch_chance: f32,
+    /// How long fishes are cached before they are reloaded from the database
+    fish_cache_ttl: StdDuration,
+    /// Logins of users allowed to use admin whispers
+    admins: HashSet<String>,
+    /// Channel where catches are not stored and there is no cooldown
+    practice_channel: Option<String>,
+    /// How many messages may be sent to a channel before replies are held back
+    rate_limit: RateLimit,
+    /// How often a user can use the same chat command besides fishing.
+    /// Zero disables the cooldown.
+    command_cooldown: StdDuration,
+    /// Where notable catches are posted to
+    catch_webhook: Option<CatchWebhook>,
+    /// Append the season rank of the user to catch replies. This costs an
+    /// extra query per catch.
+    report_rank: bool,
+    /// Windows during which rare fishes are boosted
+    happy_hours: HappyHours,
+    /// How long messages still being handled on quit get to finish
+    drain_timeout: StdDuration,
+    /// Reply to `!bot`, `{trigger}` is replaced with the command trigger
+    bot_reply: Option<String>,
+    /// Score it costs to fish for someone else
+    fish_gift_cost: f32,
+    /// Which emote triggers which command
+    commands: Commands,
+    /// Unit weights are shown in, unless the channel has its own
+    weight_unit: WeightUnit,
+    /// Unit weights are shown in per channel
+    channel_weight_units: HashMap<String, WeightUnit>,
+    /// Twitch accounts younger than this can't fish for the first time
+    min_account_age: Option<Duration>,
+}
+
+impl Config {
+    fn from_env() -> Result<Self, Error> {
+        Ok(Self {
+            avoid_repeat_rare: env_flag("AVOID_REPEAT_RARE"),
+            dry_run: env_flag("DRY_RUN"),
+            season_snapshot_window: env_duration(
+                "SEASON_SNAPSHOT_WINDOW",
+                StdDuration::from_secs(60 * 60),
+            )?,
+            bot_fishing: env_parse("BOT_FISHING", BotFishing::default())?,
+            score_floor: env_flag("SCORE_FLOOR"),
+            subscriber_cooldown_factor: env_factor("SUBSCRIBER_COOLDOWN_FACTOR", 0.5)?,
+            cooldown_seed: env_parse("COOLDOWN_SEED", CooldownSeed::default())?,
+            double_catch_chance: env_factor("DOUBLE_CATCH_CHANCE", 0.0)?,
+            fish_cache_ttl: env_duration("FISH_CACHE_TTL", StdDuration::from_secs(60))?,
+            admins: env::var("ADMINS")
+                .as_deref()
+                .unwrap_or("chronophylos")
+                .split(',')
+                .map(|admin| admin.trim().to_lowercase())
+                .filter(|admin| !admin.is_empty())
+                .collect(),
+            practice_channel: env::var("PRACTICE_CHANNEL")
+                .ok()
+                .map(|channel| channel.trim().trim_start_matches('#').to_lowercase())
+                .filter(|channel| !channel.is_empty()),
+            rate_limit: env_rate_limit()?,
+            command_cooldown: env_duration("COMMAND_COOLDOWN", StdDuration::from_secs(5))?,
+            catch_webhook: env_catch_webhook()?,
+            report_rank: env_flag("REPORT_RANK"),
+            happy_hours: env_happy_hours()?,
+            drain_timeout: env_duration("DRAIN_TIMEOUT", DEFAULT_DRAIN_TIMEOUT)?,
+            bot_reply: env::var("BOT_REPLY")
+                .ok()
+                .map(|reply| reply.trim().to_string())
+                .filter(|reply| !reply.is_empty()),
+            fish_gift_cost: env_fish_gift_cost()?,
+            commands: env_commands()?,
+            weight_unit: env_parse("WEIGHT_UNIT", WeightUnit::default())?,
+            channel_weight_units: env_weight_units()?,
+            min_account_age: env_min_account_age()?,
+        })
+    }
+
+    fn is_practice_channel(&self, channel_login: &str) -> bool {
+        self.practice_channel.as_deref() == Some(channel_login)
+    }
+
+    fn weight_unit(&self, channel_login: &str) -> WeightUnit {
+        self.channel_weight_units
+            .get(channel_login)
+            .copied()
+            .unwrap_or(self.weight_unit)
+    }
+
+    fn bot_reply(&self) -> String {
+        self.bot_reply
+            .as_deref()
+            .unwrap_or(DEFAULT_BOT_REPLY)
+            .replace("{trigger}", &TRIGGER)
+    }
+}
+
+/// Whether `sender` is one of the configured admins
+fn is_admin(config: &Config, sender: &TwitchUserBasics) -> bool {
+    config.admins.contains(&sender.login.to_lowercase())
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum BotFishing {
+    /// Bots fish like everyone else
+    #[default]
+    Allow,
+    /// Bots are silently ignored
+    Ignore,
+    /// Bots are told that they can't fish
+    Reply,
+}
+
+impl BotFishing {
+    fn blocks(self, is_bot: bool) -> bool {
+        is_bot && self != Self::Allow
+    }
+}
+
+impl FromStr for BotFishing {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "allow" => Ok(Self::Allow),
+            "ignore" => Ok(Self::Ignore),
+            "reply" => Ok(Self::Reply),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum CooldownSeed {
+    /// The same message until the user fished again
+    #[default]
+    Timestamp,
+    /// Like `Timestamp` but mixed with the user so users that fished at the
+    /// same time get different messages
+    Hash,
+    /// A different message every time
+    Random,
+}
+
+impl CooldownSeed {
+    fn seed(self, user: &users::Model) -> u64 {
+        match self {
+            // timestamps before the epoch are negative
+            Self::Timestamp => user.last_fished.timestamp().unsigned_abs(),
+            Self::Hash => fnv1a(
+                user.id
+                    .to_le_bytes()
+                    .into_iter()
+                    .chain(user.last_fished.timestamp().to_le_bytes()),
+            ),
+            Self::Random => thread_rng().gen(),
+        }
+    }
+}
+
+/// 64 bit FNV-1a hash of `bytes`. Unlike `DefaultHasher` it is the same
+/// across releases and restarts, so the chosen message stays the same too.
+fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    bytes.into_iter().fold(OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+impl FromStr for CooldownSeed {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "timestamp" => Ok(Self::Timestamp),
+            "hash" => Ok(Self::Hash),
+            "random" => Ok(Self::Random),
+            _ => Err(()),
+        }
+    }
+}
+
+/// How often to check whether the active season is about to end
+const SEASON_SNAPSHOT_INTERVAL: StdDuration = StdDuration::from_secs(5 * 60);
+
+/// How often to checkpoint and vacuum a sqlite database
+const MAINTENANCE_INTERVAL: StdDuration = StdDuration::from_secs(60 * 60);
+
 async fn run() -> Result<()> {
-    let signals = Signals::new([SIGTERM, SIGINT, SIGQUIT]).map_err(Error::Signals)?;
+    let signals = Signals::new([SIGTERM, SIGINT, SIGQUIT, SIGHUP]).map_err(Error::Signals)?;
     let quit_signal = Arc::new(Notify::new());
+    let reload_signal = Arc::new(Notify::new());
+    let config = Config::from_env()?;
+    info!("Commands are triggered by {}", *TRIGGER);
 
-    let db = connection().await?;
+    let db = connection_with_retry(DB_CONNECT_ATTEMPTS, DB_CONNECT_DELAY).await?;
 
     info!("Running Migrations");
-    migrate(&db).await?;
+    for name in migrate(&db).await? {
+        info!("Applied migration {name}");
+    }
 
     let season_create_task = tokio::spawn({
         let db = (db).clone();
         let quit_signal = quit_signal.clone();
+        let snapshot_window = config.season_snapshot_window;
 
         async move {
             // once per week
             let mut interval = tokio::time::interval(StdDuration::from_secs(60 * 60 * 24 * 7));
+            let mut snapshot_interval = tokio::time::interval(SEASON_SNAPSHOT_INTERVAL);
+            let mut maintenance_interval = tokio::time::interval_at(
+                tokio::time::Instant::now() + MAINTENANCE_INTERVAL,
+                MAINTENANCE_INTERVAL,
+            );
 
             while !QUITTING.load(Ordering::Relaxed) {
                 select! {
@@ -133,6 +646,22 @@ async fn run() -> Result<()> {
                             _ => {}
                         }
                     }
+                    _ = snapshot_interval.tick(), if !snapshot_window.is_zero() => {
+                        match snapshot_ending_season(&db, snapshot_window).await {
+                            Ok(true) => info!("Snapshotted scores of the ending season"),
+                            Ok(false) => {}
+                            Err(err) => error!("Error snapshotting season: {err}"),
+                        }
+                    }
+                    _ = maintenance_interval.tick() => {
+                        match maintenance(&db).await {
+                            Ok(Some(reclaimed)) => {
+                                info!("Database maintenance reclaimed {reclaimed} bytes");
+                            }
+                            Ok(None) => {}
+                            Err(err) => error!("Error running database maintenance: {err}"),
+                        }
+                    }
                     _ = quit_signal.notified() => {
                         debug!("Received quitting twitch task");
                         break;
@@ -142,54 +671,147 @@ async fn run() -> Result<()> {
         }
     });
 
-    let username = env_var("USERNAME")?;
-    let client_id = env_var("CLIENT_ID")?;
-    let client_secret = env_var("CLIENT_SECRET")?;
-    let account = Account::new(db.clone(), &username).await?;
-    let credentials = RefreshingLoginCredentials::init_with_username(
-        Some(username),
-        client_id,
-        client_secret,
-        account,
-    );
-    let config = ClientConfig::new_simple(credentials);
+    let default_account = env_var("USERNAME")?;
+    let client_id = secret_var("CLIENT_ID")?;
+    let client_secret = secret_var("CLIENT_SECRET")?;
+    let wanted_channels = env_channels()?;
+    let channel_accounts = env_channel_accounts()?;
+
+    // every account gets its own client, so channels can be moved between
+    // them on reload
+    let mut usernames: Vec<_> = channel_accounts.values().cloned().collect();
+    usernames.push(default_account.clone());
+    usernames.sort_unstable();
+    usernames.dedup();
 
-    info!("Creating client");
-    let (mut incoming_messages, client) = Client::new(config);
+    let mut credentials = HashMap::new();
+    for username in usernames {
+        let account = Account::new(db.clone(), &username).await?;
+        let account_credentials = RefreshingLoginCredentials::init_with_username(
+            Some(username.clone()),
+            client_id.clone(),
+            client_secret.clone(),
+            account,
+        );
+        credentials.insert(username, account_credentials);
+    }
+
+    let account_age_gate = config.min_account_age.map(|min_age| {
+        info!(
+            "Accounts younger than {} can't fish for the first time",
+            humantime::format_duration(min_age.to_std().unwrap_or_default())
+        );
+
+        Arc::new(AccountAgeGate::new(
+            min_age,
+            Box::new(HelixAccountLookup::new(
+                client_id.clone(),
+                credentials[&default_account].clone(),
+            )),
+        ))
+    });
 
     let handle = signals.handle();
-    let signals_task = tokio::spawn(handle_signals(signals, quit_signal.clone()));
+    let signals_task = tokio::spawn(handle_signals(
+        signals,
+        quit_signal.clone(),
+        reload_signal.clone(),
+    ));
 
-    // consume the incoming messages stream
-    let twitch_task = tokio::spawn({
-        let client = client.clone();
+    if config.dry_run {
+        warn!("Dry run: replies are only logged and nothing is written to the database");
+    }
+
+    let fish_cache = Arc::new(FishCache::new(config.fish_cache_ttl));
+    let catch_repository: Arc<dyn CatchRepository> = Arc::new(DbCatchRepository {
+        db: db.clone(),
+        fish_cache: fish_cache.clone(),
+    });
+    let seen_messages = Arc::new(SeenMessages::default());
+    let command_cooldowns = Arc::new(CommandCooldowns::new(config.command_cooldown));
+    let drain_timeout = config.drain_timeout;
+    let config = Arc::new(config);
+
+    let mut clients = HashMap::new();
+    let mut twitch_tasks = Vec::new();
+    let mut ready_receivers = Vec::new();
+
+    for (username, credentials) in credentials {
+        info!("Creating client for {username}");
+        let client_config = ClientConfig::new_simple(credentials.clone());
+        let (mut incoming_messages, client) = Client::new(client_config);
+
+        let responder: Arc<dyn Responder> = if config.dry_run {
+            Arc::new(DryRunResponder)
+        } else {
+            Arc::new(TwitchResponder::new(
+                client.clone(),
+                client_id.clone(),
+                credentials,
+                RateLimiter::new(config.rate_limit),
+            ))
+        };
+
+        let (ready_tx, ready_rx) = oneshot::channel();
+        let ctx = Context {
+            db: db.clone(),
+            catch_repository: catch_repository.clone(),
+            responder,
+            config: config.clone(),
+            fish_cache: fish_cache.clone(),
+            seen_messages: seen_messages.clone(),
+            command_cooldowns: command_cooldowns.clone(),
+            account_age_gate: account_age_gate.clone(),
+        };
+        let quit_signal = quit_signal.clone();
+
+        // consume the incoming messages stream
+        twitch_tasks.push(tokio::spawn(async move {
+            let mut ready_tx = Some(ready_tx);
+            // handlers run as tasks, so a quit waits for them instead of cutting
+            // off a catch that is being written
+            let mut handlers = JoinSet::new();
 
-        async move {
             while !QUITTING.load(Ordering::Relaxed) {
                 select! {
                     maybe_message = incoming_messages.recv() => {
                         if let Some(message) = maybe_message {
-                            if let Err(err) = handle_server_message(&db, &client, message).await {
-                                error!("Error handling message: {err}");
+                            if matches!(message, ServerMessage::GlobalUserState(_)) {
+                                if let Some(ready_tx) = ready_tx.take() {
+                                    ready_tx.send(()).ok();
+                                }
                             }
 
+                            let ctx = ctx.clone();
+
+                            handlers.spawn(async move {
+                                if let Err(err) = handle_server_message(&ctx, message).await {
+                                    error!("Error handling message: {err}");
+                                }
+                            });
                         } else {
                             break;
                         }
                     }
+                    Some(result) = handlers.join_next() => drain::report(result),
                     _ = quit_signal.notified() => {
                         debug!("Received quitting twitch task");
                         break;
                     }
                 }
             }
-        }
-    });
 
-    let wanted_channels = env_var("CHANNELS")?
-        .split(',')
-        .map(|channel| channel.trim().to_string())
-        .collect::<HashSet<_>>();
+            drain::drain(&mut handlers, drain_timeout).await;
+        }));
+
+        client.connect().await;
+        ready_receivers.push(ready_rx);
+        clients.insert(username, client);
+    }
+
+    for ready_rx in ready_receivers {
+        wait_until_ready(ready_rx).await;
+    }
 
     debug!(
         "Wanting to join channels {}",
@@ -200,13 +822,28 @@ async fn run() -> Result<()> {
             .join(", ")
     );
 
-    client.set_wanted_channels(wanted_channels)?;
+    let assignment = ChannelAssignment {
+        default_account,
+        channel_accounts,
+    };
+    assignment.apply(&clients, &wanted_channels)?;
+
+    let channels_task = tokio::spawn(reload_channels(
+        clients,
+        assignment,
+        wanted_channels,
+        reload_signal,
+        quit_signal,
+    ));
 
     // keep the tokio executor alive.
     // If you return instead of waiting the background task will exit.
-    twitch_task.await?;
+    for twitch_task in twitch_tasks {
+        twitch_task.await?;
+    }
 
     season_create_task.await?;
+    channels_task.await?;
 
     // Terminate the signal stream.
     handle.close();
@@ -215,23 +852,229 @@ async fn run() -> Result<()> {
     Ok(())
 }
 
-async fn handle_server_message(
-    db: &DatabaseConnection,
-    client: &Client,
-    message: ServerMessage,
-) -> Result<()> {
+const READY_TIMEOUT: StdDuration = StdDuration::from_secs(30);
+
+/// Read the channels to join from the environment
+fn env_channels() -> Result<HashSet<String>, Error> {
+    let value = env_var("CHANNELS")?;
+    let channels = parse_channels(&value);
+
+    if channels.is_empty() {
+        return Err(Error::InvalidEnvar {
+            name: "CHANNELS",
+            value,
+        });
+    }
+
+    Ok(channels)
+}
+
+/// Parse a comma separated list of `channel=username`, e.g.
+/// `forsen=fishingebot,xqc=otherbot`. Returns the first invalid entry as error.
+fn parse_channel_accounts(value: &str) -> Result<HashMap<String, String>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (channel, username) = entry.split_once('=').ok_or_else(|| entry.to_string())?;
+            let channel = channel.trim().trim_start_matches('#').to_lowercase();
+            let username = username.trim().to_lowercase();
+
+            if channel.is_empty() || username.is_empty() {
+                return Err(entry.to_string());
+            }
+
+            Ok((channel, username))
+        })
+        .collect()
+}
+
+/// Read which account joins which channel from the environment
+fn env_channel_accounts() -> Result<HashMap<String, String>, Error> {
+    match env::var("CHANNEL_ACCOUNTS") {
+        Ok(value) => parse_channel_accounts(&value).map_err(|entry| Error::InvalidEnvar {
+            name: "CHANNEL_ACCOUNTS",
+            value: entry,
+        }),
+        Err(_) => Ok(HashMap::new()),
+    }
+}
+
+/// Which account joins which channel
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ChannelAssignment {
+    /// Account joining channels without an account of their own
+    default_account: String,
+    channel_accounts: HashMap<String, String>,
+}
+
+impl ChannelAssignment {
+    fn account(&self, channel: &str) -> &str {
+        self.channel_accounts
+            .get(channel)
+            .unwrap_or(&self.default_account)
+    }
+
+    /// The `channels` each account joins. Accounts without channels are
+    /// missing.
+    fn assign(&self, channels: &HashSet<String>) -> HashMap<String, HashSet<String>> {
+        let mut assigned: HashMap<String, HashSet<String>> = HashMap::new();
+
+        for channel in channels {
+            assigned
+                .entry(self.account(channel).to_string())
+                .or_default()
+                .insert(channel.clone());
+        }
+
+        assigned
+    }
+
+    /// Make each of the `clients`, keyed by their account, join its share of
+    /// `channels` and part the rest. Nothing is changed if a channel name is
+    /// invalid or belongs to an account without a client.
+    fn apply(
+        &self,
+        clients: &HashMap<String, Client>,
+        channels: &HashSet<String>,
+    ) -> Result<(), Error> {
+        for channel in channels {
+            twitch_irc::validate::validate_login(channel)?;
+        }
+
+        let mut assigned = self.assign(channels);
+        if let Some(account) = assigned
+            .keys()
+            .find(|account| !clients.contains_key(*account))
+        {
+            return Err(Error::UnknownAccount(account.clone()));
+        }
+
+        for (account, client) in clients {
+            client.set_wanted_channels(assigned.remove(account).unwrap_or_default())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Channels in `wanted` but not in `current`, and channels in `current` but
+/// not in `wanted`, both sorted.
+fn diff_channels<'a>(
+    current: &'a HashSet<String>,
+    wanted: &'a HashSet<String>,
+) -> (Vec<&'a str>, Vec<&'a str>) {
+    let mut joined: Vec<_> = wanted.difference(current).map(String::as_str).collect();
+    let mut parted: Vec<_> = current.difference(wanted).map(String::as_str).collect();
+    joined.sort_unstable();
+    parted.sort_unstable();
+
+    (joined, parted)
+}
+
+/// Re-read `CHANNELS` and `CHANNEL_ACCOUNTS` from `.env` and the environment
+/// whenever `reload_signal` is notified and join or part channels to match.
+/// The current channels are kept if the new ones are invalid. Accounts can
+/// only be added on restart.
+async fn reload_channels(
+    clients: HashMap<String, Client>,
+    mut assignment: ChannelAssignment,
+    mut channels: HashSet<String>,
+    reload_signal: Arc<Notify>,
+    quit_signal: Arc<Notify>,
+) {
+    while !QUITTING.load(Ordering::Relaxed) {
+        select! {
+            _ = reload_signal.notified() => {}
+            _ = quit_signal.notified() => {
+                debug!("Received quitting channels task");
+                break;
+            }
+        }
+
+        dotenvy::dotenv_override().ok();
+
+        let (wanted, channel_accounts) = match env_channels().and_then(|wanted| {
+            env_channel_accounts().map(|channel_accounts| (wanted, channel_accounts))
+        }) {
+            Ok(reloaded) => reloaded,
+            Err(err) => {
+                error!("Keeping current channels, could not reload them: {err}");
+                continue;
+            }
+        };
+
+        let wanted_assignment = ChannelAssignment {
+            channel_accounts,
+            ..assignment.clone()
+        };
+        if let Err(err) = wanted_assignment.apply(&clients, &wanted) {
+            error!("Keeping current channels, could not reload them: {err}");
+            continue;
+        }
+
+        let (joined, parted) = diff_channels(&channels, &wanted);
+        if joined.is_empty() && parted.is_empty() {
+            info!("Channels are unchanged");
+        }
+        for channel in joined {
+            info!(
+                "Joining #{channel} as {}",
+                wanted_assignment.account(channel)
+            );
+        }
+        for channel in parted {
+            info!("Parting #{channel}");
+        }
+
+        channels = wanted;
+        assignment = wanted_assignment;
+    }
+}
+
+/// Wait for twitch to send `GLOBALUSERSTATE` after we are authenticated.
+async fn wait_until_ready(mut ready: oneshot::Receiver<()>) {
+    debug!("Waiting for twitch to be ready");
+
+    if timeout(READY_TIMEOUT, &mut ready).await.is_err() {
+        warn!("Twitch is not ready after {READY_TIMEOUT:?}, still waiting");
+        ready.await.ok();
+    }
+
+    info!("Twitch is ready");
+}
+
+/// Everything the handlers of one account share
+#[derive(Clone)]
+struct Context {
+    db: DatabaseConnection,
+    catch_repository: Arc<dyn CatchRepository>,
+    responder: Arc<dyn Responder>,
+    config: Arc<Config>,
+    fish_cache: Arc<FishCache>,
+    seen_messages: Arc<SeenMessages>,
+    command_cooldowns: Arc<CommandCooldowns>,
+    account_age_gate: Option<Arc<AccountAgeGate>>,
+}
+
+async fn handle_server_message(ctx: &Context, message: ServerMessage) -> Result<()> {
     trace!("Received message: {:?}", &message);
 
     match message {
         ServerMessage::Privmsg(msg) => {
-            handle_privmsg(db, client, &msg).await?;
+            handle_privmsg(ctx, &msg).await?;
+        }
+        ServerMessage::Whisper(msg) => {
+            handle_whisper(ctx, &msg).await?;
         }
         ServerMessage::Notice(msg) => {
             warn!(
                 "Notice: {} {}",
-                msg.channel_login.unwrap_or_else(|| "Server".to_string()),
+                msg.channel_login.as_deref().unwrap_or("Server"),
                 msg.message_text
             );
+            ctx.responder.notice(&msg).await?;
         }
         ServerMessage::Reconnect(_) => {
             info!("Twitch Server requested a reconnect");
@@ -241,327 +1084,3566 @@ async fn handle_server_message(
     Ok(())
 }
 
-static COMMAND_REGEX: Lazy<Regex> =
-    Lazy::new(|| Regex::new(r"^((?P<emote>\S+)\s+)?Fishinge( (?P<args>.*))?$").unwrap());
-const WEB_URL: &str = "https://fishinge.chronophylos.com";
-
-async fn handle_privmsg(
-    db: &DatabaseConnection,
-    client: &Client,
-    msg: &PrivmsgMessage,
-) -> Result<()> {
-    if msg.message_text.starts_with("!bot") {
-        client
-            .say_in_reply_to(
-                msg,
-                "this micro bot allows you to fish. Type `❓ Fishinge` for help.".to_string(),
-            )
-            .await
-            .map_err(Error::ReplyToMessage)?;
+async fn handle_whisper(ctx: &Context, msg: &WhisperMessage) -> Result<()> {
+    let Context {
+        db,
+        responder,
+        config,
+        fish_cache,
+        ..
+    } = ctx;
 
+    if !is_admin(config, &msg.sender) {
+        debug!(
+            "Ignoring whisper from {}: {}",
+            msg.sender.login, msg.message_text
+        );
         return Ok(());
     }
 
-    if let Some(captures) = COMMAND_REGEX.captures(&msg.message_text) {
-        match captures.name("emote").map(|m| m.as_str()) {
-            Some("🐱") => {
-                client
-                    .say_in_reply_to(msg, "No catfishing!".to_string())
-                    .await
-                    .map_err(Error::ReplyToMessage)?;
+    let reply = match msg.message_text.trim() {
+        "reload-fishes" => {
+            fish_cache.invalidate();
+            let season = get_active_season(db).await?;
+            let fishes = fish_cache.get(db, &season).await?;
 
-                Ok(())
-            }
-            Some("🔍") | Some("🔎") => {
-                client
-                    .say_in_reply_to(msg, format!("fishes are here {WEB_URL}/fishes"))
-                    .await
-                    .map_err(Error::ReplyToMessage)?;
+            format!("reloaded {} fishes of season {}", fishes.len(), season.name)
+        }
+        "stats" => {
+            let users = Users::find().count(db).await?;
+            let catches = Catches::find().count(db).await?;
 
-                Ok(())
+            format!(
+                "{} users caught {} fishes, {} replies failed",
+                format_thousands(users),
+                format_thousands(catches),
+                format_thousands(failed_replies())
+            )
+        }
+        text if text.starts_with("give-bait") => {
+            match parse_give_bait(text.trim_start_matches("give-bait")) {
+                Some((user_name, amount)) => match give_bait(db, &user_name, amount).await? {
+                    Some(total) => format!("{user_name} has {total} bait now"),
+                    None => format!("there is no fisher called {user_name}"),
+                },
+                None => "usage: give-bait user amount".to_string(),
             }
-            Some("🏆") => {
-                client
-                    .say_in_reply_to(
-                        msg,
-                        format!("check out the leaderboard at {WEB_URL}/leaderboard"),
-                    )
-                    .await
-                    .map_err(Error::ReplyToMessage)?;
+        }
+        _ => "unknown command, try reload-fishes, stats or give-bait".to_string(),
+    };
 
-                Ok(())
-            }
-            Some("🤖") => {
-                if &msg.sender.login != "chronophylos" {
-                    return Ok(());
-                }
+    responder.whisper(&msg.sender, reply).await?;
 
-                if let Some(args) = captures.name("args") {
-                    let target = args
-                        .as_str()
-                        .split_whitespace()
-                        .next()
-                        .unwrap()
-                        .trim_start_matches('@')
-                        .to_lowercase();
+    Ok(())
+}
+
+/// Word that triggers commands, `Fishinge` unless `TRIGGER` is set
+static TRIGGER: Lazy<String> = Lazy::new(|| {
+    env::var("TRIGGER")
+        .ok()
+        .map(|trigger| trigger.trim().to_string())
+        .filter(|trigger| !trigger.is_empty())
+        .unwrap_or_else(|| DEFAULT_TRIGGER.to_string())
+});
+static COMMAND_REGEX: Lazy<Regex> = Lazy::new(|| command_regex(&TRIGGER));
+const WEB_URL: &str = "https://fishinge.chronophylos.com";
+
+/// Reply to `!bot` if `BOT_REPLY` is not set
+const DEFAULT_BOT_REPLY: &str = "this micro bot allows you to fish. Type `❓ {trigger}` for help.";
+
+async fn handle_privmsg(ctx: &Context, msg: &PrivmsgMessage) -> Result<()> {
+    let Context {
+        db,
+        catch_repository,
+        responder,
+        config,
+        fish_cache,
+        seen_messages,
+        command_cooldowns,
+        account_age_gate,
+        ..
+    } = ctx;
+
+    if !seen_messages.insert(&msg.message_id) {
+        debug!("Ignoring redelivered message {}", msg.message_id);
+
+        return Ok(());
+    }
+
+    // fishing has its own cooldown. Commands with several emotes share one.
+    let command = if msg.message_text.starts_with("!bot") {
+        Some("!bot")
+    } else {
+        COMMAND_REGEX
+            .captures(&msg.message_text)
+            .and_then(|captures| captures.name("emote"))
+            .and_then(|emote| config.commands.get(emote.as_str()))
+            .map(Command::name)
+    };
+    if let Some(command) = command {
+        if !command_cooldowns.try_use(&msg.sender.login, command, Instant::now()) {
+            debug!("Ignoring {command} of {} on cooldown", msg.sender.login);
+
+            return Ok(());
+        }
+    }
+
+    if msg.message_text.starts_with("!bot") {
+        responder.reply(msg, config.bot_reply()).await?;
+
+        return Ok(());
+    }
+
+    if let Some(captures) = COMMAND_REGEX.captures(&msg.message_text) {
+        let command = match captures.name("emote") {
+            Some(emote) => match config.commands.get(emote.as_str()) {
+                Some(command) => Some(command),
+                None => return Ok(()),
+            },
+            None => None,
+        };
+
+        match command {
+            Some(Command::Catfishing) => {
+                responder.reply(msg, "No catfishing!".to_string()).await?;
+
+                Ok(())
+            }
+            Some(Command::Fishes) => {
+                responder
+                    .reply(msg, format!("fishes are here {WEB_URL}/fishes"))
+                    .await?;
 
-                    let epoch = DateTime::<Utc>::from_utc(
-                        NaiveDateTime::from_timestamp_opt(61, 0).unwrap(),
-                        Utc,
+                Ok(())
+            }
+            Some(Command::Leaderboard) => {
+                responder
+                    .reply(
+                        msg,
+                        format!("check out the leaderboard at {WEB_URL}/leaderboard"),
                     )
-                    .into();
+                    .await?;
+
+                Ok(())
+            }
+            Some(Command::DesignateBot) => {
+                if !is_admin(config, &msg.sender) {
+                    debug!("Ignoring bot command of {}", msg.sender.login);
+
+                    return Ok(());
+                }
+
+                if let Some(args) = captures.name("args") {
+                    let target = args
+                        .as_str()
+                        .split_whitespace()
+                        .next()
+                        .unwrap()
+                        .trim_start_matches('@')
+                        .to_lowercase();
 
                     let user = users::ActiveModel {
                         name: ActiveValue::set(target.to_string()),
                         is_bot: ActiveValue::set(true),
-                        last_fished: ActiveValue::set(epoch),
+                        last_fished: ActiveValue::set(never_fished()),
                         ..Default::default()
                     };
 
-                    users::Entity::insert(user)
-                        .on_conflict(
-                            // on conflict do update
-                            OnConflict::column(users::Column::Name)
-                                .update_column(users::Column::IsBot)
-                                .to_owned(),
-                        )
-                        .exec(db)
+                    if !config.dry_run {
+                        users::Entity::insert(user)
+                            .on_conflict(
+                                // on conflict do update
+                                OnConflict::column(users::Column::Name)
+                                    .update_column(users::Column::IsBot)
+                                    .to_owned(),
+                            )
+                            .exec(db)
+                            .await?;
+                    }
+
+                    responder
+                        .reply(msg, format!("designated {} as bot", target))
                         .await?;
+                }
+
+                Ok(())
+            }
+            Some(Command::Population) => {
+                let season = get_active_season(db).await?;
+                let fishes = fish_cache.get(db, &season).await?;
+                let population = fishes.population();
+
+                responder
+                    .reply(
+                        msg,
+                        format!(
+                            "the sea holds {} fish across {} species",
+                            format_thousands(population.into()),
+                            fishes.len()
+                        ),
+                    )
+                    .await?;
+
+                Ok(())
+            }
+            Some(Command::Recount) => {
+                if !is_admin(config, &msg.sender) {
+                    debug!("Ignoring recount command of {}", msg.sender.login);
 
-                    client
-                        .say_in_reply_to(msg, format!("designated {} as bot", target))
-                        .await
-                        .map_err(Error::ReplyToMessage)?;
+                    return Ok(());
+                }
+
+                // reloading the fishes recounts the population
+                fish_cache.invalidate();
+                let season = get_active_season(db).await?;
+                let fishes = fish_cache.get(db, &season).await?;
+
+                responder
+                    .reply(msg, recount_reply(fishes.population(), &fishes))
+                    .await?;
+
+                Ok(())
+            }
+            Some(Command::Messages) => {
+                if !is_admin(config, &msg.sender) {
+                    debug!("Ignoring messages command of {}", msg.sender.login);
+
+                    return Ok(());
+                }
+
+                let args = captures.name("args").map_or("", |args| args.as_str());
+
+                match parse_message_command(args) {
+                    Some(MessageCommand::Add(text)) => {
+                        let message = messages::ActiveModel {
+                            text: ActiveValue::set(text.to_string()),
+                            r#type: ActiveValue::set(MessageType::Cooldown),
+                            ..Default::default()
+                        };
+
+                        if !config.dry_run {
+                            message.insert(db).await?;
+                        }
+
+                        responder
+                            .reply(msg, format!("added cooldown message \"{text}\""))
+                            .await?;
+                    }
+                    Some(MessageCommand::List) => {
+                        let count = Messages::find()
+                            .filter(messages::Column::Type.eq(MessageType::Cooldown))
+                            .count(db)
+                            .await?;
+
+                        responder
+                            .reply(msg, format!("there are {count} cooldown messages"))
+                            .await?;
+                    }
+                    None => {
+                        responder
+                            .reply(
+                                msg,
+                                format!("usage: 💬 {0} add <message> | 💬 {0} list", *TRIGGER),
+                            )
+                            .await?;
+                    }
                 }
 
                 Ok(())
             }
-            Some("❓") => {
-                client
-                    .say_in_reply_to(msg, format!("the list of commands is here {WEB_URL}"))
-                    .await
-                    .map_err(Error::ReplyToMessage)?;
+            Some(Command::Help) => {
+                responder
+                    .reply(msg, format!("the list of commands is here {WEB_URL}"))
+                    .await?;
 
                 Ok(())
             }
-            Some("💎") => {
+            Some(Command::MostValuable) => {
                 let query: Option<(catches::Model, Option<fishes::Model>)> = Catches::find()
                     .inner_join(Users)
                     .filter(users::Column::Name.eq(msg.sender.login.to_lowercase()))
                     .order_by_desc(catches::Column::Value)
                     .find_also_related(Fishes)
+                    // gifted score is not a catch
+                    .filter(fishes::Column::Name.ne(GIFT_FISH))
                     .one(db)
                     .await?;
 
                 if let Some((catch_model, Some(fish_model))) = query {
-                    let catch = Catch {
-                        fish_name: fish_model.name,
-                        weight: catch_model.weight,
-                        value: catch_model.value,
-                    };
+                    let catch = Catch::from_model(catch_model, fish_model.name);
 
-                    client
-                        .say_in_reply_to(msg, format!("your most valuable catch is {}", catch))
-                        .await
-                        .map_err(Error::ReplyToMessage)?;
+                    responder
+                        .reply(
+                            msg,
+                            format!(
+                                "your most valuable catch is {}",
+                                catch.in_unit(config.weight_unit(&msg.channel_login))
+                            ),
+                        )
+                        .await?;
                 } else {
-                    client
-                        .say_in_reply_to(msg, "you did not catch any fish yet".to_string())
-                        .await
-                        .map_err(Error::ReplyToMessage)?;
+                    responder
+                        .reply(msg, "you did not catch any fish yet".to_string())
+                        .await?;
                 };
 
                 Ok(())
             }
-            Some("💰") => {
-                #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
-                enum QueryAs {
-                    Score,
-                }
+            Some(Command::Luckiest) => {
+                let caught = luckiest_catch_query(&msg.sender.login)
+                    .into_model::<CaughtFish>()
+                    .all(db)
+                    .await?;
+                let populations =
+                    bundle_populations_query(caught.iter().map(|fish| fish.bundle_id))
+                        .into_model::<BundlePopulation>()
+                        .all(db)
+                        .await?
+                        .into_iter()
+                        .map(|bundle| (bundle.bundle_id, bundle.population))
+                        .collect();
 
-                let query: Option<f32> = Catches::find()
-                    .inner_join(Users)
-                    .filter(users::Column::Name.eq(msg.sender.login.to_lowercase()))
-                    .select_only()
-                    .column_as(catches::Column::Value.sum(), "score")
-                    .into_values::<_, QueryAs>()
+                responder
+                    .reply(msg, luckiest_catch_reply(&caught, &populations))
+                    .await?;
+
+                Ok(())
+            }
+            Some(Command::Aquarium) => {
+                let aquarium = aquarium_query(&msg.sender.login)
+                    .into_model::<AquariumEntry>()
+                    .all(db)
+                    .await?;
+
+                responder.reply(msg, aquarium_reply(&aquarium)).await?;
+
+                Ok(())
+            }
+            Some(Command::Bait) => {
+                let bait = bait_query(&msg.sender.login).one(db).await?;
+
+                responder
+                    .reply(msg, bait_reply(bait.map_or(0, |bait| bait.amount)))
+                    .await?;
+
+                Ok(())
+            }
+            Some(Command::AverageWeight) => {
+                let name = match captures.name("args") {
+                    Some(args) => args.as_str().trim(),
+                    None => return Ok(()),
+                };
+
+                let fish = match Fishes::find()
+                    .filter(fishes::Column::Name.eq(name))
                     .one(db)
                     .await?
-                    .flatten();
+                {
+                    Some(fish) => fish,
+                    None => {
+                        responder
+                            .reply(msg, format!("there is no fish called {name}"))
+                            .await?;
+
+                        return Ok(());
+                    }
+                };
 
-                if let Some(score) = query {
-                    client
-                        .say_in_reply_to(msg, format!("your current score is ${score:.2}"))
-                        .await
-                        .map_err(Error::ReplyToMessage)?;
+                let has_weight = fish.min_weight > f32::EPSILON && fish.max_weight > f32::EPSILON;
+                let average = if has_weight {
+                    average_weight_query(fish.id)
+                        .into_model::<AverageWeight>()
+                        .one(db)
+                        .await?
                 } else {
-                    client
-                        .say_in_reply_to(msg, "you did not catch any fish yet".to_string())
-                        .await
-                        .map_err(Error::ReplyToMessage)?;
+                    None
                 };
 
+                responder
+                    .reply(
+                        msg,
+                        average_weight_reply(
+                            &fish.name,
+                            has_weight,
+                            average,
+                            config.weight_unit(&msg.channel_login),
+                        ),
+                    )
+                    .await?;
+
                 Ok(())
             }
-            None => handle_fishinge(db, client, msg).await,
-            _ => Ok(()),
-        }
-    } else {
-        Ok(())
-    }
-}
+            Some(Command::Score) => {
+                let totals = catch_totals_query(&msg.sender.login)
+                    .into_model::<CatchTotals>()
+                    .one(db)
+                    .await?;
 
-pub static COOLDOWN: Lazy<Duration> = Lazy::new(|| Duration::hours(4));
+                responder.reply(msg, score_reply(totals)).await?;
 
-async fn handle_fishinge(
-    db: &DatabaseConnection,
-    client: &Client,
-    msg: &PrivmsgMessage,
-) -> Result<()> {
-    let now = Utc::now().into();
-    // TODO: remove unwrap
-    let mut rng = StdRng::from_rng(thread_rng()).unwrap();
+                Ok(())
+            }
+            Some(Command::AverageCatch) => {
+                let average = catch_totals_query(&msg.sender.login)
+                    .into_model::<CatchTotals>()
+                    .one(db)
+                    .await?;
 
-    // get user from database
-    let user = if let Some(user) = Users::find()
-        .filter(users::Column::Name.eq(msg.sender.login.to_lowercase()))
-        .one(db)
-        .await?
-    {
-        // cooldown
-        let cooled_off = user.last_fished + *COOLDOWN;
-        if cooled_off > now {
-            let cooldown = humantime::format_duration(StdDuration::from_secs(
-                (cooled_off - now).num_seconds() as u64,
-            ));
+                responder.reply(msg, average_catch_reply(average)).await?;
+
+                Ok(())
+            }
+            Some(Command::NextSeason) => {
+                if !is_admin(config, &msg.sender) {
+                    debug!("Ignoring next season command of {}", msg.sender.login);
 
-            let mut biased_rng = StdRng::seed_from_u64(user.last_fished.timestamp() as u64);
+                    return Ok(());
+                }
+
+                info!("{} triggered the next season", msg.sender.login);
+
+                let reply = if has_next_season(db).await? {
+                    "the next season is already scheduled".to_string()
+                } else if config.dry_run {
+                    "dry run: not creating the next season".to_string()
+                } else {
+                    next_season_reply(&create_next_season(db).await?)
+                };
 
-            #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
-            enum QueryAs {
-                Text,
+                responder.reply(msg, reply).await?;
+
+                Ok(())
             }
+            Some(Command::Gift) => {
+                let args = captures.name("args").map_or("", |args| args.as_str());
 
-            let messages: Vec<String> = Messages::find()
-                .filter(messages::Column::Type.eq(MessageType::Cooldown))
-                .into_values::<_, QueryAs>()
-                .all(db)
-                .await?;
+                if let Some(target) = parse_fish_for(args) {
+                    let reply = if target == msg.sender.login.to_lowercase() {
+                        "just fish yourself".to_string()
+                    } else if config.is_practice_channel(&msg.channel_login) {
+                        "gifts are not possible in this channel".to_string()
+                    } else {
+                        match active_season_or_rollover(db).await? {
+                            Some(season) => {
+                                let outcome = fish_for(
+                                    db,
+                                    config,
+                                    fish_cache,
+                                    account_age_gate.as_deref(),
+                                    msg,
+                                    &target,
+                                    &season,
+                                )
+                                .await?;
+
+                                if matches!(outcome, FishForOutcome::SenderIsBot) {
+                                    debug!("Ignoring bot {} trying to fish", msg.sender.login);
+
+                                    if config.bot_fishing != BotFishing::Reply {
+                                        return Ok(());
+                                    }
+                                }
+
+                                fish_for_reply(
+                                    outcome,
+                                    &target,
+                                    config.fish_gift_cost,
+                                    config.weight_unit(&msg.channel_login),
+                                )
+                            }
+                            None => "fishing is closed between seasons".to_string(),
+                        }
+                    };
 
-            if messages.is_empty() {
-                return Err(eyre!("no cooldown messages found in database"));
+                    responder.reply(msg, reply).await?;
+
+                    return Ok(());
+                }
+
+                let gift = match parse_gift(args) {
+                    Some(gift) => gift,
+                    None => {
+                        responder
+                            .reply(msg, format!("usage: 🎁 {} @user [amount]", *TRIGGER))
+                            .await?;
+
+                        return Ok(());
+                    }
+                };
+
+                if let Err(reason) = gift.validate(&msg.sender.login) {
+                    responder.reply(msg, reason.to_string()).await?;
+
+                    return Ok(());
+                }
+
+                if config.is_practice_channel(&msg.channel_login) {
+                    responder
+                        .reply(msg, "gifts are not possible in this channel".to_string())
+                        .await?;
+
+                    return Ok(());
+                }
+
+                let season = match active_season_or_rollover(db).await? {
+                    Some(season) => season,
+                    None => {
+                        responder
+                            .reply(msg, "gifting is closed between seasons".to_string())
+                            .await?;
+
+                        return Ok(());
+                    }
+                };
+
+                let reply = match send_gift(db, &msg.sender.login, &gift, &season, !config.dry_run)
+                    .await?
+                {
+                    GiftOutcome::Sent => format!(
+                        "you gifted {} to {}",
+                        format_money(gift.amount),
+                        gift.target
+                    ),
+                    GiftOutcome::UnknownTarget => {
+                        format!("there is no fisher called {}", gift.target)
+                    }
+                    GiftOutcome::InsufficientScore(score) => {
+                        format!("you only have {}", format_money(score))
+                    }
+                };
+
+                responder.reply(msg, reply).await?;
+
+                Ok(())
             }
+            Some(Command::TopCatch) => {
+                let top_catch = top_catch_query().into_model::<TopCatch>().one(db).await?;
 
-            let message = messages
-                .choose(&mut biased_rng)
-                .unwrap()
-                .replace("{cooldown}", &cooldown.to_string());
+                responder
+                    .reply(
+                        msg,
+                        top_catch_reply(top_catch, config.weight_unit(&msg.channel_login)),
+                    )
+                    .await?;
 
-            client
-                .say_in_reply_to(msg, message)
-                .await
-                .map_err(Error::ReplyToMessage)?;
+                Ok(())
+            }
+            Some(Command::WhatIf) => {
+                let score: Option<f32> = score_query(&msg.sender.login, false)
+                    .into_values::<_, ScoreQueryAs>()
+                    .one(db)
+                    .await?
+                    .flatten();
+                let score_without_junk: Option<f32> = score_query(&msg.sender.login, true)
+                    .into_values::<_, ScoreQueryAs>()
+                    .one(db)
+                    .await?
+                    .flatten();
 
-            return Ok(());
-        }
-        users::ActiveModel {
-            last_fished: ActiveValue::set(now),
-            ..user.into()
+                responder
+                    .reply(msg, what_if_reply(score, score_without_junk))
+                    .await?;
+
+                Ok(())
+            }
+            Some(Command::Gap) => {
+                let reply = match active_season_or_rollover(db).await? {
+                    Some(season) => {
+                        let leader = season_leader_query(season.id)
+                            .into_model::<SeasonLeader>()
+                            .one(db)
+                            .await?;
+                        let score = match find_user(db, &msg.sender.login.to_lowercase()).await? {
+                            Some(user) => season_score_query(user.id, season.id)
+                                .into_values::<_, ScoreQueryAs>()
+                                .one(db)
+                                .await?
+                                .flatten(),
+                            None => None,
+                        };
+
+                        gap_reply(&msg.sender.login, score, leader.as_ref())
+                    }
+                    None => "there is no active season".to_string(),
+                };
+
+                responder.reply(msg, reply).await?;
+
+                Ok(())
+            }
+            None => {
+                handle_fishinge(
+                    catch_repository.as_ref(),
+                    responder.as_ref(),
+                    config,
+                    account_age_gate.as_deref(),
+                    msg,
+                )
+                .await
+            }
         }
-        .update(db)
-        .await?
     } else {
-        // create user
-        let user = users::ActiveModel {
-            name: ActiveValue::set(msg.sender.login.to_lowercase()),
-            last_fished: ActiveValue::set(now),
-            is_bot: ActiveValue::set(false),
-            ..Default::default()
-        };
-        user.insert(db).await?
-    };
+        Ok(())
+    }
+}
 
-    let season = get_active_season(db).await?;
-    let fishes = get_fishes(db, &season).await?;
+#[derive(Debug, PartialEq, Eq)]
+enum MessageCommand<'a> {
+    /// Add a cooldown message. `{cooldown}` is replaced with the remaining
+    /// cooldown when the message is sent.
+    Add(&'a str),
+    List,
+}
 
-    if fishes.is_empty() {
-        return Err(eyre!("no fishes found in database"));
+fn parse_message_command(args: &str) -> Option<MessageCommand<'_>> {
+    let args = args.trim();
+    let (command, rest) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+
+    match command {
+        "add" if !rest.trim().is_empty() => Some(MessageCommand::Add(rest.trim())),
+        "list" => Some(MessageCommand::List),
+        _ => None,
     }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+enum ScoreQueryAs {
+    Score,
+}
 
-    let fish = fishes.choose_weighted(&mut rng, |fish| fish.count).unwrap();
+/// Sum the value of the catches of the user with `user_id` in one season.
+fn season_score_query(user_id: i32, season_id: i32) -> Select<Catches> {
+    Catches::find()
+        .filter(catches::Column::UserId.eq(user_id))
+        .filter(catches::Column::SeasonId.eq(season_id))
+        .select_only()
+        .column_as(catches::Column::Value.sum(), ScoreQueryAs::Score)
+}
 
-    info!("{} is fishing for {fish}", msg.sender.name);
+/// Users with a season score above `score`. Bots are left out like on the
+/// season leaderboard.
+fn users_ahead_query(season_id: i32, score: f32) -> Select<Catches> {
+    Catches::find()
+        .inner_join(Users)
+        .filter(catches::Column::SeasonId.eq(season_id))
+        .filter(users::Column::IsBot.eq(false))
+        .group_by(catches::Column::UserId)
+        .having(Expr::expr(Func::sum(Expr::col((Catches, catches::Column::Value)))).gt(score))
+        .select_only()
+        .column(catches::Column::UserId)
+}
 
-    let catch = fish.catch();
+#[derive(Debug, FromQueryResult)]
+struct SeasonLeader {
+    name: String,
+    score: f32,
+}
 
-    info!("{} caught {catch}", msg.sender.name);
+/// The user with the highest score in one season. Bots are left out like on
+/// the season leaderboard.
+fn season_leader_query(season_id: i32) -> Select<Catches> {
+    Catches::find()
+        .inner_join(Users)
+        .filter(catches::Column::SeasonId.eq(season_id))
+        .filter(users::Column::IsBot.eq(false))
+        .group_by(users::Column::Id)
+        .order_by_desc(catches::Column::Value.sum())
+        .select_only()
+        .column(users::Column::Name)
+        .column_as(catches::Column::Value.sum(), "score")
+        .limit(1)
+}
 
-    catches::ActiveModel {
-        user_id: ActiveValue::set(user.id),
-        fish_id: ActiveValue::set(fish.id),
-        weight: ActiveValue::set(catch.weight),
-        caught_at: ActiveValue::set(now),
-        value: ActiveValue::set(catch.value),
-        season_id: ActiveValue::set(season.id),
-        ..Default::default()
+/// Reply to `🥈` with how far `user_name` with a season `score` is behind
+/// the `leader`.
+fn gap_reply(user_name: &str, score: Option<f32>, leader: Option<&SeasonLeader>) -> String {
+    match (score, leader) {
+        (None, _) => "you did not catch any fish this season yet".to_string(),
+        (Some(_), None) => "you are not on the leaderboard".to_string(),
+        (Some(score), Some(leader))
+            if leader.name == user_name.to_lowercase() || score >= leader.score =>
+        {
+            "you're in the lead!".to_string()
+        }
+        (Some(score), Some(leader)) => format!(
+            "you're {} behind {} for #1",
+            format_money(leader.score - score),
+            leader.name
+        ),
     }
-    .insert(db)
-    .await?;
+}
 
-    client
-        .say_in_reply_to(msg, format!("caught a {catch}!"))
-        .await?;
+/// Sum the value of all catches of `user_name`.
+///
+/// With `without_junk` trash and negative catches are left out.
+fn score_query(user_name: &str, without_junk: bool) -> Select<Catches> {
+    let query = Catches::find()
+        .inner_join(Users)
+        .filter(users::Column::Name.eq(user_name.to_lowercase()));
 
-    Ok(())
+    let query = if without_junk {
+        query
+            .inner_join(Fishes)
+            .filter(fishes::Column::IsTrash.eq(false))
+            .filter(catches::Column::Value.gt(0.0))
+    } else {
+        query
+    };
+
+    query
+        .select_only()
+        .column_as(catches::Column::Value.sum(), "score")
 }
 
-#[cfg(test)]
-mod tests {
-    use std::ops::Range;
+#[derive(Debug, FromQueryResult)]
+struct CaughtFish {
+    name: String,
+    count: i32,
+    bundle_id: i32,
+}
 
-    use approx::assert_ulps_eq;
-    use fishinge_bot::Fish;
-    use test_case::test_case;
+#[derive(Debug, FromQueryResult)]
+struct BundlePopulation {
+    bundle_id: i32,
+    population: i64,
+}
 
-    use super::*;
+/// The fishes `user_name` caught, once for every bundle they are in
+fn luckiest_catch_query(user_name: &str) -> Select<Fishes> {
+    Fishes::find()
+        .join(JoinType::InnerJoin, fishes::Relation::Catches.def())
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .join(
+            JoinType::InnerJoin,
+            fish_bundle::Relation::Fishes.def().rev(),
+        )
+        .filter(users::Column::Name.eq(user_name.to_lowercase()))
+        .filter(fishes::Column::Count.gt(0))
+        .distinct()
+        .select_only()
+        .column(fishes::Column::Name)
+        .column(fishes::Column::Count)
+        .column(fish_bundle::Column::BundleId)
+}
 
-    #[test_case(Some(0.0..1.0), 100, 0.0, 89.940796 ; "range 0.0 to 1.0 with base value 100 and weight 0.0")]
-    #[test_case(Some(0.0..1.0), 100, 0.5, 107.299995 ; "range 0.0 to 1.0 with base value 100 and weight 0.5")]
-    #[test_case(Some(0.0..1.0), 100, 1.0, 180.1472 ; "range 0.0 to 1.0 with base value 100 and weight 1.0")]
-    #[test_case(Some(0.0..1.0), 100, 1.1, 217.97722 ; "range 0.0 to 1.0 with base value 100 and over weight 1.1")]
-    #[test_case(Some(0.0..1.0), -100, 0.0, -89.940796 ; "range 0.0 to 1.0 with negative base value -100 and weight 0.0")]
-    #[test_case(Some(0.0..1.0), -100, 0.5, -107.299995 ; "range 0.0 to 1.0 with negative base value -100 and weight 0.5")]
-    #[test_case(Some(0.0..1.0), -100, 1.0, -180.1472 ; "range 0.0 to 1.0 with negative base value -100 and weight 1.0")]
-    #[test_case(Some(5.3..12.6), 123, 5.3, 110.62718 ; "range 5.3 to 12.6 with base value 123 and weight 5.3")]
-    #[test_case(Some(5.3..12.6), 123, 8.95, 131.97899 ; "range 5.3 to 12.6 with base value 123 and weight 8.95")]
-    #[test_case(Some(5.3..12.6), 123, 12.6, 221.58107 ; "range 5.3 to 12.6 with base value 123 and weight 12.6")]
-    #[test_case(Some(88000.0..130000.0), 800, 91961.3 , 781.4889 ; "range 88000.0 to 130000.0 with base value 800 and weight 91961.3")]
-    #[test_case(None, -50, 0.0, -50.0 ; "without range with base value -50 and weight 0.0")]
-    #[test_case(None, -50, 100.0, -50.0 ; "without range with base value -50 and weight 100.0")]
-    fn catch_value(
-        weight_range: Option<Range<f32>>,
-        base_value: i32,
-        weight: f32,
-        expected_value: f32,
-    ) {
-        let fish = Fish {
-            id: 0,
-            name: String::new(),
-            count: 0,
-            base_value,
-            weight_range,
-        };
-        let catch = Catch::new(&fish, Some(weight));
-        assert_ulps_eq!(catch.value, expected_value, max_ulps = 4);
-    }
-
-    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: 0.0 }, "fish worth nothing" ; "without weight worth nothing")]
-    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: -50.0 }, "fish worth $-50.00" ; "without weight with negative worth")]
-    #[test_case(Catch{ fish_name: "fish".to_string(), weight: None, value: 50.0 }, "fish worth $50.00" ; "without weight with positive worth")]
-    #[test_case(Catch{ fish_name: "fish".to_string(), weight: Some(1.23), value: 0.0 }, "fish (1.2kg) worth nothing" ; "with weight worth nothing")]
-    #[test_case(Catch{ fish_name: "fish".to_string(), weight: Some(1.23), value: -50.0 }, "fish (1.2kg) worth $-50.00" ; "with weight with negative worth")]
-    #[test_case(Catch{ fish_name: "fish".to_string(), weight: Some(1.23), value: 50.0 }, "fish (1.2kg) worth $50.00" ; "with weight with positive worth")]
-    fn catch_format(catch: Catch, expected: &str) {
-        assert_eq!(catch.to_string(), expected);
+/// Summed count of the fishes in each of `bundle_ids`
+fn bundle_populations_query(bundle_ids: impl IntoIterator<Item = i32>) -> Select<FishBundle> {
+    FishBundle::find()
+        .join(JoinType::InnerJoin, fish_bundle::Relation::Fishes.def())
+        .filter(fish_bundle::Column::BundleId.is_in(bundle_ids))
+        .group_by(fish_bundle::Column::BundleId)
+        .select_only()
+        .column(fish_bundle::Column::BundleId)
+        .column_as(fishes::Column::Count.sum(), "population")
+}
+
+/// The catch with the lowest odds in the population of its own bundle. Empty
+/// bundles are skipped.
+fn luckiest_catch_reply(caught: &[CaughtFish], populations: &HashMap<i32, i64>) -> String {
+    let luckiest = caught
+        .iter()
+        .filter_map(|fish| {
+            let population = *populations.get(&fish.bundle_id)?;
+
+            (population > 0).then_some((fish, fish.count as f32 / population as f32))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b));
+
+    match luckiest {
+        Some((fish, chance)) => format!(
+            "your luckiest catch was {} ({:.1}% odds)",
+            fish.name,
+            chance * 100.0
+        ),
+        None => "you did not catch any fish yet".to_string(),
+    }
+}
+
+/// The bait `user_name` has left, if any
+fn bait_query(user_name: &str) -> Select<Inventory> {
+    Inventory::find()
+        .inner_join(Users)
+        .filter(users::Column::Name.eq(user_name.to_lowercase()))
+        .filter(inventory::Column::Item.eq(BAIT))
+        .filter(inventory::Column::Amount.gt(0))
+}
+
+fn bait_reply(amount: i32) -> String {
+    match amount {
+        0 => "you have no bait".to_string(),
+        1 => "you have 1 bait, your next catch is more likely to be rare".to_string(),
+        amount => format!("you have {amount} bait, your next catches are more likely to be rare"),
+    }
+}
+
+/// How many species are listed in an aquarium
+const AQUARIUM_SIZE: u64 = 5;
+/// Twitch drops messages longer than this
+const MAX_REPLY_LENGTH: usize = 500;
+
+#[derive(Debug, FromQueryResult)]
+struct AquariumEntry {
+    fish_name: String,
+    catches: i64,
+}
+
+/// The species `user_name` caught most often. Gifts are not catches, so they
+/// are left out.
+fn aquarium_query(user_name: &str) -> Select<Catches> {
+    Catches::find()
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .filter(users::Column::Name.eq(user_name.to_lowercase()))
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .group_by(fishes::Column::Id)
+        .group_by(fishes::Column::Name)
+        .order_by_desc(catches::Column::Id.count())
+        .order_by_asc(fishes::Column::Name)
+        .limit(AQUARIUM_SIZE)
+        .select_only()
+        .column_as(fishes::Column::Name, "fish_name")
+        .column_as(catches::Column::Id.count(), "catches")
+}
+
+fn aquarium_reply(aquarium: &[AquariumEntry]) -> String {
+    if aquarium.is_empty() {
+        return "you did not catch any fish yet".to_string();
+    }
+
+    let species = aquarium
+        .iter()
+        .map(|entry| format!("{} x{}", entry.fish_name, entry.catches))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    truncate_reply(format!("your aquarium: {species}"), MAX_REPLY_LENGTH)
+}
+
+/// The recounted `population` and the chance of every fish, cut to fit into a
+/// single message.
+fn recount_reply(population: u32, fishes: &[Fish]) -> String {
+    let chances = fishes
+        .iter()
+        .map(|fish| format!("{} {:.1}%", fish.name, fish.chance() * 100.0))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    truncate_reply(
+        format!(
+            "recounted {} fish: {chances}",
+            format_thousands(population.into())
+        ),
+        MAX_REPLY_LENGTH,
+    )
+}
+
+/// Cut `reply` to at most `max_length` characters, ending it with "…" if it
+/// was too long.
+fn truncate_reply(reply: String, max_length: usize) -> String {
+    if reply.chars().count() <= max_length {
+        return reply;
+    }
+
+    let mut truncated: String = reply.chars().take(max_length.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[derive(Debug, FromQueryResult)]
+struct TopCatch {
+    fish_name: String,
+    weight: Option<f32>,
+    value: f32,
+    user_name: String,
+}
+
+/// The most valuable catch of all users. Gifts are not catches, so they are
+/// left out.
+fn top_catch_query() -> Select<Catches> {
+    Catches::find()
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .order_by_desc(catches::Column::Value)
+        .select_only()
+        .column_as(fishes::Column::Name, "fish_name")
+        .column_as(users::Column::Name, "user_name")
+        .column(catches::Column::Value)
+        .column(catches::Column::Weight)
+}
+
+fn top_catch_reply(top_catch: Option<TopCatch>, unit: WeightUnit) -> String {
+    match top_catch {
+        Some(top_catch) => {
+            let weight = top_catch
+                .weight
+                .map(|weight| format!(" ({})", format_weight(weight, unit)))
+                .unwrap_or_default();
+
+            format!(
+                "the biggest catch ever is {}{weight} by {} worth {}",
+                top_catch.fish_name,
+                top_catch.user_name,
+                format_money(top_catch.value)
+            )
+        }
+        None => "nobody caught a fish yet".to_string(),
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct AverageWeight {
+    average_weight: Option<f64>,
+    catches: i64,
+}
+
+/// Average weight and number of weighed catches of a fish.
+fn average_weight_query(fish_id: i32) -> Select<Catches> {
+    Catches::find()
+        .filter(catches::Column::FishId.eq(fish_id))
+        .select_only()
+        .column_as(
+            SimpleExpr::from(Func::avg(Expr::col((Catches, catches::Column::Weight)))),
+            "average_weight",
+        )
+        .column_as(catches::Column::Weight.count(), "catches")
+}
+
+fn average_weight_reply(
+    name: &str,
+    has_weight: bool,
+    average: Option<AverageWeight>,
+    unit: WeightUnit,
+) -> String {
+    match average {
+        _ if !has_weight => format!("{name} is not weighed"),
+        Some(AverageWeight {
+            average_weight: Some(average_weight),
+            catches,
+        }) if catches > 0 => format!(
+            "{name} averages {} over {} catches",
+            format_weight(average_weight as f32, unit),
+            format_thousands(catches as u64)
+        ),
+        _ => format!("nobody caught {name} yet"),
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CatchTotals {
+    score: Option<f32>,
+    catches: i64,
+}
+
+/// Score and number of catches of `user_name`
+fn catch_totals_query(user_name: &str) -> Select<Catches> {
+    score_query(user_name, false).column_as(catches::Column::Id.count(), "catches")
+}
+
+/// Reply to `💰`. A score of zero is only reported once the user caught
+/// something, since the sum of no catches is `NULL`.
+fn score_reply(totals: Option<CatchTotals>) -> String {
+    match totals {
+        Some(CatchTotals {
+            score: Some(score),
+            catches,
+        }) if catches > 0 => {
+            let score = format_money(score);
+
+            if score == format_money(0.0) {
+                format!("your current score is {score}, your catches broke even")
+            } else {
+                format!("your current score is {score}")
+            }
+        }
+        _ => "you did not catch any fish yet".to_string(),
+    }
+}
+
+fn average_catch_reply(average: Option<CatchTotals>) -> String {
+    match average {
+        Some(CatchTotals {
+            score: Some(score),
+            catches,
+        }) if catches > 0 => format!(
+            "your average catch is worth {} over {} catches",
+            format_money(score / catches as f32),
+            format_thousands(catches as u64)
+        ),
+        _ => "you did not catch any fish yet".to_string(),
+    }
+}
+
+fn next_season_reply(season: &seasons::Model) -> String {
+    let end = season
+        .end
+        .map(|end| end.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "forever".to_string());
+
+    format!(
+        "created season {} ({} - {end})",
+        season.name,
+        season.start.format("%Y-%m-%d")
+    )
+}
+
+fn what_if_reply(score: Option<f32>, score_without_junk: Option<f32>) -> String {
+    match score {
+        Some(score) => format!(
+            "real {}, without junk {}",
+            format_money(score),
+            format_money(score_without_junk.unwrap_or_default())
+        ),
+        None => "you did not catch any fish yet".to_string(),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Gift {
+    target: String,
+    amount: f32,
+}
+
+impl Gift {
+    /// Check everything that can be checked without the database
+    fn validate(&self, sender: &str) -> Result<(), &'static str> {
+        if self.target == sender.to_lowercase() {
+            return Err("you can not gift yourself");
+        }
+
+        if !self.amount.is_finite() || self.amount <= 0.0 {
+            return Err("you can only gift a positive amount");
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse `@user amount` with a positive amount of bait.
+fn parse_give_bait(args: &str) -> Option<(String, i32)> {
+    let mut args = args.split_whitespace();
+
+    let user_name = args.next()?.trim_start_matches('@').to_lowercase();
+    let amount: i32 = args.next()?.parse().ok()?;
+
+    if user_name.is_empty() || amount <= 0 || args.next().is_some() {
+        return None;
+    }
+
+    Some((user_name, amount))
+}
+
+/// Add `amount` bait to the inventory of `user_name`. Returns the bait the
+/// user has now or `None` if there is no such user.
+async fn give_bait(
+    db: &DatabaseConnection,
+    user_name: &str,
+    amount: i32,
+) -> Result<Option<i32>, sea_orm::DbErr> {
+    let txn = db.begin().await?;
+
+    let user = match Users::find()
+        .filter(users::Column::Name.eq(user_name))
+        .one(&txn)
+        .await?
+    {
+        Some(user) => user,
+        None => return Ok(None),
+    };
+
+    let bait = Inventory::find()
+        .filter(inventory::Column::UserId.eq(user.id))
+        .filter(inventory::Column::Item.eq(BAIT))
+        .lock_exclusive()
+        .one(&txn)
+        .await?;
+
+    let total = match bait {
+        Some(bait) => {
+            let total = bait.amount + amount;
+            inventory::ActiveModel {
+                amount: ActiveValue::set(total),
+                ..bait.into()
+            }
+            .update(&txn)
+            .await?;
+            total
+        }
+        None => {
+            inventory::ActiveModel {
+                user_id: ActiveValue::set(user.id),
+                item: ActiveValue::set(BAIT.to_string()),
+                amount: ActiveValue::set(amount),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+            amount
+        }
+    };
+
+    txn.commit().await?;
+
+    Ok(Some(total))
+}
+
+/// Parse `@user amount`. The amount is rounded to cents.
+fn parse_gift(args: &str) -> Option<Gift> {
+    let mut args = args.split_whitespace();
+
+    let target = args.next()?.trim_start_matches('@').to_lowercase();
+    let amount: f32 = args.next()?.trim_start_matches('$').parse().ok()?;
+
+    if target.is_empty() || args.next().is_some() {
+        return None;
+    }
+
+    Some(Gift {
+        target,
+        amount: (amount * 100.0).round() / 100.0,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+enum GiftOutcome {
+    Sent,
+    UnknownTarget,
+    InsufficientScore(f32),
+}
+
+/// Move `gift.amount` from the score of `sender_name` to `gift.target` by
+/// recording a negative catch of the gift fish for the sender and a positive
+/// one for the target in `season`.
+async fn send_gift(
+    db: &DatabaseConnection,
+    sender_name: &str,
+    gift: &Gift,
+    season: &seasons::Model,
+    persist: bool,
+) -> Result<GiftOutcome> {
+    let txn = db.begin().await?;
+
+    // lock the sender so concurrent gifts can not spend the same score twice
+    let sender = match Users::find()
+        .filter(users::Column::Name.eq(sender_name.to_lowercase()))
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+    {
+        Some(sender) => sender,
+        None => return Ok(GiftOutcome::InsufficientScore(0.0)),
+    };
+
+    let target = match Users::find()
+        .filter(users::Column::Name.eq(&gift.target))
+        .one(&txn)
+        .await?
+    {
+        Some(target) => target,
+        None => return Ok(GiftOutcome::UnknownTarget),
+    };
+
+    // only the score of the season the gift is recorded in can be spent
+    let score: f32 = season_score_query(sender.id, season.id)
+        .into_values::<Option<f32>, ScoreQueryAs>()
+        .one(&txn)
+        .await?
+        .flatten()
+        .unwrap_or_default();
+
+    if score < gift.amount {
+        return Ok(GiftOutcome::InsufficientScore(score));
+    }
+
+    let fish = Fishes::find()
+        .filter(fishes::Column::Name.eq(GIFT_FISH))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| eyre!("Gift fish {GIFT_FISH} is missing"))?;
+
+    if persist {
+        let now: DateTime<FixedOffset> = Utc::now().into();
+        let catch = |user_id, value| catches::ActiveModel {
+            user_id: ActiveValue::Set(user_id),
+            fish_id: ActiveValue::Set(fish.id),
+            weight: ActiveValue::Set(None),
+            caught_at: ActiveValue::Set(now),
+            value: ActiveValue::Set(value),
+            season_id: ActiveValue::Set(season.id),
+            ..Default::default()
+        };
+
+        Catches::insert_many([
+            catch(sender.id, -gift.amount),
+            catch(target.id, gift.amount),
+        ])
+        .exec(&txn)
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    info!(
+        "{} gifted {} to {}",
+        sender.name,
+        format_money(gift.amount),
+        target.name
+    );
+
+    Ok(GiftOutcome::Sent)
+}
+
+/// `last_fished` of users created without fishing
+fn never_fished() -> DateTime<FixedOffset> {
+    DateTime::<Utc>::from_utc(NaiveDateTime::from_timestamp_opt(61, 0).unwrap(), Utc).into()
+}
+
+/// Whether `login` can be a Twitch login
+fn is_valid_login(login: &str) -> bool {
+    (1..=25).contains(&login.len())
+        && !login.starts_with('_')
+        && login
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Parse `@user`, someone to fish for. Returns `None` for anything that is not
+/// a single Twitch login.
+fn parse_fish_for(args: &str) -> Option<String> {
+    let mut args = args.split_whitespace();
+
+    let target = args.next()?.trim_start_matches('@').to_lowercase();
+
+    if args.next().is_some() || !is_valid_login(&target) {
+        return None;
+    }
+
+    Some(target)
+}
+
+#[derive(Debug)]
+enum FishForOutcome {
+    Caught(Catch),
+    OnCooldown(Duration),
+    InsufficientScore(f32),
+    /// The sender is a bot and bots may not fish
+    SenderIsBot,
+    TargetIsBot,
+    /// The target never fished and may not be created
+    UnknownTarget,
+}
+
+/// Cast on behalf of `target`. The catch is credited to `target`, who is
+/// created if they never fished, unless accounts have to be old enough to
+/// fish. Their age can't be checked by login, so only existing fishers can be
+/// gifted a cast then. The sender pays `fish_gift_cost` with a gift
+/// fish catch and uses up their own cast.
+async fn fish_for(
+    db: &DatabaseConnection,
+    config: &Config,
+    fish_cache: &FishCache,
+    account_age_gate: Option<&AccountAgeGate>,
+    msg: &PrivmsgMessage,
+    target_name: &str,
+    season: &seasons::Model,
+) -> Result<FishForOutcome> {
+    let now: DateTime<FixedOffset> = Utc::now().into();
+    let fishes = fish_cache.get(db, season).await?;
+    let txn = db.begin().await?;
+
+    // lock the sender so the same cast or score can not be spent twice
+    let sender = match Users::find()
+        .filter(users::Column::Name.eq(msg.sender.login.to_lowercase()))
+        .lock_exclusive()
+        .one(&txn)
+        .await?
+    {
+        Some(sender) => sender,
+        None => return Ok(FishForOutcome::InsufficientScore(0.0)),
+    };
+
+    if config.bot_fishing.blocks(sender.is_bot) {
+        return Ok(FishForOutcome::SenderIsBot);
+    }
+
+    let cooled_off = sender.last_fished
+        + cooldown_for(&msg.badges, *COOLDOWN, config.subscriber_cooldown_factor);
+    if cooled_off > now {
+        return Ok(FishForOutcome::OnCooldown(cooled_off - now));
+    }
+
+    // the cost is recorded in `season`, so only its score can be spent
+    let score: f32 = season_score_query(sender.id, season.id)
+        .into_values::<Option<f32>, ScoreQueryAs>()
+        .one(&txn)
+        .await?
+        .flatten()
+        .unwrap_or_default();
+
+    if score < config.fish_gift_cost {
+        return Ok(FishForOutcome::InsufficientScore(score));
+    }
+
+    let target = Users::find()
+        .filter(users::Column::Name.eq(target_name))
+        .one(&txn)
+        .await?;
+
+    if target.as_ref().map_or(false, |target| target.is_bot) {
+        return Ok(FishForOutcome::TargetIsBot);
+    }
+    if target.is_none() && account_age_gate.is_some() {
+        return Ok(FishForOutcome::UnknownTarget);
+    }
+
+    let fish = fish_once(&fishes, &mut thread_rng(), None, false, 0.0)?;
+    let catch = fish.catch();
+
+    info!(
+        "{} is fishing for {target_name} and caught {catch}",
+        sender.name
+    );
+
+    if config.dry_run {
+        return Ok(FishForOutcome::Caught(catch));
+    }
+
+    let target = match target {
+        Some(target) => target,
+        // never fished, so they have no cooldown
+        None => {
+            users::ActiveModel {
+                name: ActiveValue::set(target_name.to_string()),
+                last_fished: ActiveValue::set(never_fished()),
+                is_bot: ActiveValue::set(false),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?
+        }
+    };
+
+    let gift_fish = Fishes::find()
+        .filter(fishes::Column::Name.eq(GIFT_FISH))
+        .one(&txn)
+        .await?
+        .ok_or_else(|| eyre!("Gift fish {GIFT_FISH} is missing"))?;
+
+    users::ActiveModel {
+        last_fished: ActiveValue::set(now),
+        ..sender.clone().into()
+    }
+    .update(&txn)
+    .await?;
+
+    let caught = |user_id, fish_id, weight, value| catches::ActiveModel {
+        user_id: ActiveValue::Set(user_id),
+        fish_id: ActiveValue::Set(fish_id),
+        weight: ActiveValue::Set(weight),
+        caught_at: ActiveValue::Set(now),
+        value: ActiveValue::Set(value),
+        season_id: ActiveValue::Set(season.id),
+        ..Default::default()
+    };
+
+    Catches::insert_many([
+        caught(target.id, fish.id, catch.weight, catch.value),
+        caught(sender.id, gift_fish.id, None, -config.fish_gift_cost),
+    ])
+    .exec(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(FishForOutcome::Caught(catch))
+}
+
+fn fish_for_reply(outcome: FishForOutcome, target: &str, cost: f32, unit: WeightUnit) -> String {
+    match outcome {
+        FishForOutcome::Caught(catch) => {
+            format!("you caught a {} for {target}!", catch.in_unit(unit))
+        }
+        FishForOutcome::OnCooldown(cooldown) => format!(
+            "you can fish for someone again in {}",
+            humantime::format_duration(StdDuration::from_secs(cooldown.num_seconds() as u64))
+        ),
+        FishForOutcome::InsufficientScore(score) => format!(
+            "fishing for someone costs {}, you only have {}",
+            format_money(cost),
+            format_money(score)
+        ),
+        FishForOutcome::SenderIsBot | FishForOutcome::TargetIsBot => {
+            "bots are not allowed to fish MrDestructoid".to_string()
+        }
+        FishForOutcome::UnknownTarget => format!("there is no fisher called {target}"),
+    }
+}
+
+pub static COOLDOWN: Lazy<Duration> = Lazy::new(|| Duration::hours(4));
+
+/// Badges whose owners get a shorter fishing cooldown
+const PRIVILEGED_BADGES: [&str; 5] = ["broadcaster", "moderator", "vip", "subscriber", "founder"];
+
+/// Reply to a catch if no catch messages are configured
+const DEFAULT_CATCH_MESSAGE: &str = "caught a {catch}!";
+
+/// Catch messages of `channel` and those shared by all channels
+fn catch_messages_query(channel: &str) -> Select<Messages> {
+    Messages::find()
+        .filter(messages::Column::Type.eq(MessageType::Catch))
+        .filter(
+            Condition::any()
+                .add(messages::Column::Channel.eq(channel))
+                .add(messages::Column::Channel.is_null()),
+        )
+}
+
+/// Pick a random catch message. Messages of `channel` take precedence over
+/// shared ones.
+fn choose_catch_message<'a, R: Rng + ?Sized>(
+    messages: &'a [messages::Model],
+    channel: &str,
+    rng: &mut R,
+) -> &'a str {
+    let own: Vec<_> = messages
+        .iter()
+        .filter(|message| message.channel.as_deref() == Some(channel))
+        .collect();
+    let candidates = if own.is_empty() {
+        messages
+            .iter()
+            .filter(|message| message.channel.is_none())
+            .collect()
+    } else {
+        own
+    };
+
+    candidates
+        .choose(rng)
+        .map_or(DEFAULT_CATCH_MESSAGE, |message| message.text.as_str())
+}
+
+/// Fill in the `{user}`, `{fish}`, `{value}`, `{weight}` and `{catch}`
+/// placeholders of a catch message with weights in `unit`. `{weight}` is empty
+/// for fishes without weight and unknown placeholders are left as they are.
+fn render_template(template: &str, catch: &Catch, user: &str, unit: WeightUnit) -> String {
+    let weight = catch
+        .weight
+        .map_or_else(String::new, |weight| format_weight(weight, unit));
+
+    template
+        .replace("{user}", user)
+        .replace("{fish}", &catch.fish_name)
+        .replace("{value}", &format_money(catch.value))
+        .replace("{weight}", &weight)
+        .replace("{catch}", &catch.in_unit(unit).to_string())
+}
+
+/// Fishing cooldown for a user with `badges`. Privileged users have their
+/// cooldown multiplied by `factor`.
+fn cooldown_for(badges: &[Badge], base: Duration, factor: f32) -> Duration {
+    let is_privileged = badges
+        .iter()
+        .any(|badge| PRIVILEGED_BADGES.contains(&badge.name.to_lowercase().as_str()));
+
+    if is_privileged {
+        Duration::milliseconds((base.num_milliseconds() as f64 * factor as f64) as i64)
+    } else {
+        base
+    }
+}
+
+async fn find_user(db: &DatabaseConnection, login: &str) -> Result<Option<users::Model>> {
+    Ok(Users::find()
+        .filter(users::Column::Name.eq(login))
+        .one(db)
+        .await?)
+}
+
+/// Stores casts in the database
+struct DbCatchRepository {
+    db: DatabaseConnection,
+    fish_cache: Arc<FishCache>,
+}
+
+#[async_trait]
+impl CatchRepository for DbCatchRepository {
+    async fn active_season(&self) -> Result<Option<seasons::Model>> {
+        active_season_or_rollover(&self.db).await
+    }
+
+    async fn fishes(&self, season: &seasons::Model) -> Result<Arc<FishSet>> {
+        self.fish_cache.get(&self.db, season).await
+    }
+
+    async fn find_user(&self, login: &str) -> Result<Option<users::Model>> {
+        find_user(&self.db, login).await
+    }
+
+    /// Two casts of a new user can both find no user. The insert ignores the
+    /// conflict on the unique name, so only one of them creates the user and
+    /// the other gets the user it created.
+    async fn create_user(&self, login: &str, now: DateTime<FixedOffset>) -> Result<NewUser> {
+        let insert = Users::insert(users::ActiveModel {
+            name: ActiveValue::set(login.to_string()),
+            last_fished: ActiveValue::set(now),
+            is_bot: ActiveValue::set(false),
+            ..Default::default()
+        })
+        .on_conflict(
+            OnConflict::column(users::Column::Name)
+                .do_nothing()
+                .to_owned(),
+        )
+        .build(self.db.get_database_backend());
+
+        let created = self.db.execute(insert).await?.rows_affected() > 0;
+        let user = find_user(&self.db, login)
+            .await?
+            .ok_or_else(|| eyre!("User {login} is missing after creating it"))?;
+
+        if created {
+            Ok(NewUser::Created(user))
+        } else {
+            debug!("User {login} was created by another cast");
+            Ok(NewUser::Existing(user))
+        }
+    }
+
+    /// Only updates `last_fished` if it is still the one `user` was read with,
+    /// so of two concurrent casts only the first one fishes.
+    async fn start_cooldown(
+        &self,
+        user: &users::Model,
+        now: DateTime<FixedOffset>,
+    ) -> Result<bool> {
+        let result = Users::update_many()
+            .set(users::ActiveModel {
+                last_fished: ActiveValue::set(now),
+                ..Default::default()
+            })
+            .filter(users::Column::Id.eq(user.id))
+            .filter(users::Column::LastFished.eq(user.last_fished))
+            .exec(&self.db)
+            .await?;
+
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn cooldown_messages(&self) -> Result<Vec<String>> {
+        #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+        enum QueryAs {
+            Text,
+        }
+
+        Ok(Messages::find()
+            .filter(messages::Column::Type.eq(MessageType::Cooldown))
+            .into_values::<_, QueryAs>()
+            .all(&self.db)
+            .await?)
+    }
+
+    async fn catch_messages(&self, channel: &str) -> Result<Vec<messages::Model>> {
+        Ok(catch_messages_query(channel).all(&self.db).await?)
+    }
+
+    async fn previous_fish(&self, user: &users::Model) -> Result<Option<i32>> {
+        Ok(Catches::find()
+            .filter(catches::Column::UserId.eq(user.id))
+            .order_by_desc(catches::Column::CaughtAt)
+            .order_by_desc(catches::Column::Id)
+            .one(&self.db)
+            .await?
+            .map(|catch| catch.fish_id))
+    }
+
+    async fn bait(&self, user: &users::Model) -> Result<Option<inventory::Model>> {
+        Ok(bait_query(&user.name).one(&self.db).await?)
+    }
+
+    async fn season_score(&self, user: &users::Model, season: &seasons::Model) -> Result<f32> {
+        Ok(season_score_query(user.id, season.id)
+            .into_values::<_, ScoreQueryAs>()
+            .one(&self.db)
+            .await?
+            .flatten()
+            .unwrap_or_default())
+    }
+
+    async fn record(&self, cast: &NewCast) -> Result<Option<u64>> {
+        let txn = self.db.begin().await?;
+
+        for catch in &cast.catches {
+            catches::ActiveModel {
+                user_id: ActiveValue::set(cast.user_id),
+                fish_id: ActiveValue::set(catch.fish_id),
+                weight: ActiveValue::set(catch.weight),
+                caught_at: ActiveValue::set(catch.caught_at),
+                value: ActiveValue::set(catch.value),
+                season_id: ActiveValue::set(cast.season_id),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+        }
+
+        if let Some(bait_id) = cast.bait_id {
+            Inventory::update_many()
+                .col_expr(
+                    inventory::Column::Amount,
+                    Expr::col(inventory::Column::Amount).sub(1),
+                )
+                .filter(inventory::Column::Id.eq(bait_id))
+                .filter(inventory::Column::Amount.gt(0))
+                .exec(&txn)
+                .await?;
+        }
+
+        let rank = if cast.rank {
+            let score: f32 = season_score_query(cast.user_id, cast.season_id)
+                .into_values::<_, ScoreQueryAs>()
+                .one(&txn)
+                .await?
+                .flatten()
+                .unwrap_or_default();
+            let ahead = users_ahead_query(cast.season_id, score).count(&txn).await?;
+
+            Some(ahead + 1)
+        } else {
+            None
+        };
+
+        txn.commit().await?;
+
+        Ok(rank)
+    }
+}
+
+async fn handle_fishinge(
+    catch_repository: &dyn CatchRepository,
+    responder: &dyn Responder,
+    config: &Config,
+    account_age_gate: Option<&AccountAgeGate>,
+    msg: &PrivmsgMessage,
+) -> Result<()> {
+    let now: DateTime<FixedOffset> = Utc::now().into();
+    // practice catches are not stored and do not trigger a cooldown
+    let practice = config.is_practice_channel(&msg.channel_login);
+    let persist = !config.dry_run && !practice;
+    // TODO: remove unwrap
+    let mut rng = StdRng::from_rng(thread_rng()).unwrap();
+
+    // looked up first, so nobody is put on cooldown while fishing is closed
+    let season = match catch_repository.active_season().await? {
+        Some(season) => season,
+        None => {
+            responder
+                .reply(msg, "fishing is closed between seasons".to_string())
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    // get user from database
+    let login = msg.sender.login.to_lowercase();
+    let mut existing_user = catch_repository.find_user(&login).await?;
+    let mut created_user = None;
+
+    if let (None, Some(gate)) = (&existing_user, account_age_gate) {
+        if !gate.admits(&msg.sender.id, now.with_timezone(&Utc)).await? {
+            debug!("Account of {login} is too young to fish");
+
+            responder
+                .reply(msg, "your account is too new to fish".to_string())
+                .await?;
+
+            return Ok(());
+        }
+    }
+
+    if existing_user.is_none() && persist {
+        match catch_repository.create_user(&login, now).await? {
+            NewUser::Created(user) => created_user = Some(user),
+            // another cast of the same user was first, this one is too early
+            NewUser::Existing(user) => existing_user = Some(user),
+        }
+    }
+
+    let user = if let Some(user) = existing_user {
+        if config.bot_fishing.blocks(user.is_bot) {
+            debug!("Ignoring bot {} trying to fish", user.name);
+
+            if config.bot_fishing == BotFishing::Reply {
+                responder
+                    .reply(
+                        msg,
+                        "bots are not allowed to fish MrDestructoid".to_string(),
+                    )
+                    .await?;
+            }
+
+            return Ok(());
+        }
+
+        // cooldown
+        let cooldown = cooldown_for(&msg.badges, *COOLDOWN, config.subscriber_cooldown_factor);
+        let cooled_off = user.last_fished + cooldown;
+        if !practice && cooled_off > now {
+            return reply_on_cooldown(
+                catch_repository,
+                responder,
+                config,
+                msg,
+                &user,
+                cooled_off - now,
+            )
+            .await;
+        }
+        if persist && !catch_repository.start_cooldown(&user, now).await? {
+            debug!("{} started fishing concurrently", user.name);
+
+            // the concurrent cast has just started the cooldown
+            return reply_on_cooldown(catch_repository, responder, config, msg, &user, cooldown)
+                .await;
+        }
+
+        users::Model {
+            last_fished: now,
+            ..user
+        }
+    } else if let Some(user) = created_user {
+        user
+    } else {
+        users::Model {
+            id: 0,
+            name: login,
+            last_fished: now,
+            is_bot: false,
+        }
+    };
+
+    let fishes = catch_repository.fishes(&season).await?;
+
+    if fishes.is_empty() {
+        return Err(eyre!("no fishes found in database"));
+    }
+
+    let previous_fish_id = if config.avoid_repeat_rare {
+        catch_repository.previous_fish(&user).await?
+    } else {
+        None
+    };
+
+    // bait is only used up by catches that are stored
+    let bait = if persist {
+        catch_repository.bait(&user).await?
+    } else {
+        None
+    };
+    let bias = if bait.is_some() { BAIT_BIAS } else { 0.0 };
+
+    let happy_hour = config.happy_hours.is_active(Utc::now());
+    let boosted;
+    let candidates: &[Fish] = if happy_hour {
+        boosted = boost_rare_fishes(
+            &fishes,
+            config.happy_hours.threshold,
+            config.happy_hours.factor,
+        );
+        &boosted
+    } else {
+        &fishes
+    };
+
+    let mut caught: Vec<_> = cast(
+        candidates,
+        &mut rng,
+        previous_fish_id,
+        config.avoid_repeat_rare,
+        config.double_catch_chance,
+        bias,
+    )?
+    .into_iter()
+    // back to the fish as it is in the bundle, boosted counts are only for
+    // choosing
+    .filter_map(|chosen| fishes.iter().find(|fish| fish.id == chosen.id))
+    .map(|fish| {
+        info!("{} is fishing for {fish}", msg.sender.name);
+
+        (fish, fish.catch())
+    })
+    .collect();
+
+    if config.score_floor && caught.iter().any(|(_, catch)| catch.value < 0.0) {
+        let mut score = catch_repository.season_score(&user, &season).await?;
+
+        for (_, catch) in &mut caught {
+            catch.clamp_to_score_floor(score);
+            score += catch.value;
+        }
+    }
+
+    for (_, catch) in &caught {
+        info!("{} caught {catch}", msg.sender.name);
+    }
+
+    let mut rank = None;
+
+    if persist {
+        let new_cast = NewCast {
+            user_id: user.id,
+            season_id: season.id,
+            catches: caught
+                .iter()
+                .map(|(fish, catch)| NewCatch {
+                    fish_id: fish.id,
+                    weight: catch.weight,
+                    value: catch.value,
+                    // the time of the catch itself, so the catches of a double
+                    // catch are ordered. Catches with the same time are ordered
+                    // by id.
+                    caught_at: Utc::now().into(),
+                })
+                .collect(),
+            bait_id: bait.as_ref().map(|bait| bait.id),
+            // ranked after recording so the rank includes this catch
+            rank: config.report_rank,
+        };
+
+        // a double catch and its bait are stored completely or not at all
+        rank = catch_repository.record(&new_cast).await?;
+
+        if let Some(webhook) = &config.catch_webhook {
+            for (fish, catch) in &caught {
+                if webhook.should_notify(fish, catch) {
+                    webhook.notify(CatchNotification {
+                        channel: msg.channel_login.clone(),
+                        user: user.name.clone(),
+                        fish: catch.fish_name.clone(),
+                        value: catch.value,
+                        weight: catch.weight,
+                    });
+                }
+            }
+        }
+    }
+
+    let unit = config.weight_unit(&msg.channel_login);
+    let mut reply = match caught.as_slice() {
+        [(_, first), (_, second)] => format!(
+            "🎣🎣 double catch! caught a {} and a {}!",
+            first.in_unit(unit),
+            second.in_unit(unit)
+        ),
+        caught => {
+            let messages = catch_repository.catch_messages(&msg.channel_login).await?;
+            let template = choose_catch_message(&messages, &msg.channel_login, &mut rng);
+
+            render_template(template, &caught[0].1, &msg.sender.name, unit)
+        }
+    };
+
+    if happy_hour {
+        reply.insert_str(0, "🎉 ");
+    }
+    if let Some(bait) = &bait {
+        reply.push_str(&format!(" (🪱 {} left)", bait.amount - 1));
+    }
+    if let Some(rank) = rank {
+        reply.push_str(&format!(" (rank #{rank})"));
+    }
+
+    responder.reply(msg, reply).await?;
+
+    Ok(())
+}
+
+/// Tell `user` they can fish again in `remaining`
+async fn reply_on_cooldown(
+    catch_repository: &dyn CatchRepository,
+    responder: &dyn Responder,
+    config: &Config,
+    msg: &PrivmsgMessage,
+    user: &users::Model,
+    remaining: Duration,
+) -> Result<()> {
+    let cooldown =
+        humantime::format_duration(StdDuration::from_secs(remaining.num_seconds() as u64));
+
+    let mut biased_rng = StdRng::seed_from_u64(config.cooldown_seed.seed(user));
+
+    let messages = catch_repository.cooldown_messages().await?;
+
+    if messages.is_empty() {
+        return Err(eyre!("no cooldown messages found in database"));
+    }
+
+    let message = messages
+        .choose(&mut biased_rng)
+        .unwrap()
+        .replace("{cooldown}", &cooldown.to_string());
+
+    responder.reply(msg, message).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+    use fishinge_bot::{MemoryCatchRepository, WeightDistribution};
+    use sea_orm::IntoActiveModel;
+    use test_case::test_case;
+    use twitch_irc::message::IRCMessage;
+
+    use super::*;
+    use crate::account_age::AccountLookup;
+
+    /// Remembers replies instead of sending them
+    #[derive(Default)]
+    struct RecordingResponder {
+        replies: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Responder for RecordingResponder {
+        async fn reply(&self, _msg: &PrivmsgMessage, text: String) -> Result<(), Error> {
+            self.replies.lock().unwrap().push(text);
+            Ok(())
+        }
+
+        async fn whisper(&self, _to: &TwitchUserBasics, text: String) -> Result<(), Error> {
+            self.replies.lock().unwrap().push(text);
+            Ok(())
+        }
+    }
+
+    fn privmsg(channel: &str, text: &str) -> PrivmsgMessage {
+        let raw = format!(
+            "@badge-info=;badges=;color=#0000FF;display-name=Alice;emotes=;flags=;\
+             id=e9d998c3-36f1-430f-89ec-6b887c28af36;mod=0;room-id=11148817;subscriber=0;\
+             tmi-sent-ts=1594545155039;turbo=0;user-id=29803735;user-type= \
+             :alice!alice@alice.tmi.twitch.tv PRIVMSG #{channel} :{text}"
+        );
+
+        PrivmsgMessage::try_from(IRCMessage::parse(&raw).unwrap()).unwrap()
+    }
+
+    fn summer_season(id: i32) -> seasons::Model {
+        seasons::Model {
+            id,
+            name: "Summer 2023".to_string(),
+            start: Utc::now().into(),
+            end: None,
+            bundle_id: 1,
+        }
+    }
+
+    /// Fishing for a single 🐟 in `season`
+    fn pond(season: seasons::Model) -> MemoryCatchRepository {
+        MemoryCatchRepository::new(
+            season,
+            vec![Fish::try_from_model(fish_model("🐟", 10)).unwrap()],
+        )
+    }
+
+    fn context(
+        db: DatabaseConnection,
+        catch_repository: Arc<dyn CatchRepository>,
+        responder: Arc<RecordingResponder>,
+    ) -> Context {
+        Context {
+            db,
+            catch_repository,
+            responder,
+            config: Arc::new(Config::default()),
+            fish_cache: Arc::new(FishCache::new(StdDuration::ZERO)),
+            seen_messages: Arc::new(SeenMessages::default()),
+            command_cooldowns: Arc::new(CommandCooldowns::new(StdDuration::ZERO)),
+            account_age_gate: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn practice_channel_does_not_store_catches() {
+        let config = Config {
+            practice_channel: Some("practice".to_string()),
+            ..Default::default()
+        };
+        let catch_repository = pond(summer_season(1));
+        let responder = RecordingResponder::default();
+
+        handle_fishinge(
+            &catch_repository,
+            &responder,
+            &config,
+            None,
+            &privmsg("practice", "Fishinge"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["caught a 🐟 worth $100.00!".to_string()]
+        );
+        assert!(catch_repository.casts().is_empty());
+        assert!(catch_repository.user("alice").is_none());
+    }
+
+    #[tokio::test]
+    async fn records_catch() {
+        let now = Utc::now();
+        let catch_repository = pond(summer_season(2)).with_user(users::Model {
+            id: 3,
+            name: "alice".to_string(),
+            last_fished: (now - Duration::days(30)).into(),
+            is_bot: false,
+        });
+        let responder = RecordingResponder::default();
+
+        handle_fishinge(
+            &catch_repository,
+            &responder,
+            &Config::default(),
+            None,
+            &privmsg("chronophylos", "Fishinge"),
+        )
+        .await
+        .unwrap();
+
+        let casts = catch_repository.casts();
+        assert_eq!(casts.len(), 1);
+        assert_eq!((casts[0].user_id, casts[0].season_id), (3, 2));
+        assert_eq!(casts[0].catches.len(), 1);
+        assert_eq!(casts[0].catches[0].fish_id, 1);
+        assert_eq!(casts[0].catches[0].value, 100.0);
+        assert!(catch_repository.user("alice").unwrap().last_fished >= now);
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["caught a 🐟 worth $100.00!".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn bait_is_used_up_with_the_cast() {
+        let catch_repository = pond(summer_season(1))
+            .with_user(users::Model {
+                id: 3,
+                name: "alice".to_string(),
+                last_fished: (Utc::now() - Duration::days(30)).into(),
+                is_bot: false,
+            })
+            .with_bait(inventory::Model {
+                id: 7,
+                user_id: 3,
+                item: BAIT.to_string(),
+                amount: 2,
+            });
+        let config = Config {
+            report_rank: true,
+            ..Default::default()
+        };
+        let responder = RecordingResponder::default();
+
+        handle_fishinge(
+            &catch_repository,
+            &responder,
+            &config,
+            None,
+            &privmsg("chronophylos", "Fishinge"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(catch_repository.casts()[0].bait_id, Some(7));
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["caught a 🐟 worth $100.00! (🪱 1 left) (rank #1)".to_string()]
+        );
+    }
+
+    #[test_case("🐱 Fishinge", "No catfishing!" ; "catfishing")]
+    #[test_case("🔍 Fishinge", "fishes are here https://fishinge.chronophylos.com/fishes" ; "search")]
+    #[test_case("🔎 Fishinge", "fishes are here https://fishinge.chronophylos.com/fishes" ; "search right")]
+    #[tokio::test]
+    async fn emote_commands(text: &str, expected: &str) {
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = context(
+            DatabaseConnection::default(),
+            Arc::new(MemoryCatchRepository::default()),
+            responder.clone(),
+        );
+
+        handle_privmsg(&ctx, &privmsg("chronophylos", text))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec![expected.to_string()]
+        );
+    }
+
+    #[test_case(None, "this micro bot allows you to fish. Type `❓ Fishinge` for help." ; "default")]
+    #[test_case(Some("fish with {trigger} catJAM"), "fish with Fishinge catJAM" ; "custom")]
+    fn bot_reply(reply: Option<&str>, expected: &str) {
+        let config = Config {
+            bot_reply: reply.map(str::to_string),
+            ..Default::default()
+        };
+
+        assert_eq!(config.bot_reply(), expected);
+    }
+
+    #[test]
+    fn weight_unit_per_channel() {
+        let config = Config {
+            weight_unit: WeightUnit::Kilograms,
+            channel_weight_units: parse_weight_units("#Forsen=lb, xqc = kg").unwrap(),
+            ..Default::default()
+        };
+
+        assert_eq!(config.weight_unit("forsen"), WeightUnit::Pounds);
+        assert_eq!(config.weight_unit("xqc"), WeightUnit::Kilograms);
+        assert_eq!(config.weight_unit("chronophylos"), WeightUnit::Kilograms);
+    }
+
+    #[test_case("forsen" ; "without unit")]
+    #[test_case("forsen=stone" ; "unknown unit")]
+    #[test_case("=lb" ; "without channel")]
+    fn invalid_weight_units(value: &str) {
+        assert_eq!(parse_weight_units(value).unwrap_err(), value);
+    }
+
+    #[test]
+    fn next_season() {
+        let season = seasons::Model {
+            id: 5,
+            name: "2023Q3".to_string(),
+            start: Utc.with_ymd_and_hms(2023, 7, 1, 0, 0, 0).unwrap().into(),
+            end: Some(Utc.with_ymd_and_hms(2023, 9, 30, 0, 0, 0).unwrap().into()),
+            bundle_id: 1,
+        };
+
+        assert_eq!(
+            next_season_reply(&season),
+            "created season 2023Q3 (2023-07-01 - 2023-09-30)"
+        );
+    }
+
+    #[tokio::test]
+    async fn gap_without_active_season() {
+        let db = database::memory_connection().await.unwrap();
+        let legacy = Seasons::find().one(&db).await.unwrap().unwrap();
+        let bundle_id = legacy.bundle_id;
+        let mut legacy = legacy.into_active_model();
+        legacy.end = ActiveValue::set(Some((Utc::now() - Duration::days(1)).into()));
+        legacy.update(&db).await.unwrap();
+        seasons::ActiveModel {
+            name: ActiveValue::set("Scheduled".to_string()),
+            start: ActiveValue::set((Utc::now() + Duration::days(1)).into()),
+            end: ActiveValue::set(None),
+            bundle_id: ActiveValue::set(bundle_id),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = context(
+            db,
+            Arc::new(MemoryCatchRepository::default()),
+            responder.clone(),
+        );
+
+        handle_privmsg(&ctx, &privmsg("chronophylos", "🥈 Fishinge"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["there is no active season".to_string()]
+        );
+    }
+
+    #[test_case(&[], &[] ; "not an admin")]
+    #[test_case(&["alice"], &["there are 7 cooldown messages"] ; "admin")]
+    #[tokio::test]
+    async fn messages_command_is_for_admins(admins: &[&str], expected: &[&str]) {
+        let db = database::memory_connection().await.unwrap();
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = Context {
+            config: Arc::new(Config {
+                admins: admins.iter().map(|admin| admin.to_string()).collect(),
+                ..Default::default()
+            }),
+            ..context(
+                db,
+                Arc::new(MemoryCatchRepository::default()),
+                responder.clone(),
+            )
+        };
+
+        handle_privmsg(&ctx, &privmsg("chronophylos", "💬 Fishinge list"))
+            .await
+            .unwrap();
+
+        assert_eq!(*responder.replies.lock().unwrap(), expected);
+    }
+
+    #[test_case(&[], &[] ; "not an admin")]
+    #[test_case(&["alice"], &["designated bob as bot"] ; "admin")]
+    #[tokio::test]
+    async fn bot_command_is_for_admins(admins: &[&str], expected: &[&str]) {
+        let db = database::memory_connection().await.unwrap();
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = Context {
+            config: Arc::new(Config {
+                admins: admins.iter().map(|admin| admin.to_string()).collect(),
+                ..Default::default()
+            }),
+            ..context(
+                db.clone(),
+                Arc::new(MemoryCatchRepository::default()),
+                responder.clone(),
+            )
+        };
+
+        handle_privmsg(&ctx, &privmsg("chronophylos", "🤖 Fishinge @Bob"))
+            .await
+            .unwrap();
+
+        assert_eq!(*responder.replies.lock().unwrap(), expected);
+        let bob = Users::find()
+            .filter(users::Column::Name.eq("bob"))
+            .one(&db)
+            .await
+            .unwrap();
+        assert_eq!(
+            bob.map(|bob| bob.is_bot),
+            (!admins.is_empty()).then_some(true)
+        );
+    }
+
+    #[tokio::test]
+    async fn gift_without_active_season() {
+        let db = database::memory_connection().await.unwrap();
+        let legacy = Seasons::find().one(&db).await.unwrap().unwrap();
+        let bundle_id = legacy.bundle_id;
+        let mut legacy = legacy.into_active_model();
+        legacy.end = ActiveValue::set(Some((Utc::now() - Duration::days(1)).into()));
+        legacy.update(&db).await.unwrap();
+        seasons::ActiveModel {
+            name: ActiveValue::set("Scheduled".to_string()),
+            start: ActiveValue::set((Utc::now() + Duration::days(1)).into()),
+            end: ActiveValue::set(None),
+            bundle_id: ActiveValue::set(bundle_id),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = context(
+            db,
+            Arc::new(MemoryCatchRepository::default()),
+            responder.clone(),
+        );
+
+        handle_privmsg(&ctx, &privmsg("chronophylos", "🎁 Fishinge @bob 10"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["gifting is closed between seasons".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn closed_between_seasons() {
+        let catch_repository = MemoryCatchRepository::default();
+        let responder = RecordingResponder::default();
+
+        handle_fishinge(
+            &catch_repository,
+            &responder,
+            &Config::default(),
+            None,
+            &privmsg("chronophylos", "Fishinge"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["fishing is closed between seasons".to_string()]
+        );
+        assert!(catch_repository.user("alice").is_none());
+    }
+
+    #[tokio::test]
+    async fn redelivered_message_is_handled_once() {
+        let catch_repository = Arc::new(pond(summer_season(1)));
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = context(
+            DatabaseConnection::default(),
+            catch_repository.clone(),
+            responder.clone(),
+        );
+        let msg = privmsg("chronophylos", "Fishinge");
+
+        for _ in 0..2 {
+            handle_privmsg(&ctx, &msg).await.unwrap();
+        }
+
+        assert_eq!(responder.replies.lock().unwrap().len(), 1);
+        assert_eq!(catch_repository.casts().len(), 1);
+    }
+
+    /// Knows every account, created at the same time
+    struct CreatedAt(DateTime<Utc>);
+
+    #[async_trait]
+    impl AccountLookup for CreatedAt {
+        async fn created_at(&self, _user_id: &str) -> Result<Option<DateTime<Utc>>, Error> {
+            Ok(Some(self.0))
+        }
+    }
+
+    #[tokio::test]
+    async fn new_account_cannot_fish() {
+        let catch_repository = pond(summer_season(1));
+        let responder = RecordingResponder::default();
+        let gate = AccountAgeGate::new(
+            Duration::days(7),
+            Box::new(CreatedAt(Utc::now() - Duration::days(1))),
+        );
+
+        handle_fishinge(
+            &catch_repository,
+            &responder,
+            &Config::default(),
+            Some(&gate),
+            &privmsg("chronophylos", "Fishinge"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["your account is too new to fish".to_string()]
+        );
+        assert!(catch_repository.user("alice").is_none());
+        assert!(catch_repository.casts().is_empty());
+    }
+
+    #[tokio::test]
+    async fn fishing_twice_is_on_cooldown() {
+        let catch_repository = pond(summer_season(1)).with_cooldown_message("wait {cooldown}");
+        let responder = RecordingResponder::default();
+
+        for _ in 0..2 {
+            handle_fishinge(
+                &catch_repository,
+                &responder,
+                &Config::default(),
+                None,
+                &privmsg("chronophylos", "Fishinge"),
+            )
+            .await
+            .unwrap();
+        }
+
+        let replies = responder.replies.lock().unwrap();
+        assert_eq!(replies.len(), 2);
+        assert!(replies[1].starts_with("wait "));
+        assert_eq!(catch_repository.casts().len(), 1);
+    }
+
+    fn db_catch_repository(db: &DatabaseConnection) -> DbCatchRepository {
+        DbCatchRepository {
+            db: db.clone(),
+            fish_cache: Arc::new(FishCache::new(StdDuration::ZERO)),
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrently_created_user_exists() {
+        let db = database::memory_connection().await.unwrap();
+        let catch_repository = db_catch_repository(&db);
+        let now = Utc::now().into();
+
+        let first = catch_repository.create_user("alice", now).await.unwrap();
+        let second = catch_repository.create_user("alice", now).await.unwrap();
+
+        match (first, second) {
+            (NewUser::Created(first), NewUser::Existing(second)) => assert_eq!(first, second),
+            outcome => panic!("unexpected outcome {outcome:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_cast_does_not_start_cooldown() {
+        let db = database::memory_connection().await.unwrap();
+        let catch_repository = db_catch_repository(&db);
+        let now = Utc::now();
+        let NewUser::Created(user) = catch_repository
+            .create_user("alice", (now - Duration::days(1)).into())
+            .await
+            .unwrap() else {
+            panic!("alice already exists");
+        };
+
+        // both casts looked the user up before either of them started fishing
+        assert!(catch_repository
+            .start_cooldown(&user, now.into())
+            .await
+            .unwrap());
+        assert!(!catch_repository
+            .start_cooldown(&user, (now + Duration::seconds(1)).into())
+            .await
+            .unwrap());
+    }
+
+    /// Create `users` who fished long ago, the first of them with a score of
+    /// 100 in a past season
+    async fn score_in_past_season(db: &DatabaseConnection, users: &[&str]) {
+        let catch_repository = db_catch_repository(db);
+        let past = insert_past_season(db).await;
+        let fish = Fishes::find().one(db).await.unwrap().unwrap();
+        let mut user_ids = Vec::new();
+        for name in users {
+            let NewUser::Created(user) = catch_repository
+                .create_user(name, past.start)
+                .await
+                .unwrap() else {
+                panic!("{name} already exists");
+            };
+            user_ids.push(user.id);
+        }
+        catch_repository
+            .record(&NewCast {
+                user_id: user_ids[0],
+                season_id: past.id,
+                catches: vec![NewCatch {
+                    fish_id: fish.id,
+                    weight: None,
+                    value: 100.0,
+                    caught_at: past.start,
+                }],
+                bait_id: None,
+                rank: false,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn score_of_past_seasons_can_not_be_gifted() {
+        let db = database::memory_connection().await.unwrap();
+        score_in_past_season(&db, &["alice", "bob"]).await;
+        let gift = Gift {
+            target: "bob".to_string(),
+            amount: 50.0,
+        };
+        let season = active_season_or_rollover(&db).await.unwrap().unwrap();
+
+        assert_eq!(
+            send_gift(&db, "alice", &gift, &season, true).await.unwrap(),
+            GiftOutcome::InsufficientScore(0.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn score_of_past_seasons_can_not_pay_for_a_cast() {
+        let db = database::memory_connection().await.unwrap();
+        score_in_past_season(&db, &["alice", "bob"]).await;
+        let config = Config {
+            fish_gift_cost: 50.0,
+            ..Default::default()
+        };
+        let season = active_season_or_rollover(&db).await.unwrap().unwrap();
+
+        let outcome = fish_for(
+            &db,
+            &config,
+            &FishCache::new(StdDuration::ZERO),
+            None,
+            &privmsg("chronophylos", "🎁 Fishinge @bob"),
+            "bob",
+            &season,
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(outcome, FishForOutcome::InsufficientScore(score) if score == 0.0));
+    }
+
+    #[test_case(BotFishing::Allow, false ; "allow")]
+    #[test_case(BotFishing::Ignore, true ; "ignore")]
+    #[test_case(BotFishing::Reply, true ; "reply")]
+    #[tokio::test]
+    async fn bots_fishing_for_someone(policy: BotFishing, blocked: bool) {
+        let db = database::memory_connection().await.unwrap();
+        let NewUser::Created(alice) = db_catch_repository(&db)
+            .create_user("alice", Utc::now().into())
+            .await
+            .unwrap() else {
+            panic!("alice already exists");
+        };
+        users::ActiveModel {
+            id: ActiveValue::unchanged(alice.id),
+            is_bot: ActiveValue::set(true),
+            ..Default::default()
+        }
+        .update(&db)
+        .await
+        .unwrap();
+        let config = Config {
+            bot_fishing: policy,
+            ..Default::default()
+        };
+        let season = active_season_or_rollover(&db).await.unwrap().unwrap();
+
+        let outcome = fish_for(
+            &db,
+            &config,
+            &FishCache::new(StdDuration::ZERO),
+            None,
+            &privmsg("chronophylos", "🎁 Fishinge @bob"),
+            "bob",
+            &season,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(matches!(outcome, FishForOutcome::SenderIsBot), blocked);
+    }
+
+    #[tokio::test]
+    async fn records_cast_with_bait_and_rank() {
+        let db = database::memory_connection().await.unwrap();
+        let catch_repository = db_catch_repository(&db);
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let fish = Fishes::find().one(&db).await.unwrap().unwrap();
+        let NewUser::Created(user) = catch_repository
+            .create_user("alice", Utc::now().into())
+            .await
+            .unwrap() else {
+            panic!("alice already exists");
+        };
+        let bait = inventory::ActiveModel {
+            user_id: ActiveValue::set(user.id),
+            item: ActiveValue::set(BAIT.to_string()),
+            amount: ActiveValue::set(1),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let rank = catch_repository
+            .record(&NewCast {
+                user_id: user.id,
+                season_id: season.id,
+                catches: vec![NewCatch {
+                    fish_id: fish.id,
+                    weight: None,
+                    value: 10.0,
+                    caught_at: Utc::now().into(),
+                }],
+                bait_id: Some(bait.id),
+                rank: true,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(rank, Some(1));
+        assert_eq!(Catches::find().count(&db).await.unwrap(), 1);
+        assert!(catch_repository.bait(&user).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn channel_diff() {
+        let current = parse_channels("a,b,c");
+        let wanted = parse_channels("c,d,b,e");
+
+        assert_eq!(
+            diff_channels(&current, &wanted),
+            (vec!["d", "e"], vec!["a"])
+        );
+        assert_eq!(
+            diff_channels(&current, &current),
+            (Vec::<&str>::new(), Vec::<&str>::new())
+        );
+    }
+
+    #[test]
+    fn channel_accounts() {
+        assert_eq!(
+            parse_channel_accounts(" #Forsen=FishingeBot, xqc = otherbot ,").unwrap(),
+            HashMap::from([
+                ("forsen".to_string(), "fishingebot".to_string()),
+                ("xqc".to_string(), "otherbot".to_string()),
+            ])
+        );
+    }
+
+    #[test_case("forsen" ; "without account")]
+    #[test_case("forsen=" ; "empty account")]
+    #[test_case("=fishingebot" ; "empty channel")]
+    fn invalid_channel_accounts(value: &str) {
+        assert_eq!(parse_channel_accounts(value).unwrap_err(), value);
+    }
+
+    #[test]
+    fn channels_are_assigned_to_accounts() {
+        let assignment = ChannelAssignment {
+            default_account: "fishingebot".to_string(),
+            channel_accounts: parse_channel_accounts("forsen=otherbot,xqc=otherbot").unwrap(),
+        };
+
+        assert_eq!(
+            assignment.assign(&parse_channels("forsen,chronophylos,pajlada")),
+            HashMap::from([
+                (
+                    "fishingebot".to_string(),
+                    parse_channels("chronophylos,pajlada")
+                ),
+                ("otherbot".to_string(), parse_channels("forsen")),
+            ])
+        );
+        assert_eq!(assignment.account("xqc"), "otherbot");
+        assert_eq!(assignment.account("pajlada"), "fishingebot");
+    }
+
+    #[test_case(BotFishing::Allow, false, false ; "allow user")]
+    #[test_case(BotFishing::Allow, true, false ; "allow bot")]
+    #[test_case(BotFishing::Ignore, false, false ; "ignore policy with user")]
+    #[test_case(BotFishing::Ignore, true, true ; "ignore bot")]
+    #[test_case(BotFishing::Reply, false, false ; "reply policy with user")]
+    #[test_case(BotFishing::Reply, true, true ; "reply to bot")]
+    fn bot_fishing(policy: BotFishing, is_bot: bool, expected: bool) {
+        assert_eq!(policy.blocks(is_bot), expected);
+    }
+
+    fn user_fished_at(timestamp: i64) -> users::Model {
+        users::Model {
+            id: 1,
+            name: "fisher".to_string(),
+            last_fished: DateTime::<Utc>::from_utc(
+                NaiveDateTime::from_timestamp_opt(timestamp, 0).unwrap(),
+                Utc,
+            )
+            .into(),
+            is_bot: false,
+        }
+    }
+
+    #[test_case(61, 61 ; "bot epoch")]
+    #[test_case(0, 0 ; "epoch")]
+    #[test_case(-61, 61 ; "before epoch")]
+    fn cooldown_seed_timestamp(timestamp: i64, expected: u64) {
+        assert_eq!(
+            CooldownSeed::Timestamp.seed(&user_fished_at(timestamp)),
+            expected
+        );
+    }
+
+    #[test_case(61, 746928855926307961 ; "bot epoch")]
+    #[test_case(0, 6855654258860239844 ; "epoch")]
+    #[test_case(-61, 6676786501198072528 ; "before epoch")]
+    fn cooldown_seed_hash_is_stable(timestamp: i64, expected: u64) {
+        assert_eq!(
+            CooldownSeed::Hash.seed(&user_fished_at(timestamp)),
+            expected
+        );
+    }
+
+    fn badge(name: &str) -> Badge {
+        Badge {
+            name: name.to_string(),
+            version: "1".to_string(),
+        }
+    }
+
+    #[test_case(vec![], 240 ; "without badges")]
+    #[test_case(vec![badge("premium"), badge("glhf-pledge")], 240 ; "without privileged badges")]
+    #[test_case(vec![badge("subscriber")], 120 ; "subscriber")]
+    #[test_case(vec![badge("founder")], 120 ; "founder")]
+    #[test_case(vec![badge("vip")], 120 ; "vip")]
+    #[test_case(vec![badge("moderator")], 120 ; "moderator")]
+    #[test_case(vec![badge("moderator"), badge("subscriber")], 120 ; "moderator and subscriber")]
+    #[test_case(vec![badge("broadcaster")], 120 ; "broadcaster")]
+    fn privileged_cooldown(badges: Vec<Badge>, expected_minutes: i64) {
+        assert_eq!(
+            cooldown_for(&badges, Duration::hours(4), 0.5),
+            Duration::minutes(expected_minutes)
+        );
+    }
+
+    #[test]
+    fn privileged_cooldown_without_reduction() {
+        assert_eq!(
+            cooldown_for(&[badge("subscriber")], Duration::hours(4), 1.0),
+            Duration::hours(4)
+        );
+    }
+
+    async fn insert_user(db: &DatabaseConnection, name: &str, is_bot: bool) -> users::Model {
+        users::ActiveModel {
+            name: ActiveValue::set(name.to_string()),
+            last_fished: ActiveValue::set(never_fished()),
+            is_bot: ActiveValue::set(is_bot),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    /// Insert `fish` with an id of its own
+    async fn insert_fish(db: &DatabaseConnection, fish: fishes::Model) -> fishes::Model {
+        let mut fish = fish.into_active_model();
+        fish.id = ActiveValue::NotSet;
+        fish.insert(db).await.unwrap()
+    }
+
+    /// The fish gifts are recorded with
+    async fn gift_fish(db: &DatabaseConnection) -> fishes::Model {
+        Fishes::find()
+            .filter(fishes::Column::Name.eq(GIFT_FISH))
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn insert_catch(
+        db: &DatabaseConnection,
+        season_id: i32,
+        user: &users::Model,
+        fish: &fishes::Model,
+        value: f32,
+        weight: Option<f32>,
+    ) -> catches::Model {
+        catches::ActiveModel {
+            user_id: ActiveValue::set(user.id),
+            fish_id: ActiveValue::set(fish.id),
+            weight: ActiveValue::set(weight),
+            caught_at: ActiveValue::set(Utc::now().into()),
+            value: ActiveValue::set(value),
+            season_id: ActiveValue::set(season_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    #[test_case(false, Some(85.0) ; "with junk")]
+    #[test_case(true, Some(100.0) ; "without junk")]
+    #[tokio::test]
+    async fn score_with_and_without_junk(without_junk: bool, expected: Option<f32>) {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let fish = insert_fish(&db, fish_model("🐟", 10)).await;
+        let trash = insert_fish(
+            &db,
+            fishes::Model {
+                is_trash: true,
+                ..fish_model("👢", 10)
+            },
+        )
+        .await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        for (fish, value) in [(&fish, 100.0), (&trash, 5.0), (&fish, -20.0)] {
+            insert_catch(&db, season.id, &alice, fish, value, None).await;
+        }
+        insert_catch(&db, season.id, &bob, &fish, 1000.0, None).await;
+
+        let score = score_query("Alice", without_junk)
+            .into_values::<_, ScoreQueryAs>()
+            .one(&db)
+            .await
+            .unwrap()
+            .flatten();
+
+        assert_eq!(score, expected);
+    }
+
+    #[tokio::test]
+    async fn users_ahead() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let past = insert_past_season(&db).await;
+        let fish = insert_fish(&db, fish_model("🐟", 10)).await;
+        for (name, is_bot, season_id, values) in [
+            ("alice", false, season.id, &[100.0, 50.0][..]),
+            ("bob", false, season.id, &[120.5]),
+            ("botty", true, season.id, &[200.0]),
+            ("carol", false, past.id, &[300.0]),
+            ("dave", false, season.id, &[80.0, 60.0]),
+        ] {
+            let user = insert_user(&db, name, is_bot).await;
+            for value in values {
+                insert_catch(&db, season_id, &user, &fish, *value, None).await;
+            }
+        }
+
+        // alice and dave, bob is tied with the score
+        let ahead = users_ahead_query(season.id, 120.5)
+            .count(&db)
+            .await
+            .unwrap();
+
+        assert_eq!(ahead, 2);
+    }
+
+    /// Insert a season that ended long ago
+    async fn insert_past_season(db: &DatabaseConnection) -> seasons::Model {
+        let active = Seasons::find().one(db).await.unwrap().unwrap();
+        seasons::ActiveModel {
+            name: ActiveValue::set("Spring 2020".to_string()),
+            start: ActiveValue::set((Utc::now() - Duration::days(2000)).into()),
+            end: ActiveValue::set(Some((Utc::now() - Duration::days(1900)).into())),
+            bundle_id: ActiveValue::set(active.bundle_id),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn season_leader() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let past = insert_past_season(&db).await;
+        let fish = insert_fish(&db, fish_model("🐟", 10)).await;
+        for (name, is_bot, season_id, values) in [
+            ("alice", false, season.id, &[300.0][..]),
+            ("bob", false, season.id, &[100.0, 150.0]),
+            ("botty", true, season.id, &[500.0]),
+            ("carol", false, past.id, &[1000.0]),
+        ] {
+            let user = insert_user(&db, name, is_bot).await;
+            for value in values {
+                insert_catch(&db, season_id, &user, &fish, *value, None).await;
+            }
+        }
+
+        let leader = season_leader_query(season.id)
+            .into_model::<SeasonLeader>()
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(leader.name, "alice");
+        assert_eq!(leader.score, 300.0);
+    }
+
+    #[test_case("bob", Some(50.0), Some(("alice", 300.0)), "you're $250.00 behind alice for #1" ; "behind")]
+    #[test_case("Alice", Some(300.0), Some(("alice", 300.0)), "you're in the lead!" ; "leader")]
+    #[test_case("bob", Some(300.0), Some(("alice", 300.0)), "you're in the lead!" ; "tied")]
+    #[test_case("bob", None, Some(("alice", 300.0)), "you did not catch any fish this season yet" ; "unranked")]
+    #[test_case("bob", Some(-5.0), None, "you are not on the leaderboard" ; "no leader")]
+    fn gap(user_name: &str, score: Option<f32>, leader: Option<(&str, f32)>, expected: &str) {
+        let leader = leader.map(|(name, score)| SeasonLeader {
+            name: name.to_string(),
+            score,
+        });
+
+        assert_eq!(gap_reply(user_name, score, leader.as_ref()), expected);
+    }
+
+    #[test_case(None, None, "you did not catch any fish yet" ; "without catches")]
+    #[test_case(Some(90.0), Some(150.0), "real $90.00, without junk $150.00" ; "with junk")]
+    #[test_case(Some(-20.0), None, "real -$20.00, without junk $0.00" ; "only junk")]
+    fn what_if(score: Option<f32>, score_without_junk: Option<f32>, expected: &str) {
+        assert_eq!(what_if_reply(score, score_without_junk), expected);
+    }
+
+    #[test_case(Some(1234.5), 3, "your current score is $1,234.50" ; "with catches")]
+    #[test_case(Some(-20.0), 4, "your current score is -$20.00" ; "negative score")]
+    #[test_case(Some(0.0), 2, "your current score is $0.00, your catches broke even" ; "broken even")]
+    #[test_case(Some(0.001), 2, "your current score is $0.00, your catches broke even" ; "rounding to zero")]
+    #[test_case(None, 0, "you did not catch any fish yet" ; "without catches")]
+    #[test_case(Some(0.0), 0, "you did not catch any fish yet" ; "zero without catches")]
+    fn score(score: Option<f32>, catches: i64, expected: &str) {
+        assert_eq!(score_reply(Some(CatchTotals { score, catches })), expected);
+    }
+
+    #[tokio::test]
+    async fn score_command_broken_even() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let fish = Fishes::find().one(&db).await.unwrap().unwrap();
+        let NewUser::Created(user) = db_catch_repository(&db)
+            .create_user("alice", Utc::now().into())
+            .await
+            .unwrap() else {
+            panic!("alice already exists");
+        };
+        for value in [50.0, -50.0] {
+            catches::ActiveModel {
+                user_id: ActiveValue::set(user.id),
+                fish_id: ActiveValue::set(fish.id),
+                weight: ActiveValue::set(None),
+                caught_at: ActiveValue::set(Utc::now().into()),
+                value: ActiveValue::set(value),
+                season_id: ActiveValue::set(season.id),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = context(
+            db,
+            Arc::new(MemoryCatchRepository::default()),
+            responder.clone(),
+        );
+
+        handle_privmsg(&ctx, &privmsg("chronophylos", "💰 Fishinge"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["your current score is $0.00, your catches broke even".to_string()]
+        );
+    }
+
+    #[test_case(Some(1234.5), 3, "your average catch is worth $411.50 over 3 catches" ; "with catches")]
+    #[test_case(Some(-20.0), 4, "your average catch is worth -$5.00 over 4 catches" ; "negative score")]
+    #[test_case(None, 0, "you did not catch any fish yet" ; "without catches")]
+    fn average_catch(score: Option<f32>, catches: i64, expected: &str) {
+        assert_eq!(
+            average_catch_reply(Some(CatchTotals { score, catches })),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn catch_totals() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let fish = insert_fish(&db, fish_model("🐟", 10)).await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        for value in [100.0, -20.0, 5.0] {
+            insert_catch(&db, season.id, &alice, &fish, value, None).await;
+        }
+        insert_catch(&db, season.id, &bob, &fish, 1000.0, None).await;
+
+        let totals = catch_totals_query("Alice")
+            .into_model::<CatchTotals>()
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(totals.score, Some(85.0));
+        assert_eq!(totals.catches, 3);
+    }
+
+    fn catch(weight: Option<f32>) -> Catch {
+        Catch {
+            fish_name: "🐟".to_string(),
+            weight,
+            value: 1234.5,
+        }
+    }
+
+    fn catch_message(text: &str, channel: Option<&str>) -> messages::Model {
+        messages::Model {
+            id: 1,
+            text: text.to_string(),
+            r#type: MessageType::Catch,
+            channel: channel.map(str::to_string),
+        }
+    }
+
+    #[test_case("{user} caught {fish} ({weight}) worth {value}", Some(2.3), "alice caught 🐟 (2.3kg) worth $1,234.50" ; "all placeholders")]
+    #[test_case("{fish} without weight{weight}", None, "🐟 without weight" ; "without weight")]
+    #[test_case("nice catch!", Some(2.3), "nice catch!" ; "no placeholders")]
+    #[test_case("{user} caught {bait}", None, "alice caught {bait}" ; "unknown placeholder")]
+    #[test_case(DEFAULT_CATCH_MESSAGE, None, "caught a 🐟 worth $1,234.50!" ; "default")]
+    fn render_catch_message(template: &str, weight: Option<f32>, expected: &str) {
+        assert_eq!(
+            render_template(template, &catch(weight), "alice", WeightUnit::Kilograms),
+            expected
+        );
+    }
+
+    #[test_case("{weight}", "5.1lb" ; "weight")]
+    #[test_case("{catch}", "🐟 (5.1lb) worth $1,234.50" ; "catch")]
+    fn render_catch_message_in_pounds(template: &str, expected: &str) {
+        assert_eq!(
+            render_template(template, &catch(Some(2.3)), "alice", WeightUnit::Pounds),
+            expected
+        );
+    }
+
+    #[test]
+    fn default_catch_message_matches_catch() {
+        let catch = catch(Some(2.3));
+
+        assert_eq!(
+            render_template(
+                DEFAULT_CATCH_MESSAGE,
+                &catch,
+                "alice",
+                WeightUnit::Kilograms
+            ),
+            format!("caught a {catch}!")
+        );
+    }
+
+    #[test_case(vec![], DEFAULT_CATCH_MESSAGE ; "no messages")]
+    #[test_case(vec![("shared", None)], "shared" ; "shared message")]
+    #[test_case(vec![("shared", None), ("own", Some("chronophylos"))], "own" ; "own message first")]
+    #[test_case(vec![("other", Some("forsen"))], DEFAULT_CATCH_MESSAGE ; "other channel")]
+    fn choose_message(messages: Vec<(&str, Option<&str>)>, expected: &str) {
+        let messages: Vec<_> = messages
+            .into_iter()
+            .map(|(text, channel)| catch_message(text, channel))
+            .collect();
+
+        assert_eq!(
+            choose_catch_message(&messages, "chronophylos", &mut thread_rng()),
+            expected
+        );
+    }
+
+    #[tokio::test]
+    async fn catch_messages_of_a_channel() {
+        let db = database::memory_connection().await.unwrap();
+        for (text, r#type, channel) in [
+            ("shared", MessageType::Catch, None),
+            ("own", MessageType::Catch, Some("chronophylos")),
+            ("elsewhere", MessageType::Catch, Some("forsen")),
+            ("cooldown", MessageType::Cooldown, Some("chronophylos")),
+        ] {
+            messages::ActiveModel {
+                text: ActiveValue::set(text.to_string()),
+                r#type: ActiveValue::set(r#type),
+                channel: ActiveValue::set(channel.map(str::to_string)),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let mut texts: Vec<_> = catch_messages_query("chronophylos")
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|message| message.text)
+            .collect();
+        texts.sort_unstable();
+
+        assert_eq!(texts, ["own", "shared"]);
+    }
+
+    #[tokio::test]
+    async fn luckiest_catch_candidates() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let first_bundle = season.bundle_id;
+        let second_bundle = database::entities::bundle::ActiveModel {
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap()
+        .id;
+        let whale = insert_fish(&db, fish_model("🐳", 8)).await;
+        let fish = insert_fish(&db, fish_model("🐟", 992)).await;
+        let extinct = insert_fish(&db, fish_model("🦤", 0)).await;
+        let shark = insert_fish(&db, fish_model("🦈", 4)).await;
+        FishBundle::insert_many(
+            [
+                (&whale, first_bundle),
+                (&whale, second_bundle),
+                (&fish, first_bundle),
+                (&extinct, first_bundle),
+                (&shark, first_bundle),
+            ]
+            .map(|(fish, bundle_id)| fish_bundle::ActiveModel {
+                fish_id: ActiveValue::set(fish.id),
+                bundle_id: ActiveValue::set(bundle_id),
+            }),
+        )
+        .exec(&db)
+        .await
+        .unwrap();
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        for fish in [&whale, &whale, &fish, &extinct] {
+            insert_catch(&db, season.id, &alice, fish, 10.0, None).await;
+        }
+        insert_catch(&db, season.id, &bob, &shark, 10.0, None).await;
+
+        let mut caught: Vec<_> = luckiest_catch_query("Alice")
+            .into_model::<CaughtFish>()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fish| (fish.name, fish.count, fish.bundle_id))
+            .collect();
+        caught.sort_unstable();
+
+        assert_eq!(
+            caught,
+            [
+                ("🐟".to_string(), 992, first_bundle),
+                ("🐳".to_string(), 8, first_bundle),
+                ("🐳".to_string(), 8, second_bundle),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn luckiest_catch_command() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let bundle = Bundle::find().one(&db).await.unwrap().unwrap();
+        let mut fishes = Vec::new();
+        for (name, count) in [("🐳", 8), ("🐟", 992)] {
+            let mut fish = fish_model(name, count).into_active_model();
+            fish.id = ActiveValue::NotSet;
+            fishes.push(fish.insert(&db).await.unwrap());
+        }
+        FishBundle::insert_many(fishes.iter().map(|fish| fish_bundle::ActiveModel {
+            fish_id: ActiveValue::set(fish.id),
+            bundle_id: ActiveValue::set(bundle.id),
+        }))
+        .exec(&db)
+        .await
+        .unwrap();
+        let NewUser::Created(user) = db_catch_repository(&db)
+            .create_user("alice", Utc::now().into())
+            .await
+            .unwrap() else {
+            panic!("alice already exists");
+        };
+        catches::ActiveModel {
+            user_id: ActiveValue::set(user.id),
+            fish_id: ActiveValue::set(fishes[0].id),
+            weight: ActiveValue::set(None),
+            caught_at: ActiveValue::set(Utc::now().into()),
+            value: ActiveValue::set(10.0),
+            season_id: ActiveValue::set(season.id),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let responder = Arc::new(RecordingResponder::default());
+        let ctx = context(
+            db,
+            Arc::new(MemoryCatchRepository::default()),
+            responder.clone(),
+        );
+
+        handle_privmsg(&ctx, &privmsg("chronophylos", "🍀 Fishinge"))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *responder.replies.lock().unwrap(),
+            vec!["your luckiest catch was 🐳 (0.8% odds)".to_string()]
+        );
+    }
+
+    #[test_case("@Alice 5", Some(("alice", 5)) ; "mention")]
+    #[test_case(" bob 1 ", Some(("bob", 1)) ; "whitespace")]
+    #[test_case("bob 0", None ; "zero")]
+    #[test_case("bob -3", None ; "negative")]
+    #[test_case("bob", None ; "without amount")]
+    #[test_case("bob 1 2", None ; "trailing argument")]
+    fn give_bait_args(args: &str, expected: Option<(&str, i32)>) {
+        assert_eq!(
+            parse_give_bait(args),
+            expected.map(|(user_name, amount)| (user_name.to_string(), amount))
+        );
+    }
+
+    #[test_case("Alice", None ; "used up")]
+    #[test_case("bob", Some(2) ; "left")]
+    #[test_case("carol", None ; "without bait")]
+    #[tokio::test]
+    async fn bait_left(user_name: &str, expected: Option<i32>) {
+        let db = database::memory_connection().await.unwrap();
+        for (name, item, amount) in [("alice", BAIT, 0), ("bob", BAIT, 2), ("carol", "net", 3)] {
+            let user = insert_user(&db, name, false).await;
+            inventory::ActiveModel {
+                user_id: ActiveValue::set(user.id),
+                item: ActiveValue::set(item.to_string()),
+                amount: ActiveValue::set(amount),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let bait = bait_query(user_name).one(&db).await.unwrap();
+
+        assert_eq!(bait.map(|bait| bait.amount), expected);
+    }
+
+    #[test_case(0, "you have no bait" ; "none")]
+    #[test_case(1, "you have 1 bait, your next catch is more likely to be rare" ; "one")]
+    #[test_case(3, "you have 3 bait, your next catches are more likely to be rare" ; "some")]
+    fn bait(amount: i32, expected: &str) {
+        assert_eq!(bait_reply(amount), expected);
+    }
+
+    #[tokio::test]
+    async fn aquarium_of_a_user() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let gift = gift_fish(&db).await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        for (name, catches) in [
+            ("🐟", 3),
+            ("🐡", 2),
+            ("🐳", 2),
+            ("🦈", 1),
+            ("🦐", 1),
+            ("🦑", 1),
+        ] {
+            let fish = insert_fish(&db, fish_model(name, 10)).await;
+            for _ in 0..catches {
+                insert_catch(&db, season.id, &alice, &fish, 10.0, None).await;
+            }
+            insert_catch(&db, season.id, &bob, &fish, 10.0, None).await;
+        }
+        for _ in 0..4 {
+            insert_catch(&db, season.id, &alice, &gift, 10.0, None).await;
+        }
+
+        let aquarium: Vec<_> = aquarium_query("Alice")
+            .into_model::<AquariumEntry>()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.fish_name, entry.catches))
+            .collect();
+
+        // ties are listed by name and only the first five species fit
+        assert_eq!(
+            aquarium,
+            [
+                ("🐟".to_string(), 3),
+                ("🐡".to_string(), 2),
+                ("🐳".to_string(), 2),
+                ("🦈".to_string(), 1),
+                ("🦐".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn aquarium() {
+        let aquarium = [
+            AquariumEntry {
+                fish_name: "🐟".to_string(),
+                catches: 12,
+            },
+            AquariumEntry {
+                fish_name: "🦈".to_string(),
+                catches: 3,
+            },
+        ];
+
+        assert_eq!(aquarium_reply(&aquarium), "your aquarium: 🐟 x12, 🦈 x3");
+        assert_eq!(aquarium_reply(&[]), "you did not catch any fish yet");
+    }
+
+    fn recounted_fish(name: &str, count: u32, population: u32) -> Fish {
+        Fish {
+            id: 1,
+            name: name.to_string(),
+            count,
+            base_value: 100,
+            weight_range: None,
+            weight_distribution: WeightDistribution::Uniform,
+            population,
+        }
+    }
+
+    #[test]
+    fn recount() {
+        let fishes = [
+            recounted_fish("🐟", 900, 1000),
+            recounted_fish("🐳", 100, 1000),
+        ];
+
+        assert_eq!(
+            recount_reply(1000, &fishes),
+            "recounted 1,000 fish: 🐟 90.0%, 🐳 10.0%"
+        );
+    }
+
+    #[test]
+    fn recount_fits_into_a_message() {
+        let fishes = (0..100)
+            .map(|_| recounted_fish("🐟", 1, 100))
+            .collect::<Vec<_>>();
+
+        let reply = recount_reply(100, &fishes);
+
+        assert_eq!(reply.chars().count(), MAX_REPLY_LENGTH);
+        assert!(reply.ends_with('…'));
+    }
+
+    #[test_case("short", 5, "short" ; "fits")]
+    #[test_case("too long", 5, "too …" ; "too long")]
+    #[test_case("🐟🐟🐟🐟", 3, "🐟🐟…" ; "counts characters")]
+    fn truncate(reply: &str, max_length: usize, expected: &str) {
+        assert_eq!(truncate_reply(reply.to_string(), max_length), expected);
+    }
+
+    fn fish_model(name: &str, count: i32) -> fishes::Model {
+        fishes::Model {
+            id: 1,
+            name: name.to_string(),
+            html_name: name.to_string(),
+            count,
+            base_value: 100.0,
+            max_weight: 0.0,
+            min_weight: 0.0,
+            is_trash: false,
+            weight_distribution: "uniform".to_string(),
+            weight_mean: None,
+            weight_stddev: None,
+        }
+    }
+
+    fn caught_fish(name: &str, count: i32, bundle_id: i32) -> CaughtFish {
+        CaughtFish {
+            name: name.to_string(),
+            count,
+            bundle_id,
+        }
+    }
+
+    #[test]
+    fn luckiest_catch() {
+        let caught = [caught_fish("🐟", 8, 1), caught_fish("🐳", 8, 2)];
+        let populations = HashMap::from([(1, 100), (2, 1000)]);
+
+        assert_eq!(
+            luckiest_catch_reply(&caught, &populations),
+            "your luckiest catch was 🐳 (0.8% odds)"
+        );
+    }
+
+    #[test]
+    fn luckiest_catch_skips_empty_bundles() {
+        let caught = [caught_fish("🐳", 8, 1), caught_fish("🐟", 8, 2)];
+        let populations = HashMap::from([(1, 0), (2, 100)]);
+
+        assert_eq!(
+            luckiest_catch_reply(&caught, &populations),
+            "your luckiest catch was 🐟 (8.0% odds)"
+        );
+    }
+
+    #[test_case("add you ran out of bait, wait {cooldown}", Some(MessageCommand::Add("you ran out of bait, wait {cooldown}")) ; "add")]
+    #[test_case("  add   spaced out  ", Some(MessageCommand::Add("spaced out")) ; "add with whitespace")]
+    #[test_case("list", Some(MessageCommand::List) ; "list")]
+    #[test_case("add", None ; "add without message")]
+    #[test_case("remove 1", None ; "unknown command")]
+    #[test_case("", None ; "empty")]
+    fn message_command(args: &str, expected: Option<MessageCommand>) {
+        assert_eq!(parse_message_command(args), expected);
+    }
+
+    #[tokio::test]
+    async fn average_weight_of_a_fish() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let fish = insert_fish(&db, fish_model("🐟", 10)).await;
+        let other = insert_fish(&db, fish_model("🐡", 10)).await;
+        let alice = insert_user(&db, "alice", false).await;
+        for (fish, weight) in [
+            (&fish, Some(2.0)),
+            (&fish, Some(4.0)),
+            (&fish, None),
+            (&other, Some(100.0)),
+        ] {
+            insert_catch(&db, season.id, &alice, fish, 10.0, weight).await;
+        }
+
+        let average = average_weight_query(fish.id)
+            .into_model::<AverageWeight>()
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(average.average_weight, Some(3.0));
+        assert_eq!(average.catches, 2);
+    }
+
+    #[test_case(true, Some(12.04), 412, "🦀 averages 12.0kg over 412 catches" ; "with catches")]
+    #[test_case(true, None, 0, "nobody caught 🦀 yet" ; "without catches")]
+    #[test_case(false, None, 0, "🦀 is not weighed" ; "without weight range")]
+    fn average_weight(has_weight: bool, average_weight: Option<f64>, catches: i64, expected: &str) {
+        let average = AverageWeight {
+            average_weight,
+            catches,
+        };
+
+        assert_eq!(
+            average_weight_reply("🦀", has_weight, Some(average), WeightUnit::Kilograms),
+            expected
+        );
+    }
+
+    #[test_case("@alice 12.5", Some(Gift { target: "alice".to_string(), amount: 12.5 }) ; "with at")]
+    #[test_case("Alice $3", Some(Gift { target: "alice".to_string(), amount: 3.0 }) ; "with dollar sign")]
+    #[test_case("alice 0.129", Some(Gift { target: "alice".to_string(), amount: 0.13 }) ; "rounded to cents")]
+    #[test_case("alice", None ; "without amount")]
+    #[test_case("alice lots", None ; "amount not a number")]
+    #[test_case("alice 5 extra", None ; "trailing arguments")]
+    #[test_case("@ 5", None ; "empty target")]
+    fn gift(args: &str, expected: Option<Gift>) {
+        assert_eq!(parse_gift(args), expected);
+    }
+
+    #[test_case("@Bob 5", Ok(()) ; "valid")]
+    #[test_case("@alice 5", Err("you can not gift yourself") ; "self gift")]
+    #[test_case("bob 0", Err("you can only gift a positive amount") ; "zero")]
+    #[test_case("bob 0.001", Err("you can only gift a positive amount") ; "rounded to zero")]
+    #[test_case("bob -5", Err("you can only gift a positive amount") ; "negative")]
+    #[test_case("bob NaN", Err("you can only gift a positive amount") ; "not a number")]
+    #[test_case("bob inf", Err("you can only gift a positive amount") ; "infinite")]
+    fn gift_validation(args: &str, expected: Result<(), &'static str>) {
+        assert_eq!(parse_gift(args).unwrap().validate("Alice"), expected);
+    }
+
+    #[test_case("@Bob", Some("bob") ; "with at")]
+    #[test_case(" bob_42 ", Some("bob_42") ; "without at")]
+    #[test_case("@bob 5", None ; "score gift")]
+    #[test_case("", None ; "empty")]
+    #[test_case("@", None ; "only at")]
+    #[test_case("@bob.tv", None ; "invalid character")]
+    #[test_case("@_bob", None ; "leading underscore")]
+    #[test_case("@abcdefghijklmnopqrstuvwxyz", None ; "too long")]
+    fn fish_for_parsing(args: &str, expected: Option<&str>) {
+        assert_eq!(parse_fish_for(args).as_deref(), expected);
+    }
+
+    #[test]
+    fn fish_for_replies() {
+        let catch = Catch {
+            fish_name: "🐟".to_string(),
+            weight: None,
+            value: 50.0,
+        };
+
+        assert_eq!(
+            fish_for_reply(
+                FishForOutcome::Caught(catch),
+                "bob",
+                10.0,
+                WeightUnit::Kilograms
+            ),
+            "you caught a 🐟 worth $50.00 for bob!"
+        );
+        assert_eq!(
+            fish_for_reply(
+                FishForOutcome::OnCooldown(Duration::minutes(90)),
+                "bob",
+                10.0,
+                WeightUnit::Kilograms
+            ),
+            "you can fish for someone again in 1h 30m"
+        );
+        assert_eq!(
+            fish_for_reply(
+                FishForOutcome::InsufficientScore(2.5),
+                "bob",
+                10.0,
+                WeightUnit::Kilograms
+            ),
+            "fishing for someone costs $10.00, you only have $2.50"
+        );
+    }
+
+    #[tokio::test]
+    async fn top_catch_is_not_a_gift() {
+        let db = database::memory_connection().await.unwrap();
+        let season = Seasons::find().one(&db).await.unwrap().unwrap();
+        let gift = gift_fish(&db).await;
+        let whale = insert_fish(&db, fish_model("🐳", 10)).await;
+        let fish = insert_fish(&db, fish_model("🐟", 10)).await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        insert_catch(&db, season.id, &alice, &gift, 1000.0, None).await;
+        insert_catch(&db, season.id, &bob, &whale, 500.0, Some(2.0)).await;
+        insert_catch(&db, season.id, &alice, &fish, 100.0, None).await;
+
+        let top_catch = top_catch_query()
+            .into_model::<TopCatch>()
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(top_catch.fish_name, "🐳");
+        assert_eq!(top_catch.user_name, "bob");
+        assert_eq!(top_catch.weight, Some(2.0));
+        assert_eq!(top_catch.value, 500.0);
+    }
+
+    #[test_case(Some(12.34), WeightUnit::Kilograms, "the biggest catch ever is 🐋 (12.3kg) by alice worth $1,234.50" ; "with weight")]
+    #[test_case(Some(120_000.0), WeightUnit::Pounds, "the biggest catch ever is 🐋 (264554.7lb) by alice worth $1,234.50" ; "with weight in pounds")]
+    #[test_case(None, WeightUnit::Pounds, "the biggest catch ever is 🐋 by alice worth $1,234.50" ; "without weight")]
+    fn top_catch(weight: Option<f32>, unit: WeightUnit, expected: &str) {
+        let top_catch = TopCatch {
+            fish_name: "🐋".to_string(),
+            weight,
+            value: 1234.5,
+            user_name: "alice".to_string(),
+        };
+
+        assert_eq!(top_catch_reply(Some(top_catch), unit), expected);
+    }
+
+    #[test]
+    fn top_catch_without_catches() {
+        assert_eq!(
+            top_catch_reply(None, WeightUnit::Kilograms),
+            "nobody caught a fish yet"
+        );
+    }
+
+    #[test]
+    fn luckiest_catch_without_catches() {
+        assert_eq!(
+            luckiest_catch_reply(&[], &HashMap::new()),
+            "you did not catch any fish yet"
+        );
     }
 }
@@ -0,0 +1,323 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use bot_framework::rate_limit::RateLimiter;
+use fishinge_bot::Account;
+use log::{error, info, warn};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+use twitch_irc::{
+    login::{LoginCredentials, RefreshingLoginCredentials},
+    message::{NoticeMessage, PrivmsgMessage, TwitchUserBasics},
+};
+
+use crate::{Client, ClientError, Error};
+
+pub(crate) const HELIX_URL: &str = "https://api.twitch.tv/helix";
+
+/// How long to wait before sending a failed reply again
+const REPLY_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How long after a reply a NOTICE that its parent message is gone still
+/// refers to it
+const REPLY_NOTICE_WINDOW: Duration = Duration::from_secs(5);
+
+/// The msg-id twitch sends a NOTICE with when the message a reply is for was
+/// deleted, or its sender timed out, before the reply arrived
+const DELETED_PARENT_MSG_ID: &str = "invalid_parent";
+
+/// Replies that could not be delivered, even after retrying
+static FAILED_REPLIES: AtomicU64 = AtomicU64::new(0);
+
+/// Number of replies that could not be delivered since the bot started
+pub(crate) fn failed_replies() -> u64 {
+    FAILED_REPLIES.load(Ordering::Relaxed)
+}
+
+/// Run `attempt`, and run it once more after `delay` if it fails.
+async fn retry_once<T, E, F, Fut>(delay: Duration, mut attempt: F) -> Result<T, E>
+where
+    E: Display,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    match attempt().await {
+        Ok(value) => Ok(value),
+        Err(err) => {
+            warn!("Retrying in {}: {err}", humantime::format_duration(delay));
+            tokio::time::sleep(delay).await;
+
+            attempt().await
+        }
+    }
+}
+
+/// Reply to `msg`, retrying once after [`REPLY_RETRY_DELAY`]. Every attempt
+/// waits for the rate limit of the channel.
+async fn reply_with_retry(
+    client: &Client,
+    limiter: &RateLimiter,
+    msg: &PrivmsgMessage,
+    text: &str,
+) -> Result<(), ClientError> {
+    retry_once(REPLY_RETRY_DELAY, || async {
+        limiter.acquire(&msg.channel_login).await;
+        client.say_in_reply_to(msg, text.to_string()).await
+    })
+    .await
+}
+
+/// Whether `notice` tells that a reply was refused, and it should be sent
+/// again as a plain message.
+///
+/// Sending a reply succeeds even if its parent message was deleted or its
+/// sender was timed out, twitch refuses it afterwards with a NOTICE.
+fn should_fall_back_to_say(notice: &NoticeMessage) -> bool {
+    notice.message_id.as_deref() == Some(DELETED_PARENT_MSG_ID)
+}
+
+/// Something that can answer chat messages and whispers
+#[async_trait]
+pub(crate) trait Responder: Send + Sync {
+    async fn reply(&self, msg: &PrivmsgMessage, text: String) -> Result<(), Error>;
+
+    async fn whisper(&self, to: &TwitchUserBasics, text: String) -> Result<(), Error>;
+
+    /// Act on a NOTICE twitch sent, for example about a refused reply
+    async fn notice(&self, _notice: &NoticeMessage) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Answers in chat and sends whispers through the Helix API, since twitch no
+/// longer supports sending whispers over IRC.
+///
+/// Replies are held back while the rate limit of their channel is exhausted.
+/// Failed replies are retried once and counted in [`failed_replies`]. Replies
+/// twitch refuses because their parent message is gone are sent again without
+/// the parent.
+pub(crate) struct TwitchResponder {
+    client: Client,
+    limiter: RateLimiter,
+    /// The last reply sent to each channel and when
+    last_replies: Mutex<HashMap<String, (Instant, String)>>,
+    http: reqwest::Client,
+    client_id: String,
+    credentials: RefreshingLoginCredentials<Account>,
+    user_id: OnceCell<String>,
+}
+
+impl TwitchResponder {
+    pub(crate) fn new(
+        client: Client,
+        client_id: String,
+        credentials: RefreshingLoginCredentials<Account>,
+        limiter: RateLimiter,
+    ) -> Self {
+        Self {
+            client,
+            limiter,
+            last_replies: Mutex::default(),
+            http: reqwest::Client::new(),
+            client_id,
+            credentials,
+            user_id: OnceCell::new(),
+        }
+    }
+
+    async fn token(&self) -> Result<String, Error> {
+        self.credentials
+            .get_credentials()
+            .await
+            .map_err(Error::GetCredentials)?
+            .token
+            .ok_or(Error::MissingToken)
+    }
+
+    /// Id of the user the bot is logged in as
+    async fn user_id(&self) -> Result<&str, Error> {
+        #[derive(Deserialize)]
+        struct User {
+            id: String,
+        }
+
+        #[derive(Deserialize)]
+        struct Users {
+            data: Vec<User>,
+        }
+
+        let user_id = self
+            .user_id
+            .get_or_try_init(|| async {
+                let users: Users = self
+                    .http
+                    .get(format!("{HELIX_URL}/users"))
+                    .bearer_auth(self.token().await?)
+                    .header("Client-Id", &self.client_id)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+
+                users
+                    .data
+                    .into_iter()
+                    .next()
+                    .map(|user| user.id)
+                    .ok_or(Error::UnknownBotUser)
+            })
+            .await?;
+
+        Ok(user_id)
+    }
+}
+
+#[async_trait]
+impl Responder for TwitchResponder {
+    async fn reply(&self, msg: &PrivmsgMessage, text: String) -> Result<(), Error> {
+        if let Err(err) = reply_with_retry(&self.client, &self.limiter, msg, &text).await {
+            let failed = FAILED_REPLIES.fetch_add(1, Ordering::Relaxed) + 1;
+            error!(
+                "Giving up on replying to {} in #{} ({failed} failed replies so far)",
+                msg.sender.login, msg.channel_login
+            );
+
+            return Err(Error::ReplyToMessage(err));
+        }
+
+        self.last_replies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(msg.channel_login.clone(), (Instant::now(), text));
+
+        Ok(())
+    }
+
+    async fn notice(&self, notice: &NoticeMessage) -> Result<(), Error> {
+        if !should_fall_back_to_say(notice) {
+            return Ok(());
+        }
+        let Some(channel) = &notice.channel_login else {
+            return Ok(());
+        };
+
+        let last_reply = self
+            .last_replies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(channel);
+        let Some((sent, text)) = last_reply else {
+            return Ok(());
+        };
+        if sent.elapsed() > REPLY_NOTICE_WINDOW {
+            return Ok(());
+        }
+
+        warn!("Reply in #{channel} was refused, sending without reply");
+
+        self.limiter.acquire(channel).await;
+        self.client
+            .say(channel.clone(), text)
+            .await
+            .map_err(Error::ReplyToMessage)
+    }
+
+    async fn whisper(&self, to: &TwitchUserBasics, text: String) -> Result<(), Error> {
+        self.http
+            .post(format!("{HELIX_URL}/whispers"))
+            .query(&[
+                ("from_user_id", self.user_id().await?),
+                ("to_user_id", to.id.as_str()),
+            ])
+            .bearer_auth(self.token().await?)
+            .header("Client-Id", &self.client_id)
+            .json(&serde_json::json!({ "message": text }))
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+/// Logs replies instead of sending them to chat
+pub(crate) struct DryRunResponder;
+
+#[async_trait]
+impl Responder for DryRunResponder {
+    async fn reply(&self, msg: &PrivmsgMessage, text: String) -> Result<(), Error> {
+        info!(
+            "Would reply to {} in #{}: {text}",
+            msg.sender.login, msg.channel_login
+        );
+
+        Ok(())
+    }
+
+    async fn whisper(&self, to: &TwitchUserBasics, text: String) -> Result<(), Error> {
+        info!("Would whisper to {}: {text}", to.login);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_case::test_case;
+    use twitch_irc::message::IRCMessage;
+
+    use super::*;
+
+    #[test_case("@msg-id=invalid_parent :tmi.twitch.tv NOTICE #forsen :You cannot reply to this message.", true; "deleted parent")]
+    #[test_case("@msg-id=msg_duplicate :tmi.twitch.tv NOTICE #forsen :Your message is identical to the one you sent less than 30 seconds ago.", false; "duplicate")]
+    #[test_case(":tmi.twitch.tv NOTICE * :Login authentication failed", false; "no msg-id")]
+    fn fall_back_to_say(source: &str, expected: bool) {
+        let notice = NoticeMessage::try_from(IRCMessage::parse(source).unwrap()).unwrap();
+
+        assert_eq!(should_fall_back_to_say(&notice), expected);
+    }
+
+    async fn attempts_until(succeeding_attempt: u32) -> (Result<u32, &'static str>, u32) {
+        let mut attempts = 0;
+        let result = retry_once(Duration::ZERO, || {
+            attempts += 1;
+            let attempt = attempts;
+
+            async move {
+                if attempt >= succeeding_attempt {
+                    Ok(attempt)
+                } else {
+                    Err("failed")
+                }
+            }
+        })
+        .await;
+
+        (result, attempts)
+    }
+
+    #[tokio::test]
+    async fn no_retry_after_success() {
+        assert_eq!(attempts_until(1).await, (Ok(1), 1));
+    }
+
+    #[tokio::test]
+    async fn retries_once() {
+        assert_eq!(attempts_until(2).await, (Ok(2), 2));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_retry() {
+        assert_eq!(attempts_until(3).await, (Err("failed"), 2));
+    }
+}
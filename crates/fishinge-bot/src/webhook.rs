@@ -0,0 +1,156 @@
+use std::time::Duration;
+
+use fishinge_bot::{Catch, Fish};
+use log::{debug, error, warn};
+use serde::Serialize;
+use url::Url;
+
+/// How often a failed delivery is retried before it is dropped
+const MAX_RETRIES: u32 = 2;
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Body posted to the webhook for a notable catch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct CatchNotification {
+    pub channel: String,
+    pub user: String,
+    pub fish: String,
+    pub value: f32,
+    pub weight: Option<f32>,
+}
+
+/// Posts notable catches to an external url, e.g. to trigger stream alerts.
+#[derive(Debug, Clone)]
+pub(crate) struct CatchWebhook {
+    url: Url,
+    http: reqwest::Client,
+    /// Notify about catches of rare fishes
+    rare: bool,
+    /// Notify about catches worth at least this much
+    min_value: Option<f32>,
+}
+
+impl CatchWebhook {
+    pub(crate) fn new(url: Url, rare: bool, min_value: Option<f32>) -> Self {
+        Self {
+            url,
+            http: reqwest::Client::new(),
+            rare,
+            min_value,
+        }
+    }
+
+    /// Whether catching `catch` of `fish` should be posted
+    pub(crate) fn should_notify(&self, fish: &Fish, catch: &Catch) -> bool {
+        (self.rare && fish.is_rare())
+            || self
+                .min_value
+                .map_or(false, |min_value| catch.value >= min_value)
+    }
+
+    /// Post `notification` in the background. Failed deliveries are logged
+    /// and retried at most [`MAX_RETRIES`] times.
+    pub(crate) fn notify(&self, notification: CatchNotification) {
+        let http = self.http.clone();
+        let url = self.url.clone();
+
+        tokio::spawn(async move {
+            for attempt in 0..=MAX_RETRIES {
+                if attempt > 0 {
+                    tokio::time::sleep(RETRY_DELAY * attempt).await;
+                }
+
+                let result = http
+                    .post(url.clone())
+                    .timeout(REQUEST_TIMEOUT)
+                    .json(&notification)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status());
+
+                match result {
+                    Ok(_) => {
+                        debug!("Delivered catch webhook for {}", notification.fish);
+                        return;
+                    }
+                    Err(err) => warn!(
+                        "Could not deliver catch webhook (attempt {}/{}): {err}",
+                        attempt + 1,
+                        MAX_RETRIES + 1
+                    ),
+                }
+            }
+
+            error!(
+                "Dropping catch webhook for {} caught by {}",
+                notification.fish, notification.user
+            );
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fishinge_bot::WeightDistribution;
+    use test_case::test_case;
+
+    use super::*;
+
+    fn fish(count: u32) -> Fish {
+        Fish {
+            id: 1,
+            name: "🐳".to_string(),
+            count,
+            base_value: 100,
+            weight_range: None,
+            weight_distribution: WeightDistribution::Uniform,
+            population: 1000,
+        }
+    }
+
+    fn webhook(rare: bool, min_value: Option<f32>) -> CatchWebhook {
+        CatchWebhook::new("http://localhost/catch".parse().unwrap(), rare, min_value)
+    }
+
+    #[test_case(true, None, 10, 100.0, true ; "rare fish")]
+    #[test_case(true, None, 500, 100.0, false ; "common fish")]
+    #[test_case(false, None, 10, 100.0, false ; "rare fish ignored")]
+    #[test_case(false, Some(100.0), 500, 100.0, true ; "value at threshold")]
+    #[test_case(false, Some(100.0), 500, 99.0, false ; "value below threshold")]
+    #[test_case(true, Some(100.0), 10, 0.0, true ; "rare fish below threshold")]
+    fn should_notify(rare: bool, min_value: Option<f32>, count: u32, value: f32, expected: bool) {
+        let catch = Catch {
+            fish_name: "🐳".to_string(),
+            weight: None,
+            value,
+        };
+
+        assert_eq!(
+            webhook(rare, min_value).should_notify(&fish(count), &catch),
+            expected
+        );
+    }
+
+    #[test]
+    fn notification_payload() {
+        let notification = CatchNotification {
+            channel: "chronophylos".to_string(),
+            user: "alice".to_string(),
+            fish: "🐳".to_string(),
+            value: 120.5,
+            weight: Some(2.5),
+        };
+
+        assert_eq!(
+            serde_json::to_value(notification).unwrap(),
+            serde_json::json!({
+                "channel": "chronophylos",
+                "user": "alice",
+                "fish": "🐳",
+                "value": 120.5,
+                "weight": 2.5,
+            })
+        );
+    }
+}
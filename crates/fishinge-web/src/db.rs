@@ -1,5 +1,7 @@
 use async_trait::async_trait;
+use database::PoolSize;
 use rocket_db_pools::{rocket::figment::Figment, Config, Database};
+use sea_orm::DbErr;
 
 #[derive(Debug)]
 pub struct RocketDbPool {
@@ -14,7 +16,11 @@ impl rocket_db_pools::Pool for RocketDbPool {
 
     async fn init(figment: &Figment) -> Result<Self, Self::Error> {
         let config = figment.extract::<Config>().unwrap();
-        let conn = sea_orm::Database::connect(&config.url).await.unwrap();
+        let pool_size = PoolSize::from_env().map_err(|err| DbErr::Custom(err.to_string()))?;
+
+        let conn = database::connect(config.url, pool_size)
+            .await
+            .map_err(|err| DbErr::Custom(err.to_string()))?;
         Ok(RocketDbPool { conn })
     }
 
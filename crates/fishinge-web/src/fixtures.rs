@@ -0,0 +1,119 @@
+//! Rows for tests that run queries against an in-memory database
+
+use chrono::{Duration, Utc};
+use database::{
+    entities::{bundle, catches, fish_bundle, fishes, prelude::*, seasons, users},
+    GIFT_FISH,
+};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+};
+
+/// The season a migrated database starts with
+pub async fn first_season(db: &DatabaseConnection) -> seasons::Model {
+    Seasons::find().one(db).await.unwrap().unwrap()
+}
+
+/// The fish gifts are recorded with
+pub async fn gift_fish(db: &DatabaseConnection) -> fishes::Model {
+    Fishes::find()
+        .filter(fishes::Column::Name.eq(GIFT_FISH))
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+/// Insert a season with the fishes of the first one that ended long ago
+pub async fn insert_past_season(db: &DatabaseConnection) -> seasons::Model {
+    let first = first_season(db).await;
+
+    seasons::ActiveModel {
+        name: ActiveValue::set("Spring 2020".to_string()),
+        start: ActiveValue::set((Utc::now() - Duration::days(2000)).into()),
+        end: ActiveValue::set(Some((Utc::now() - Duration::days(1900)).into())),
+        bundle_id: ActiveValue::set(first.bundle_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+pub async fn insert_user(db: &DatabaseConnection, name: &str, is_bot: bool) -> users::Model {
+    users::ActiveModel {
+        name: ActiveValue::set(name.to_string()),
+        last_fished: ActiveValue::set(Utc::now().into()),
+        is_bot: ActiveValue::set(is_bot),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+pub async fn insert_fish(
+    db: &DatabaseConnection,
+    name: &str,
+    count: i32,
+    is_trash: bool,
+) -> fishes::Model {
+    fishes::ActiveModel {
+        name: ActiveValue::set(name.to_string()),
+        html_name: ActiveValue::set(name.to_string()),
+        count: ActiveValue::set(count),
+        base_value: ActiveValue::set(10.0),
+        max_weight: ActiveValue::set(0.0),
+        min_weight: ActiveValue::set(0.0),
+        is_trash: ActiveValue::set(is_trash),
+        weight_distribution: ActiveValue::set("uniform".to_string()),
+        weight_mean: ActiveValue::set(None),
+        weight_stddev: ActiveValue::set(None),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+/// Insert a bundle of `fishes` and return its id
+pub async fn insert_bundle(db: &DatabaseConnection, fishes: &[&fishes::Model]) -> i32 {
+    let bundle = bundle::ActiveModel {
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap();
+
+    FishBundle::insert_many(fishes.iter().map(|fish| fish_bundle::ActiveModel {
+        fish_id: ActiveValue::set(fish.id),
+        bundle_id: ActiveValue::set(bundle.id),
+    }))
+    .exec(db)
+    .await
+    .unwrap();
+
+    bundle.id
+}
+
+pub async fn insert_catch(
+    db: &DatabaseConnection,
+    season_id: i32,
+    user: &users::Model,
+    fish: &fishes::Model,
+    value: f32,
+    weight: Option<f32>,
+) -> catches::Model {
+    catches::ActiveModel {
+        user_id: ActiveValue::set(user.id),
+        fish_id: ActiveValue::set(fish.id),
+        weight: ActiveValue::set(weight),
+        caught_at: ActiveValue::set(Utc::now().into()),
+        value: ActiveValue::set(value),
+        season_id: ActiveValue::set(season_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
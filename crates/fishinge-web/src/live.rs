@@ -0,0 +1,191 @@
+//! Broadcast new catches to overlays connected to `/live`.
+//!
+//! The bot runs in another process, so new catches are found by polling the
+//! catches table every [`POLL_INTERVAL`] for ids above the highest id seen so
+//! far. Every connected client gets its own receiver of the broadcast channel.
+
+use std::time::Duration;
+
+use database::entities::{catches, fishes, prelude::*, users};
+use log::{debug, error, warn};
+use rocket::{futures::SinkExt, get, State};
+use rocket_ws::{Channel, Message, WebSocket};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, DeriveColumn, EntityTrait, EnumIter, FromQueryResult,
+    JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Select,
+};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// How often the catches table is checked for new catches
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Number of catches a slow client may fall behind before it misses some
+const CHANNEL_CAPACITY: usize = 64;
+
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct LiveCatch {
+    #[serde(skip)]
+    id: i32,
+    user: String,
+    fish: String,
+    weight: Option<f32>,
+    value: f32,
+}
+
+pub struct LiveCatches(broadcast::Sender<LiveCatch>);
+
+impl LiveCatches {
+    pub fn new() -> Self {
+        Self(broadcast::channel(CHANNEL_CAPACITY).0)
+    }
+
+    pub fn sender(&self) -> broadcast::Sender<LiveCatch> {
+        self.0.clone()
+    }
+}
+
+/// Catches with an id above `last_id`, oldest first.
+fn new_catches_query(last_id: i32) -> Select<Catches> {
+    Catches::find()
+        .filter(catches::Column::Id.gt(last_id))
+        .order_by_asc(catches::Column::Id)
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .select_only()
+        .column(catches::Column::Id)
+        .column_as(users::Column::Name, "user")
+        .column_as(fishes::Column::Name, "fish")
+        .column(catches::Column::Weight)
+        .column(catches::Column::Value)
+}
+
+async fn latest_catch_id(db: &DatabaseConnection) -> Result<i32, DbErr> {
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+    enum QueryAs {
+        Id,
+    }
+
+    let id = Catches::find()
+        .select_only()
+        .column_as(catches::Column::Id.max(), "id")
+        .into_values::<Option<i32>, QueryAs>()
+        .one(db)
+        .await?;
+
+    Ok(id.flatten().unwrap_or_default())
+}
+
+/// Poll for new catches and broadcast them. Catches made before the web
+/// server started are not broadcast.
+pub async fn poll(db: DatabaseConnection, sender: broadcast::Sender<LiveCatch>) {
+    let mut last_id = match latest_catch_id(&db).await {
+        Ok(id) => id,
+        Err(err) => {
+            error!("Error querying latest catch: {err}");
+            return;
+        }
+    };
+
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let catches = match new_catches_query(last_id)
+            .into_model::<LiveCatch>()
+            .all(&db)
+            .await
+        {
+            Ok(catches) => catches,
+            Err(err) => {
+                error!("Error querying new catches: {err}");
+                continue;
+            }
+        };
+
+        for catch in catches {
+            debug!("Broadcasting catch {}", catch.id);
+            last_id = catch.id;
+
+            // sending only fails if nobody is listening
+            sender.send(catch).ok();
+        }
+    }
+}
+
+#[get("/live")]
+pub fn live(ws: WebSocket, live: &State<LiveCatches>) -> Channel<'static> {
+    let mut receiver = live.0.subscribe();
+
+    ws.channel(move |mut stream| {
+        Box::pin(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(catch) => {
+                        let json =
+                            serde_json::to_string(&catch).expect("catches are always serializable");
+                        stream.send(Message::Text(json)).await?;
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Live client missed {skipped} catches");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+
+            Ok(())
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{first_season, insert_catch, insert_fish, insert_user};
+
+    #[tokio::test]
+    async fn new_catches_after_last_id() {
+        let db = database::memory_connection().await.unwrap();
+        let season = first_season(&db).await;
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        let seen = insert_catch(&db, season.id, &alice, &fish, 5.0, None).await;
+        insert_catch(&db, season.id, &bob, &fish, 10.0, Some(1.5)).await;
+        insert_catch(&db, season.id, &alice, &fish, 20.0, None).await;
+
+        let catches: Vec<_> = new_catches_query(seen.id)
+            .into_model::<LiveCatch>()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|catch| (catch.user, catch.fish, catch.weight, catch.value))
+            .collect();
+
+        assert_eq!(
+            catches,
+            [
+                ("bob".to_string(), "🐟".to_string(), Some(1.5), 10.0),
+                ("alice".to_string(), "🐟".to_string(), None, 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn live_catch_json() {
+        let catch = LiveCatch {
+            id: 42,
+            user: "alice".to_string(),
+            fish: "🐟".to_string(),
+            weight: Some(1.5),
+            value: 10.0,
+        };
+
+        assert_eq!(
+            serde_json::to_string(&catch).unwrap(),
+            r#"{"user":"alice","fish":"🐟","weight":1.5,"value":10.0}"#
+        );
+    }
+}
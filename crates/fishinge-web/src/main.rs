@@ -1,40 +1,79 @@
 mod db;
+#[cfg(test)]
+mod fixtures;
+mod live;
+mod stats_stream;
 
-use std::{collections::HashMap, env};
+use std::{
+    collections::HashMap,
+    env,
+    net::{AddrParseError, IpAddr, SocketAddr},
+    num::ParseIntError,
+    path::PathBuf,
+};
 
 use chrono::{DateTime, Utc};
-use database::entities::{catches, fishes, prelude::*, users};
+use database::{
+    definition::FishDefinition,
+    entities::{catches, fish_bundle, fishes, prelude::*, seasons, users},
+    secret::{secret_var, SecretError},
+    tier::{tier, Tier},
+    PoolSize, GIFT_FISH,
+};
 use db::Db;
 use dotenvy::dotenv;
-use log::{debug, error, warn};
-use rocket::{catch, catchers, fs::FileServer, get, http::Status, routes, Build, FromForm, Rocket};
-use rocket_db_pools::{Connection, Database};
+use live::LiveCatches;
+use log::{debug, error, info, warn};
+use rocket::{
+    catch, catchers, config::TlsConfig, fairing::AdHoc, fs::FileServer, get, http::Status, routes,
+    serde::json::Json, Build, FromForm, Rocket,
+};
+use rocket_db_pools::{Connection, Database, Pool};
 use rocket_dyn_templates::{
     context,
     tera::{Result as TeraResult, Value},
     Template,
 };
 use sea_orm::{
-    ColumnTrait, DeriveColumn, EntityTrait, EnumIter, FromQueryResult, JoinType, QueryFilter,
-    QueryOrder, QuerySelect, RelationTrait,
+    sea_query::{Alias, Expr, Order, Query},
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DeriveColumn, EntityTrait, EnumIter,
+    FromQueryResult, JoinType, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
+    Select, Statement,
 };
 use serde::Serialize;
+use stats_stream::StatsFeed;
 
 #[derive(Debug, thiserror::Error)]
 enum Error {
-    #[error("Environent variable {name} not set")]
-    EnvarNotSet {
-        source: std::env::VarError,
-        name: &'static str,
-    },
+    #[error("Could not read secret")]
+    Secret(#[from] SecretError),
+
+    #[error("Invalid database configuration")]
+    Database(#[from] database::Error),
 
     #[error("Joining task failed")]
     JoinTask(#[from] tokio::task::JoinError),
-}
 
-#[inline]
-fn env_var(name: &'static str) -> Result<String, Error> {
-    env::var(name).map_err(|source| Error::EnvarNotSet { source, name })
+    #[error("Bind address {value:?} is not a valid IP address")]
+    InvalidBindAddr {
+        source: AddrParseError,
+        value: String,
+    },
+
+    #[error("Listen address {value:?} is not a valid socket address")]
+    InvalidListen {
+        source: AddrParseError,
+        value: String,
+    },
+
+    #[error("Port {value:?} is not a valid port")]
+    InvalidPort {
+        source: ParseIntError,
+        value: String,
+    },
+
+    #[error("TLS needs both TLS_CERT and TLS_KEY, but {missing} is not set")]
+    IncompleteTls { missing: &'static str },
 }
 
 #[rocket::main]
@@ -47,6 +86,97 @@ async fn main() -> Result<(), eyre::Error> {
     Ok(())
 }
 
+const DEFAULT_BIND_ADDR: &str = "0.0.0.0";
+const DEFAULT_PORT: &str = "3030";
+
+/// Read the address to listen on from `LISTEN`, like `127.0.0.1:8080`, or
+/// else from `BIND_ADDR` and `PORT`.
+///
+/// `None` if none of them are set, Rocket's own configuration applies then.
+fn bind_addr() -> Result<Option<SocketAddr>, Error> {
+    select_bind_addr(
+        env::var("LISTEN").ok(),
+        env::var("BIND_ADDR").ok(),
+        env::var("PORT").ok(),
+    )
+}
+
+fn select_bind_addr(
+    listen: Option<String>,
+    addr: Option<String>,
+    port: Option<String>,
+) -> Result<Option<SocketAddr>, Error> {
+    if let Some(listen) = listen {
+        return parse_listen(&listen).map(Some);
+    }
+
+    if addr.is_none() && port.is_none() {
+        return Ok(None);
+    }
+
+    parse_bind_addr(
+        addr.as_deref().unwrap_or(DEFAULT_BIND_ADDR),
+        port.as_deref().unwrap_or(DEFAULT_PORT),
+    )
+    .map(Some)
+}
+
+fn parse_bind_addr(addr: &str, port: &str) -> Result<SocketAddr, Error> {
+    let ip: IpAddr = addr
+        .trim()
+        .parse()
+        .map_err(|source| Error::InvalidBindAddr {
+            source,
+            value: addr.to_string(),
+        })?;
+    let port: u16 = port.trim().parse().map_err(|source| Error::InvalidPort {
+        source,
+        value: port.to_string(),
+    })?;
+
+    Ok(SocketAddr::new(ip, port))
+}
+
+fn parse_listen(listen: &str) -> Result<SocketAddr, Error> {
+    listen
+        .trim()
+        .parse()
+        .map_err(|source| Error::InvalidListen {
+            source,
+            value: listen.to_string(),
+        })
+}
+
+/// Paths of the certificate chain and private key to serve HTTPS with
+#[derive(Debug, PartialEq, Eq)]
+struct TlsPaths {
+    cert: PathBuf,
+    key: PathBuf,
+}
+
+/// Read `TLS_CERT` and `TLS_KEY`. Without either the website is served over
+/// plain HTTP.
+fn tls_paths() -> Result<Option<TlsPaths>, Error> {
+    parse_tls_paths(env::var("TLS_CERT").ok(), env::var("TLS_KEY").ok())
+}
+
+fn parse_tls_paths(cert: Option<String>, key: Option<String>) -> Result<Option<TlsPaths>, Error> {
+    let cert = cert.filter(|cert| !cert.trim().is_empty());
+    let key = key.filter(|key| !key.trim().is_empty());
+
+    match (cert, key) {
+        (Some(cert), Some(key)) => Ok(Some(TlsPaths {
+            cert: cert.trim().into(),
+            key: key.trim().into(),
+        })),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err(Error::IncompleteTls { missing: "TLS_KEY" }),
+        (None, Some(_)) => Err(Error::IncompleteTls {
+            missing: "TLS_CERT",
+        }),
+    }
+}
+
 fn round<const N: usize>(value: &Value, _args: &HashMap<String, Value>) -> TeraResult<Value> {
     match value {
         Value::Number(n) => {
@@ -58,25 +188,76 @@ fn round<const N: usize>(value: &Value, _args: &HashMap<String, Value>) -> TeraR
 }
 
 fn rocket() -> Result<Rocket<Build>, Error> {
-    let figment = rocket::Config::figment().merge((
-        "databases.postgres",
-        rocket_db_pools::Config {
-            url: env_var("DATABASE_URL")?,
-            min_connections: None,
-            max_connections: 1024,
-            connect_timeout: 3,
-            idle_timeout: None,
-        },
-    ));
+    let bind_addr = bind_addr()?;
+    // checked here to fail before launching, the pool reads it again
+    PoolSize::from_env()?;
+    // the pool is sized and configured by database::connect
+    let mut figment =
+        rocket::Config::figment().merge(("databases.postgres.url", secret_var("DATABASE_URL")?));
+
+    if let Some(bind_addr) = bind_addr {
+        figment = figment
+            .merge(("address", bind_addr.ip()))
+            .merge(("port", bind_addr.port()));
+    }
+
+    match tls_paths()? {
+        Some(tls) => {
+            info!("Serving HTTPS with certificate {:?}", tls.cert);
+            figment = figment.merge(("tls", TlsConfig::from_paths(tls.cert, tls.key)));
+        }
+        None => info!("Serving plain HTTP"),
+    }
 
     let rocket = rocket::custom(figment)
         .attach(Db::init())
+        .manage(LiveCatches::new())
+        .attach(AdHoc::on_liftoff("Live catches", |rocket| {
+            Box::pin(async move {
+                match (Db::fetch(rocket), rocket.state::<LiveCatches>()) {
+                    (Some(db), Some(live)) => {
+                        tokio::spawn(live::poll(db.conn.clone(), live.sender()));
+                    }
+                    _ => error!("Could not start polling live catches"),
+                }
+            })
+        }))
+        .manage(StatsFeed::new())
+        .attach(AdHoc::on_liftoff("Stats stream", |rocket| {
+            Box::pin(async move {
+                match (Db::fetch(rocket), rocket.state::<StatsFeed>()) {
+                    (Some(db), Some(feed)) => {
+                        tokio::spawn(stats_stream::refresh(db.conn.clone(), feed.sender()));
+                    }
+                    _ => error!("Could not start refreshing the stats stream"),
+                }
+            })
+        }))
         .attach(Template::custom(|engine| {
             engine.tera.register_filter("round1", round::<1>);
             engine.tera.register_filter("round2", round::<2>);
         }))
         .register("/", catchers![internal_server_error])
-        .mount("/", routes![index, leaderboard, get_fishes, user, stats])
+        .mount(
+            "/",
+            routes![
+                index,
+                healthz,
+                readyz,
+                leaderboard,
+                top_catch_leaderboard,
+                get_fishes,
+                user,
+                stats,
+                stats_stream::stream,
+                performance,
+                fish,
+                fish_stats,
+                season_results,
+                export_fishes,
+                live::live
+            ],
+        )
         .mount(
             "/",
             FileServer::from(
@@ -97,20 +278,73 @@ fn index() -> Template {
     Template::render("index", context! {})
 }
 
+#[derive(Serialize)]
+struct Health {
+    status: &'static str,
+}
+
+/// Health check running a trivial query, so it reflects whether the database
+/// is reachable right now. The connection is taken here instead of by a
+/// request guard, so the status is reported even if there is none.
+#[get("/healthz")]
+async fn healthz(db: &Db) -> (Status, Json<Health>) {
+    let reachable = match db.get().await {
+        Ok(conn) => database_reachable(&conn).await,
+        Err(err) => {
+            error!("Could not get a database connection: {err}");
+            false
+        }
+    };
+
+    if reachable {
+        (Status::Ok, Json(Health { status: "ok" }))
+    } else {
+        (
+            Status::ServiceUnavailable,
+            Json(Health {
+                status: "db_unreachable",
+            }),
+        )
+    }
+}
+
+/// Readiness probe, fails with 503 if the database can not be queried
+#[get("/readyz")]
+async fn readyz(conn: Connection<Db>) -> Result<&'static str, Status> {
+    if database_reachable(&conn).await {
+        Ok("ok")
+    } else {
+        Err(Status::ServiceUnavailable)
+    }
+}
+
+async fn database_reachable(conn: &DatabaseConnection) -> bool {
+    let select_one = Statement::from_string(conn.get_database_backend(), "SELECT 1".to_owned());
+
+    match conn.execute(select_one).await {
+        Ok(_) => true,
+        Err(err) => {
+            error!("Database is not reachable: {err}");
+            false
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Default, FromForm)]
 struct LeaderboardFilter {
     include_bots: bool,
 }
 
-#[get("/leaderboard?<filter>")]
-async fn leaderboard(conn: Connection<Db>, filter: LeaderboardFilter) -> Result<Template, Status> {
-    #[derive(FromQueryResult, Serialize)]
-    struct UserWithScore {
-        name: String,
-        is_bot: bool,
-        score: f32,
-    }
+#[derive(FromQueryResult, Serialize)]
+struct UserWithScore {
+    name: String,
+    is_bot: bool,
+    score: f32,
+}
 
+/// Rank users by their summed catch values, optionally only counting the
+/// catches of one season.
+fn leaderboard_query(season_id: Option<i32>, include_bots: bool) -> Select<Catches> {
     #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
     enum QueryAs {
         Score,
@@ -126,15 +360,28 @@ async fn leaderboard(conn: Connection<Db>, filter: LeaderboardFilter) -> Result<
         .column(users::Column::Name)
         .column(users::Column::IsBot);
     sea_orm::QuerySelect::query(&mut query).conditions(
-        !filter.include_bots,
+        !include_bots,
         |q| {
             q.and_where(users::Column::IsBot.eq(false));
         },
         |_| (),
     );
 
+    if let Some(season_id) = season_id {
+        query = query.filter(catches::Column::SeasonId.eq(season_id));
+    }
+
+    query
+}
+
+#[get("/leaderboard?<filter>")]
+async fn leaderboard(conn: Connection<Db>, filter: LeaderboardFilter) -> Result<Template, Status> {
     debug!("Querying leaderboard");
-    let users = match query.into_model::<UserWithScore>().all(&*conn).await {
+    let users = match leaderboard_query(None, filter.include_bots)
+        .into_model::<UserWithScore>()
+        .all(&*conn)
+        .await
+    {
         Ok(users) => users
             .into_iter()
             .filter(|u| u.score.abs() > f32::EPSILON)
@@ -148,150 +395,531 @@ async fn leaderboard(conn: Connection<Db>, filter: LeaderboardFilter) -> Result<
     Ok(Template::render("leaderboard", context! {users: &users}))
 }
 
-#[get("/fishes")]
-async fn get_fishes(conn: Connection<Db>) -> Result<Template, Status> {
-    #[derive(Serialize)]
-    struct Row {
-        html_name: String,
-        chance: f32,
-        base_value: f32,
-        min_weight: f32,
-        max_weight: f32,
-        is_trash: bool,
+#[derive(FromQueryResult, Serialize)]
+struct UserTopCatch {
+    name: String,
+    is_bot: bool,
+    fish_name: String,
+    weight: Option<f32>,
+    value: f32,
+}
+
+/// Rank users by their single most valuable catch. Ties within a user are
+/// broken by the earlier catch and gifts are left out.
+fn top_catch_leaderboard_query(include_bots: bool) -> Select<Catches> {
+    let top = Alias::new("top");
+
+    let top_catch_of_user = Query::select()
+        .column((top.clone(), catches::Column::Id))
+        .from_as(Catches, top.clone())
+        .inner_join(
+            Fishes,
+            Expr::col((Fishes, fishes::Column::Id)).equals((top.clone(), catches::Column::FishId)),
+        )
+        .and_where(
+            Expr::col((top.clone(), catches::Column::UserId))
+                .equals((Catches, catches::Column::UserId)),
+        )
+        .and_where(Expr::col((Fishes, fishes::Column::Name)).ne(GIFT_FISH))
+        .order_by((top.clone(), catches::Column::Value), Order::Desc)
+        .order_by((top, catches::Column::Id), Order::Asc)
+        .limit(1)
+        .to_owned();
+
+    let query = Catches::find()
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .filter(catches::Column::Id.in_subquery(top_catch_of_user))
+        .order_by_desc(catches::Column::Value)
+        .select_only()
+        .column(users::Column::Name)
+        .column(users::Column::IsBot)
+        .column_as(fishes::Column::Name, "fish_name")
+        .column(catches::Column::Weight)
+        .column(catches::Column::Value);
+
+    if include_bots {
+        query
+    } else {
+        query.filter(users::Column::IsBot.eq(false))
     }
+}
 
-    debug!("Querying fishes");
-    let fishes = match Fishes::find().all(&*conn).await {
-        Ok(fishes) => fishes,
+#[get("/leaderboard/topcatch?<filter>")]
+async fn top_catch_leaderboard(
+    conn: Connection<Db>,
+    filter: LeaderboardFilter,
+) -> Result<Template, Status> {
+    debug!("Querying top catch leaderboard");
+    let users = match top_catch_leaderboard_query(filter.include_bots)
+        .into_model::<UserTopCatch>()
+        .all(&*conn)
+        .await
+    {
+        Ok(users) => users,
         Err(err) => {
-            error!("Error querying fishes: {err}");
+            error!("Error querying top catch leaderboard: {err}");
             return Err(Status::InternalServerError);
         }
     };
 
-    let population: i32 = fishes.iter().map(|fish| fish.count).sum();
+    Ok(Template::render("topcatch", context! {users: &users}))
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Standing {
+    rank: usize,
+    user: String,
+    score: f32,
+}
+
+/// Rank users sorted by score. Users with the same score share a rank.
+fn rank_standings(users: Vec<UserWithScore>) -> Vec<Standing> {
+    let mut standings: Vec<Standing> = Vec::with_capacity(users.len());
+
+    for (index, user) in users.into_iter().enumerate() {
+        let rank = match standings.last() {
+            Some(previous) if previous.score == user.score => previous.rank,
+            _ => index + 1,
+        };
+
+        standings.push(Standing {
+            rank,
+            user: user.name,
+            score: user.score,
+        });
+    }
+
+    standings
+}
+
+#[derive(Serialize)]
+struct SeasonResults {
+    season: String,
+    standings: Vec<Standing>,
+}
+
+#[get("/api/seasons/<id>/results?<filter>")]
+async fn season_results(
+    conn: Connection<Db>,
+    id: i32,
+    filter: LeaderboardFilter,
+) -> Result<Json<SeasonResults>, Status> {
+    debug!("Querying season {id}");
+    let season = match Seasons::find_by_id(id).one(&*conn).await {
+        Ok(Some(season)) => season,
+        Ok(None) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying season {id}: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    debug!("Querying results of season {}", season.name);
+    let users = match leaderboard_query(Some(season.id), filter.include_bots)
+        .into_model::<UserWithScore>()
+        .all(&*conn)
+        .await
+    {
+        Ok(users) => users,
+        Err(err) => {
+            error!("Error querying results of season {}: {err}", season.name);
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    Ok(Json(SeasonResults {
+        season: season.name,
+        standings: rank_standings(users),
+    }))
+}
+
+const FISHES_PER_PAGE: u64 = 50;
+const MAX_FISHES_PER_PAGE: u64 = 200;
+
+#[derive(Debug, PartialEq, Default, FromForm, Serialize)]
+struct FishesFilter {
+    hide_trash: bool,
+    page: Option<u64>,
+    per_page: Option<u64>,
+}
+
+impl FishesFilter {
+    /// Requested page, starting at 1
+    fn page(&self) -> u64 {
+        self.page.unwrap_or(1).max(1)
+    }
+
+    fn per_page(&self) -> u64 {
+        self.per_page
+            .unwrap_or(FISHES_PER_PAGE)
+            .clamp(1, MAX_FISHES_PER_PAGE)
+    }
+
+    /// Number of fishes before the requested page. Pages far past the end
+    /// are empty instead of overflowing the offset, which databases take as
+    /// a signed 64 bit integer.
+    fn offset(&self) -> u64 {
+        (self.page() - 1)
+            .saturating_mul(self.per_page())
+            .min(i64::MAX as u64)
+    }
+
+    /// Number of pages needed to show `fishes` fishes. There is always at
+    /// least one page, even if it is empty.
+    fn total_pages(&self, fishes: u64) -> u64 {
+        let per_page = self.per_page();
+
+        (fishes / per_page + u64::from(fishes % per_page != 0)).max(1)
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct FishRow {
+    id: i32,
+    html_name: String,
+    chance: f32,
+    base_value: f32,
+    min_weight: f32,
+    max_weight: f32,
+    is_trash: bool,
+    tier: Tier,
+}
 
-    let mut rows: Vec<_> = fishes
+/// Fishes matching `filter`, most likely fishes first.
+fn fishes_query(filter: &FishesFilter) -> Select<Fishes> {
+    let query = Fishes::find()
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .order_by_desc(fishes::Column::Count)
+        .order_by_asc(fishes::Column::Id);
+
+    if filter.hide_trash {
+        query.filter(fishes::Column::IsTrash.eq(false))
+    } else {
+        query
+    }
+}
+
+/// Rows of the fishes table, with chances relative to `population`.
+fn fish_rows(fishes: Vec<fishes::Model>, population: i64) -> Vec<FishRow> {
+    fishes
         .into_iter()
-        .map(|fish| Row {
+        .map(|fish| FishRow {
+            id: fish.id,
             html_name: fish.html_name,
             chance: fish.count as f32 / population as f32,
             base_value: fish.base_value,
             min_weight: fish.min_weight,
             max_weight: fish.max_weight,
             is_trash: fish.is_trash,
+            tier: tier(fish.count.into(), population),
         })
-        .collect();
-
-    rows.sort_by_key(|row| (row.chance * 10000.0) as u64);
-    rows.reverse();
-
-    Ok(Template::render("fishes", context! {fishes: &rows}))
+        .collect()
 }
 
-#[get("/user/<username>")]
-async fn user(conn: Connection<Db>, username: String) -> Result<Template, Status> {
-    debug!("Quering user {username}");
-    let user = match Users::find()
-        .filter(users::Column::Name.eq(username.to_lowercase()))
+#[get("/fishes?<filter..>")]
+async fn get_fishes(conn: Connection<Db>, filter: FishesFilter) -> Result<Template, Status> {
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+    enum QueryAs {
+        Count,
+    }
+
+    // chances are relative to every fish, even if trash is hidden
+    debug!("Querying population");
+    let population: i64 = match Fishes::find()
+        .select_only()
+        .column_as(fishes::Column::Count.sum(), "count")
+        .into_values::<Option<i64>, QueryAs>()
         .one(&*conn)
         .await
     {
-        Ok(Some(user)) => user,
-        Ok(None) => return Err(Status::NotFound),
+        Ok(population) => population.flatten().unwrap_or_default(),
         Err(err) => {
-            error!("Error querying user {username}: {err}");
+            error!("Error querying population: {err}");
             return Err(Status::InternalServerError);
         }
     };
 
-    #[derive(FromQueryResult, Serialize)]
-    struct TopCatch {
-        name: String,
-        weight: Option<f32>,
-        value: f32,
-    }
+    debug!("Counting fishes");
+    let total_fishes = match fishes_query(&filter).count(&*conn).await {
+        Ok(total_fishes) => total_fishes,
+        Err(err) => {
+            error!("Error counting fishes: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
 
-    debug!("Querying top catch");
-    let top_catch = match Catches::find()
-        .filter(catches::Column::UserId.eq(user.id))
-        .order_by_desc(catches::Column::Value)
-        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
-        .select_only()
-        .column(fishes::Column::Name)
-        .column(catches::Column::Value)
-        .column(catches::Column::Weight)
-        .into_model::<TopCatch>()
-        .one(&*conn)
+    let (page, per_page) = (filter.page(), filter.per_page());
+
+    debug!("Querying fishes");
+    let fishes = match fishes_query(&filter)
+        .limit(per_page)
+        .offset(filter.offset())
+        .all(&*conn)
         .await
     {
-        Ok(Some(top_catch)) => top_catch,
+        Ok(fishes) => fishes,
+        Err(err) => {
+            error!("Error querying fishes: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    let rows = fish_rows(fishes, population);
+
+    Ok(Template::render(
+        "fishes",
+        context! {
+            fishes: &rows,
+            hide_trash: filter.hide_trash,
+            page: page,
+            per_page: per_page,
+            total_pages: filter.total_pages(total_fishes),
+        },
+    ))
+}
+
+/// The season running right now, like the bot picks it
+fn active_season_query(now: DateTime<Utc>) -> Select<Seasons> {
+    Seasons::find()
+        .filter(seasons::Column::Start.lt(now))
+        .filter(
+            seasons::Column::End
+                .gt(now)
+                .or(seasons::Column::End.is_null()),
+        )
+        .order_by_desc(seasons::Column::Start)
+}
+
+/// The fishes of the bundle with `bundle_id`
+fn bundle_fishes_query(bundle_id: i32) -> Select<Fishes> {
+    Fishes::find()
+        .join(
+            JoinType::InnerJoin,
+            fish_bundle::Relation::Fishes.def().rev(),
+        )
+        .filter(fish_bundle::Column::BundleId.eq(bundle_id))
+        .order_by_asc(fishes::Column::Id)
+}
+
+/// Export the fishes of the active season, e.g. for backups
+#[get("/api/fishes")]
+async fn export_fishes(conn: Connection<Db>) -> Result<Json<Vec<FishDefinition>>, Status> {
+    debug!("Querying active season");
+    let season = match active_season_query(Utc::now()).one(&*conn).await {
+        Ok(Some(season)) => season,
         Ok(None) => return Err(Status::NotFound),
         Err(err) => {
-            error!("Error querying top catch for {username}: {err}");
+            error!("Error querying active season: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    debug!("Querying fishes of season {}", season.name);
+    match bundle_fishes_query(season.bundle_id).all(&*conn).await {
+        Ok(fishes) => Ok(Json(fishes.into_iter().map(FishDefinition::from).collect())),
+        Err(err) => {
+            error!("Error querying fishes of season {}: {err}", season.name);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[get("/fish/<id>")]
+async fn fish(conn: Connection<Db>, id: i32) -> Result<Template, Status> {
+    debug!("Querying fish {id}");
+    let fish = match Fishes::find_by_id(id).one(&*conn).await {
+        Ok(Some(fish)) => fish,
+        Ok(None) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying fish {id}: {err}");
             return Err(Status::InternalServerError);
         }
     };
 
     #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
     enum QueryAs {
-        Score,
+        Count,
     }
 
-    debug!("Querying total score");
-    let total_score: f32 = match Catches::find()
-        .filter(catches::Column::UserId.eq(user.id))
+    debug!("Querying population");
+    let population: i64 = match Fishes::find()
         .select_only()
-        .column_as(catches::Column::Value.sum(), "score")
-        .into_values::<_, QueryAs>()
+        .column_as(fishes::Column::Count.sum(), "count")
+        .into_values::<Option<i64>, QueryAs>()
         .one(&*conn)
         .await
     {
-        Ok(Some(score)) => score,
-        Ok(None) => return Err(Status::NotFound),
+        Ok(population) => population.flatten().unwrap_or_default(),
         Err(err) => {
-            error!("Error querying score for {username}: {err}");
+            error!("Error querying population: {err}");
             return Err(Status::InternalServerError);
         }
     };
 
-    debug!("Querying total caught fishes");
+    debug!("Querying times caught");
     let total_catches: i64 = match Catches::find()
-        .filter(catches::Column::UserId.eq(user.id))
+        .filter(catches::Column::FishId.eq(fish.id))
         .select_only()
-        .column_as(catches::Column::Id.count(), "score")
+        .column_as(catches::Column::Id.count(), "count")
         .into_values::<_, QueryAs>()
         .one(&*conn)
         .await
     {
-        Ok(Some(total_catches)) => total_catches,
-        Ok(None) => return Err(Status::NotFound),
+        Ok(total_catches) => total_catches.unwrap_or_default(),
         Err(err) => {
-            error!("Error querying total catches: {err}");
+            error!("Error querying times caught: {err}");
             return Err(Status::InternalServerError);
         }
     };
 
-    #[derive(FromQueryResult)]
-    struct CatchQuery {
-        caught_at: DateTime<Utc>,
-        value: f32,
-    }
-
-    #[derive(Serialize)]
-    struct Catch {
-        caught_at: i64,
+    #[derive(FromQueryResult, Serialize)]
+    struct RecordCatch {
+        weight: Option<f32>,
         value: f32,
+        user_name: String,
     }
 
-    debug!("Querying last all catches");
-    let catches: Vec<_> = match Catches::find()
-        .filter(catches::Column::UserId.eq(user.id))
-        .column(catches::Column::CaughtAt)
+    debug!("Querying record catch");
+    let record_catch = match Catches::find()
+        .filter(catches::Column::FishId.eq(fish.id))
+        .order_by_desc(catches::Column::Value)
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .select_only()
+        .column(catches::Column::Weight)
         .column(catches::Column::Value)
-        .into_model::<CatchQuery>()
-        .all(&*conn)
-        .await
+        .column_as(users::Column::Name, "user_name")
+        .into_model::<RecordCatch>()
+        .one(&*conn)
+        .await
+    {
+        Ok(record_catch) => record_catch,
+        Err(err) => {
+            error!("Error querying record catch: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    Ok(Template::render(
+        "fish",
+        context! {
+            html_name: &fish.html_name,
+            base_value: fish.base_value,
+            min_weight: fish.min_weight,
+            max_weight: fish.max_weight,
+            chance: fish.count as f32 / population.max(1) as f32,
+            total_catches: &total_catches,
+            record_catch: &record_catch,
+        },
+    ))
+}
+
+#[get("/user/<username>")]
+async fn user(conn: Connection<Db>, username: String) -> Result<Template, Status> {
+    debug!("Quering user {username}");
+    let user = match Users::find()
+        .filter(users::Column::Name.eq(username.to_lowercase()))
+        .one(&*conn)
+        .await
+    {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying user {username}: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    #[derive(FromQueryResult, Serialize)]
+    struct TopCatch {
+        name: String,
+        weight: Option<f32>,
+        value: f32,
+    }
+
+    debug!("Querying top catch");
+    let top_catch = match Catches::find()
+        .filter(catches::Column::UserId.eq(user.id))
+        .order_by_desc(catches::Column::Value)
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .select_only()
+        .column(fishes::Column::Name)
+        .column(catches::Column::Value)
+        .column(catches::Column::Weight)
+        .into_model::<TopCatch>()
+        .one(&*conn)
+        .await
+    {
+        Ok(Some(top_catch)) => top_catch,
+        Ok(None) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying top catch for {username}: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+    enum QueryAs {
+        Score,
+    }
+
+    debug!("Querying total score");
+    let total_score: f32 = match Catches::find()
+        .filter(catches::Column::UserId.eq(user.id))
+        .select_only()
+        .column_as(catches::Column::Value.sum(), "score")
+        .into_values::<_, QueryAs>()
+        .one(&*conn)
+        .await
+    {
+        Ok(Some(score)) => score,
+        Ok(None) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying score for {username}: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    debug!("Querying total caught fishes");
+    let total_catches: i64 = match Catches::find()
+        .filter(catches::Column::UserId.eq(user.id))
+        .select_only()
+        .column_as(catches::Column::Id.count(), "score")
+        .into_values::<_, QueryAs>()
+        .one(&*conn)
+        .await
+    {
+        Ok(Some(total_catches)) => total_catches,
+        Ok(None) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying total catches: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    #[derive(FromQueryResult)]
+    struct CatchQuery {
+        caught_at: DateTime<Utc>,
+        value: f32,
+        season_id: i32,
+        season_name: String,
+    }
+
+    /// Point of the score timeline, annotated with the season of the catch
+    #[derive(Serialize)]
+    struct Catch {
+        caught_at: i64,
+        value: f32,
+        season_id: i32,
+        season_name: String,
+    }
+
+    debug!("Querying last all catches");
+    let catches: Vec<_> = match user_catches_query(user.id)
+        .into_model::<CatchQuery>()
+        .all(&*conn)
+        .await
     {
         Ok(catches) => {
             let mut total = 0.0;
@@ -302,6 +930,8 @@ async fn user(conn: Connection<Db>, username: String) -> Result<Template, Status
                     Catch {
                         value: total,
                         caught_at: catch.caught_at.timestamp_millis(),
+                        season_id: catch.season_id,
+                        season_name: catch.season_name,
                     }
                 })
                 .collect()
@@ -325,6 +955,51 @@ async fn user(conn: Connection<Db>, username: String) -> Result<Template, Status
     ))
 }
 
+/// The catches of the user with `user_id` in the order they were made.
+///
+/// Catches are ordered by `caught_at` and then by id, which grows with every
+/// insert, so catches made at the same time keep a stable order.
+fn user_catches_query(user_id: i32) -> Select<Catches> {
+    Catches::find()
+        .filter(catches::Column::UserId.eq(user_id))
+        .join(JoinType::InnerJoin, catches::Relation::Seasons.def())
+        .column(catches::Column::CaughtAt)
+        .column(catches::Column::Value)
+        .column_as(seasons::Column::Name, "season_name")
+        .order_by_asc(catches::Column::CaughtAt)
+        .order_by_asc(catches::Column::Id)
+}
+
+/// Users need at least this many catches to be considered the unluckiest
+/// fisher, so a single bad catch does not make anyone unlucky.
+const UNLUCKIEST_MIN_CATCHES: i64 = 50;
+
+#[derive(Debug, FromQueryResult, Serialize)]
+struct UnluckiestFisher {
+    name: String,
+    average_value: f64,
+    catches: i64,
+}
+
+/// Users ordered by their average catch value, lowest first. Only users with
+/// at least `min_catches` catches are included and gifts are left out.
+fn unluckiest_fisher_query(min_catches: i64) -> Select<Catches> {
+    let sum = catches::Column::Value.sum();
+    let count = catches::Column::Id.count();
+
+    Catches::find()
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .group_by(users::Column::Id)
+        .having(Expr::expr(count.clone()).gte(min_catches))
+        .order_by_asc(Expr::expr(sum.clone()).div(count.clone()))
+        .select_only()
+        .column(users::Column::Name)
+        .column_as(Expr::expr(sum).div(count.clone()), "average_value")
+        .column_as(count, "catches")
+}
+
 #[get("/stats")]
 async fn stats(conn: Connection<Db>) -> Result<Template, Status> {
     #[derive(FromQueryResult, Serialize)]
@@ -340,6 +1015,7 @@ async fn stats(conn: Connection<Db>) -> Result<Template, Status> {
         .order_by_desc(catches::Column::Value)
         .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
         .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
         .select_only()
         .column_as(fishes::Column::Name, "fish_name")
         .column_as(users::Column::Name, "user_name")
@@ -360,6 +1036,19 @@ async fn stats(conn: Connection<Db>) -> Result<Template, Status> {
         }
     };
 
+    debug!("Querying unluckiest fisher");
+    let unluckiest_fisher = match unluckiest_fisher_query(UNLUCKIEST_MIN_CATCHES)
+        .into_model::<UnluckiestFisher>()
+        .one(&*conn)
+        .await
+    {
+        Ok(unluckiest_fisher) => unluckiest_fisher,
+        Err(err) => {
+            error!("Error querying unluckiest fisher: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
     #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
     enum QueryAs {
         Score,
@@ -381,21 +1070,7 @@ async fn stats(conn: Connection<Db>) -> Result<Template, Status> {
         }
     };
 
-    debug!("Querying total caught fishes");
-    let total_catches: i64 = match Catches::find()
-        .select_only()
-        .column_as(catches::Column::Id.count(), "score")
-        .into_values::<_, QueryAs>()
-        .one(&*conn)
-        .await
-    {
-        Ok(Some(total_catches)) => total_catches,
-        Ok(None) => return Err(Status::NotFound),
-        Err(err) => {
-            error!("Error querying total catches: {err}");
-            return Err(Status::InternalServerError);
-        }
-    };
+    let total_catches = total_catches(&conn).await?;
 
     debug!("Querying total caught trash");
     let total_trash: i64 = match Catches::find()
@@ -415,57 +1090,7 @@ async fn stats(conn: Connection<Db>) -> Result<Template, Status> {
         }
     };
 
-    #[derive(FromQueryResult, Serialize)]
-    struct FishCatches {
-        html_name: String,
-        count: i32,
-        base_value: f32,
-        catches: i64,
-    }
-
-    debug!("Querying fishes and catches");
-    let fishes = Fishes::find()
-        .join(JoinType::InnerJoin, fishes::Relation::Catches.def())
-        .column_as(catches::Column::FishId.count(), "catches")
-        .group_by(fishes::Column::Id)
-        .into_model::<FishCatches>()
-        .all(&*conn)
-        .await
-        .map_err(|err| {
-            error!("Error querying fishes: {err}");
-            Status::InternalServerError
-        })?;
-
-    let population: i32 = fishes.iter().map(|fish| fish.count).sum();
-
-    #[derive(Serialize)]
-    struct FishEntry {
-        html_name: String,
-        count: i32,
-        base_value: f32,
-        catches: i64,
-        ideal_chance: f32,
-        real_chance: f32,
-        performance: f32,
-    }
-
-    let mut fish_entries: Vec<_> = fishes
-        .into_iter()
-        .map(|fish| FishEntry {
-            html_name: fish.html_name,
-            count: fish.count,
-            base_value: fish.base_value,
-            catches: fish.catches,
-            ideal_chance: fish.count as f32 / population as f32,
-            real_chance: fish.catches as f32 / total_catches as f32,
-            performance: fish.catches as f32
-                / total_catches as f32
-                / (fish.count as f32 / population as f32),
-        })
-        .collect();
-
-    fish_entries.sort_by_key(|row| (row.catches) as u64);
-    fish_entries.reverse();
+    let fish_entries = fish_performance(query_fish_catches(&conn).await?, total_catches);
 
     #[derive(Serialize)]
     struct Catch {
@@ -491,7 +1116,8 @@ async fn stats(conn: Connection<Db>) -> Result<Template, Status> {
         .into_iter()
         .map(|(user, mut catches)| {
             let mut total = 0.0;
-            catches.sort_by_key(|catch| catch.caught_at);
+            // same order as `user_catches_query`
+            catches.sort_by_key(|catch| (catch.caught_at, catch.id));
             let catches = catches
                 .into_iter()
                 .map(|catch| {
@@ -517,8 +1143,816 @@ async fn stats(conn: Connection<Db>) -> Result<Template, Status> {
             total_trash: &total_trash,
             total_score: &total_score,
             top_catch: &top_catch,
+            unluckiest_fisher: &unluckiest_fisher,
             fishes: &fish_entries,
             users: &users,
         },
     ))
 }
+
+/// Number of catches of all fishes. Gifts are not catches.
+async fn total_catches(db: &DatabaseConnection) -> Result<i64, Status> {
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveColumn)]
+    enum QueryAs {
+        Catches,
+    }
+
+    debug!("Querying total caught fishes");
+    match Catches::find()
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .select_only()
+        .column_as(catches::Column::Id.count(), "catches")
+        .into_values::<_, QueryAs>()
+        .one(db)
+        .await
+    {
+        Ok(Some(total_catches)) => Ok(total_catches),
+        Ok(None) => Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying total catches: {err}");
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct FishCatches {
+    name: String,
+    html_name: String,
+    count: i32,
+    base_value: f32,
+    catches: i64,
+}
+
+/// Fishes that were caught at least once with how often
+fn fish_catches_query() -> Select<Fishes> {
+    Fishes::find()
+        .join(JoinType::InnerJoin, fishes::Relation::Catches.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .column_as(catches::Column::FishId.count(), "catches")
+        .group_by(fishes::Column::Id)
+}
+
+async fn query_fish_catches(db: &DatabaseConnection) -> Result<Vec<FishCatches>, Status> {
+    debug!("Querying fishes and catches");
+    fish_catches_query()
+        .into_model::<FishCatches>()
+        .all(db)
+        .await
+        .map_err(|err| {
+            error!("Error querying fishes: {err}");
+            Status::InternalServerError
+        })
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct FishPerformance {
+    name: String,
+    html_name: String,
+    count: i32,
+    base_value: f32,
+    catches: i64,
+    /// Chance of catching the fish according to its count
+    ideal_chance: f32,
+    /// Share of the fish in all catches
+    real_chance: f32,
+    /// How much more often the fish was caught than it should have been
+    performance: f32,
+    tier: Tier,
+}
+
+/// Compare how often `fishes` were caught out of `total_catches` to how
+/// often they should have been caught, most caught first.
+fn fish_performance(fishes: Vec<FishCatches>, total_catches: i64) -> Vec<FishPerformance> {
+    // the gift fish can't be caught, so it has no chance to compare to
+    let fishes: Vec<_> = fishes
+        .into_iter()
+        .filter(|fish| fish.name != GIFT_FISH)
+        .collect();
+    let population: i32 = fishes.iter().map(|fish| fish.count).sum();
+
+    let mut performances: Vec<_> = fishes
+        .into_iter()
+        .map(|fish| FishPerformance {
+            name: fish.name,
+            html_name: fish.html_name,
+            count: fish.count,
+            base_value: fish.base_value,
+            catches: fish.catches,
+            ideal_chance: fish.count as f32 / population as f32,
+            real_chance: fish.catches as f32 / total_catches as f32,
+            performance: fish.catches as f32
+                / total_catches as f32
+                / (fish.count as f32 / population as f32),
+            tier: tier(fish.count.into(), population.into()),
+        })
+        .collect();
+
+    performances.sort_by_key(|row| (row.catches) as u64);
+    performances.reverse();
+
+    performances
+}
+
+#[derive(Debug, Serialize)]
+struct PerformanceReport {
+    total_catches: i64,
+    fishes: Vec<FishPerformance>,
+}
+
+/// How each fish performs compared to its ideal chance, for monitoring the
+/// balance over time
+#[get("/api/stats/performance")]
+async fn performance(conn: Connection<Db>) -> Result<Json<PerformanceReport>, Status> {
+    let total_catches = total_catches(&conn).await?;
+    let fishes = fish_performance(query_fish_catches(&conn).await?, total_catches);
+
+    Ok(Json(PerformanceReport {
+        total_catches,
+        fishes,
+    }))
+}
+
+const WEIGHT_HISTOGRAM_BINS: usize = 10;
+
+#[derive(Debug, PartialEq, Serialize)]
+struct WeightBin {
+    start: f32,
+    end: f32,
+    catches: usize,
+}
+
+/// Bucket `weights` into `bins` equally sized bins from `min` to `max`.
+/// Weights outside of the range are counted in the first or last bin.
+fn weight_histogram(weights: &[f32], min: f32, max: f32, bins: usize) -> Vec<WeightBin> {
+    if bins == 0 || max <= min {
+        return Vec::new();
+    }
+
+    let width = (max - min) / bins as f32;
+    let mut histogram: Vec<_> = (0..bins)
+        .map(|i| WeightBin {
+            start: min + width * i as f32,
+            end: min + width * (i + 1) as f32,
+            catches: 0,
+        })
+        .collect();
+
+    for weight in weights {
+        let index = (((weight - min) / width).floor().max(0.0) as usize).min(bins - 1);
+        histogram[index].catches += 1;
+    }
+
+    histogram
+}
+
+#[get("/stats/fish/<name>")]
+async fn fish_stats(conn: Connection<Db>, name: String) -> Result<Template, Status> {
+    debug!("Querying fish {name}");
+    let fish = match Fishes::find()
+        .filter(fishes::Column::Name.eq(name.as_str()))
+        .one(&*conn)
+        .await
+    {
+        Ok(Some(fish)) => fish,
+        Ok(None) => return Err(Status::NotFound),
+        Err(err) => {
+            error!("Error querying fish {name}: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    #[derive(FromQueryResult)]
+    struct CatchQuery {
+        weight: Option<f32>,
+        value: f32,
+    }
+
+    debug!("Querying catches of {name}");
+    let catches = match Catches::find()
+        .filter(catches::Column::FishId.eq(fish.id))
+        .select_only()
+        .column(catches::Column::Weight)
+        .column(catches::Column::Value)
+        .into_model::<CatchQuery>()
+        .all(&*conn)
+        .await
+    {
+        Ok(catches) => catches,
+        Err(err) => {
+            error!("Error querying catches of {name}: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    #[derive(FromQueryResult, Serialize)]
+    struct HeaviestCatch {
+        weight: f32,
+        value: f32,
+        user_name: String,
+    }
+
+    debug!("Querying heaviest catch of {name}");
+    let heaviest_catch = match Catches::find()
+        .filter(catches::Column::FishId.eq(fish.id))
+        .filter(catches::Column::Weight.is_not_null())
+        .order_by_desc(catches::Column::Weight)
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .select_only()
+        .column(catches::Column::Weight)
+        .column(catches::Column::Value)
+        .column_as(users::Column::Name, "user_name")
+        .into_model::<HeaviestCatch>()
+        .one(&*conn)
+        .await
+    {
+        Ok(heaviest_catch) => heaviest_catch,
+        Err(err) => {
+            error!("Error querying heaviest catch of {name}: {err}");
+            return Err(Status::InternalServerError);
+        }
+    };
+
+    let total_catches = catches.len();
+    let average_value = (total_catches > 0)
+        .then(|| catches.iter().map(|catch| catch.value).sum::<f32>() / total_catches as f32);
+
+    let weights: Vec<_> = catches.iter().filter_map(|catch| catch.weight).collect();
+    let histogram = if weights.is_empty() {
+        Vec::new()
+    } else {
+        weight_histogram(
+            &weights,
+            fish.min_weight,
+            fish.max_weight,
+            WEIGHT_HISTOGRAM_BINS,
+        )
+    };
+
+    Ok(Template::render(
+        "fish_stats",
+        context! {
+            html_name: &fish.html_name,
+            total_catches: &total_catches,
+            average_value: &average_value,
+            heaviest_catch: &heaviest_catch,
+            histogram: &histogram,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveModelTrait, ActiveValue};
+    use test_case::test_case;
+
+    use super::*;
+    use crate::fixtures::{
+        first_season, gift_fish, insert_bundle, insert_catch, insert_fish, insert_past_season,
+        insert_user,
+    };
+
+    fn user(name: &str, score: f32) -> UserWithScore {
+        UserWithScore {
+            name: name.to_string(),
+            is_bot: false,
+            score,
+        }
+    }
+
+    fn fish(id: i32, count: i32, is_trash: bool) -> fishes::Model {
+        fishes::Model {
+            id,
+            name: format!("fish {id}"),
+            html_name: format!("fish {id}"),
+            count,
+            base_value: 10.0,
+            max_weight: 0.0,
+            min_weight: 0.0,
+            is_trash,
+            weight_distribution: "uniform".to_string(),
+            weight_mean: None,
+            weight_stddev: None,
+        }
+    }
+
+    #[test]
+    fn fish_rows_use_population() {
+        let rows = fish_rows(vec![fish(1, 25, false), fish(2, 0, false)], 100);
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].chance, 0.25);
+        assert_eq!(rows[0].tier, Tier::Common);
+        assert_eq!(rows[1].tier, Tier::Common);
+    }
+
+    #[test_case(false, &["🐟", "👢", "🐳"] ; "with trash")]
+    #[test_case(true, &["🐟", "🐳"] ; "without trash")]
+    #[tokio::test]
+    async fn fishes_list(hide_trash: bool, expected: &[&str]) {
+        let db = database::memory_connection().await.unwrap();
+        insert_fish(&db, "🐟", 10, false).await;
+        insert_fish(&db, "👢", 10, true).await;
+        insert_fish(&db, "🐳", 2, false).await;
+        let filter = FishesFilter {
+            hide_trash,
+            ..Default::default()
+        };
+
+        let names: Vec<_> = fishes_query(&filter)
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fish| fish.name)
+            .collect();
+
+        assert_eq!(names, expected);
+    }
+
+    #[test_case(None, None, 1, 50 ; "defaults")]
+    #[test_case(Some(0), Some(0), 1, 1 ; "below minimum")]
+    #[test_case(Some(3), Some(500), 3, 200 ; "capped")]
+    fn fishes_pagination(
+        page: Option<u64>,
+        per_page: Option<u64>,
+        expected_page: u64,
+        expected_per_page: u64,
+    ) {
+        let filter = FishesFilter {
+            page,
+            per_page,
+            ..Default::default()
+        };
+
+        assert_eq!(filter.page(), expected_page);
+        assert_eq!(filter.per_page(), expected_per_page);
+    }
+
+    #[test_case(None, 0 ; "first page")]
+    #[test_case(Some(3), 100 ; "third page")]
+    #[test_case(Some(u64::MAX), i64::MAX as u64 ; "far past the end")]
+    fn fishes_offset(page: Option<u64>, expected: u64) {
+        let filter = FishesFilter {
+            page,
+            ..Default::default()
+        };
+
+        assert_eq!(filter.offset(), expected);
+    }
+
+    #[test_case(0, 1 ; "empty")]
+    #[test_case(50, 1 ; "one full page")]
+    #[test_case(51, 2 ; "partial page")]
+    #[test_case(u64::MAX, u64::MAX / 50 + 1 ; "overflowing")]
+    fn fishes_total_pages(fishes: u64, expected: u64) {
+        assert_eq!(FishesFilter::default().total_pages(fishes), expected);
+    }
+
+    #[tokio::test]
+    async fn unluckiest_fishers() {
+        let db = database::memory_connection().await.unwrap();
+        let season = first_season(&db).await;
+        let gift = gift_fish(&db).await;
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        for (name, catches) in [
+            (
+                "alice",
+                &[(&fish, 10.0), (&fish, 20.0), (&gift, -1000.0)][..],
+            ),
+            ("bob", &[(&fish, 5.0), (&fish, 5.0)]),
+            ("carol", &[(&fish, 1.0)]),
+        ] {
+            let user = insert_user(&db, name, false).await;
+            for (fish, value) in catches {
+                insert_catch(&db, season.id, &user, fish, *value, None).await;
+            }
+        }
+
+        let fishers: Vec<_> = unluckiest_fisher_query(2)
+            .into_model::<UnluckiestFisher>()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fisher| (fisher.name, fisher.average_value, fisher.catches))
+            .collect();
+
+        assert_eq!(
+            fishers,
+            [("bob".to_string(), 5.0, 2), ("alice".to_string(), 15.0, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn user_catches_are_ordered() {
+        let db = database::memory_connection().await.unwrap();
+        let season = first_season(&db).await;
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        let start = Utc::now();
+        let mut ids = Vec::new();
+        for (user, minutes) in [(&alice, 2), (&alice, 1), (&alice, 1), (&bob, 0)] {
+            let catch = insert_catch(&db, season.id, user, &fish, 10.0, None).await;
+            let catch = catches::ActiveModel {
+                caught_at: ActiveValue::set((start + chrono::Duration::minutes(minutes)).into()),
+                ..catch.into()
+            }
+            .update(&db)
+            .await
+            .unwrap();
+            ids.push(catch.id);
+        }
+
+        let catches: Vec<_> = user_catches_query(alice.id)
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|catch| catch.id)
+            .collect();
+
+        // catches made at the same time stay in the order they were made
+        assert_eq!(catches, [ids[1], ids[2], ids[0]]);
+    }
+
+    #[tokio::test]
+    async fn fishes_of_a_bundle() {
+        let db = database::memory_connection().await.unwrap();
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        let whale = insert_fish(&db, "🐳", 2, false).await;
+        let shark = insert_fish(&db, "🦈", 1, false).await;
+        let bundle_id = insert_bundle(&db, &[&fish, &whale]).await;
+        insert_bundle(&db, &[&fish, &shark]).await;
+
+        let names: Vec<_> = bundle_fishes_query(bundle_id)
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fish| fish.name)
+            .collect();
+
+        assert_eq!(names, ["🐟", "🐳"]);
+    }
+
+    #[test]
+    fn exported_fish() {
+        let fish = fishes::Model {
+            id: 7,
+            name: "🐡".to_string(),
+            html_name: "blowfish".to_string(),
+            count: 20,
+            base_value: 12.5,
+            max_weight: 2.0,
+            min_weight: 0.5,
+            is_trash: false,
+            weight_distribution: "uniform".to_string(),
+            weight_mean: None,
+            weight_stddev: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(FishDefinition::from(fish)).unwrap(),
+            serde_json::json!({
+                "name": "🐡",
+                "html_name": "blowfish",
+                "count": 20,
+                "base_value": 12.5,
+                "min_weight": 0.5,
+                "max_weight": 2.0,
+                "is_trash": false,
+                "weight_distribution": "uniform",
+                "weight_mean": null,
+                "weight_stddev": null,
+            })
+        );
+    }
+
+    /// Scores on the leaderboard of one season or of all seasons
+    async fn leaderboard_scores(in_season: bool, include_bots: bool) -> Vec<(String, f32)> {
+        let db = database::memory_connection().await.unwrap();
+        let season = first_season(&db).await;
+        let past = insert_past_season(&db).await;
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        for (name, is_bot, catches) in [
+            ("alice", false, &[(season.id, 100.0), (past.id, 50.0)][..]),
+            ("bob", true, &[(season.id, 500.0)]),
+            ("carol", false, &[(past.id, 70.0)]),
+        ] {
+            let user = insert_user(&db, name, is_bot).await;
+            for (season_id, value) in catches {
+                insert_catch(&db, *season_id, &user, &fish, *value, None).await;
+            }
+        }
+
+        leaderboard_query(in_season.then_some(season.id), include_bots)
+            .into_model::<UserWithScore>()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| (user.name, user.score))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn leaderboard_scoped_to_season() {
+        assert_eq!(
+            leaderboard_scores(true, false).await,
+            [("alice".to_string(), 100.0)]
+        );
+    }
+
+    #[tokio::test]
+    async fn leaderboard_of_all_seasons_with_bots() {
+        assert_eq!(
+            leaderboard_scores(false, true).await,
+            [
+                ("bob".to_string(), 500.0),
+                ("alice".to_string(), 150.0),
+                ("carol".to_string(), 70.0),
+            ]
+        );
+    }
+
+    /// Top catches of users, one of them a bot, ranked with or without bots
+    async fn top_catches(include_bots: bool) -> Vec<(String, String, f32)> {
+        let db = database::memory_connection().await.unwrap();
+        let season = first_season(&db).await;
+        let gift = gift_fish(&db).await;
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        let whale = insert_fish(&db, "🐳", 10, false).await;
+        let blowfish = insert_fish(&db, "🐡", 10, false).await;
+        for (name, is_bot, catches) in [
+            (
+                "alice",
+                false,
+                &[(&fish, 100.0), (&whale, 300.0), (&gift, 5000.0)][..],
+            ),
+            ("bob", true, &[(&fish, 400.0)]),
+            // ties go to the earlier catch
+            ("carol", false, &[(&blowfish, 50.0), (&fish, 50.0)]),
+        ] {
+            let user = insert_user(&db, name, is_bot).await;
+            for (fish, value) in catches {
+                insert_catch(&db, season.id, &user, fish, *value, None).await;
+            }
+        }
+
+        top_catch_leaderboard_query(include_bots)
+            .into_model::<UserTopCatch>()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|user| (user.name, user.fish_name, user.value))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn top_catch_leaderboard() {
+        assert_eq!(
+            top_catches(false).await,
+            [
+                ("alice".to_string(), "🐳".to_string(), 300.0),
+                ("carol".to_string(), "🐡".to_string(), 50.0),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn top_catch_leaderboard_with_bots() {
+        assert_eq!(
+            top_catches(true).await,
+            [
+                ("bob".to_string(), "🐟".to_string(), 400.0),
+                ("alice".to_string(), "🐳".to_string(), 300.0),
+                ("carol".to_string(), "🐡".to_string(), 50.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranks_standings() {
+        let standings = rank_standings(vec![
+            user("alice", 300.0),
+            user("bob", 150.0),
+            user("carol", 150.0),
+            user("dave", -20.0),
+        ]);
+
+        let ranks: Vec<_> = standings
+            .iter()
+            .map(|standing| (standing.user.as_str(), standing.rank))
+            .collect();
+
+        assert_eq!(
+            ranks,
+            vec![("alice", 1), ("bob", 2), ("carol", 2), ("dave", 4)]
+        );
+    }
+
+    #[test]
+    fn default_bind_addr() {
+        assert_eq!(
+            parse_bind_addr(DEFAULT_BIND_ADDR, DEFAULT_PORT).unwrap(),
+            SocketAddr::from(([0, 0, 0, 0], 3030))
+        );
+    }
+
+    #[test]
+    fn localhost_bind_addr() {
+        assert_eq!(
+            parse_bind_addr("127.0.0.1", "8080").unwrap(),
+            SocketAddr::from(([127, 0, 0, 1], 8080))
+        );
+        assert_eq!(
+            parse_bind_addr("::1", "8080").unwrap(),
+            "[::1]:8080".parse().unwrap()
+        );
+    }
+
+    #[test]
+    fn bind_addr_is_optional() {
+        assert_eq!(select_bind_addr(None, None, None).unwrap(), None);
+        assert_eq!(
+            select_bind_addr(None, None, Some("8080".to_string())).unwrap(),
+            Some(SocketAddr::from(([0, 0, 0, 0], 8080)))
+        );
+        assert_eq!(
+            select_bind_addr(
+                Some("127.0.0.1:8080".to_string()),
+                Some("0.0.0.0".to_string()),
+                None
+            )
+            .unwrap(),
+            Some(SocketAddr::from(([127, 0, 0, 1], 8080)))
+        );
+    }
+
+    #[test]
+    fn invalid_bind_addr() {
+        assert!(matches!(
+            parse_bind_addr("localhost", "3030"),
+            Err(Error::InvalidBindAddr { .. })
+        ));
+        assert!(matches!(
+            parse_bind_addr("0.0.0.0", "70000"),
+            Err(Error::InvalidPort { .. })
+        ));
+    }
+
+    #[test_case("127.0.0.1:8080", SocketAddr::from(([127, 0, 0, 1], 8080)) ; "ipv4")]
+    #[test_case(" [::1]:3031 ", "[::1]:3031".parse().unwrap() ; "ipv6")]
+    fn listen(listen: &str, expected: SocketAddr) {
+        assert_eq!(parse_listen(listen).unwrap(), expected);
+    }
+
+    #[test_case("0.0.0.0" ; "without port")]
+    #[test_case("localhost:3030" ; "hostname")]
+    fn invalid_listen(listen: &str) {
+        assert!(matches!(
+            parse_listen(listen),
+            Err(Error::InvalidListen { .. })
+        ));
+    }
+
+    #[test]
+    fn tls_paths_from_env_values() {
+        assert_eq!(parse_tls_paths(None, None).unwrap(), None);
+        assert_eq!(
+            parse_tls_paths(Some("cert.pem".to_string()), Some(" key.pem ".to_string())).unwrap(),
+            Some(TlsPaths {
+                cert: "cert.pem".into(),
+                key: "key.pem".into(),
+            })
+        );
+        assert!(matches!(
+            parse_tls_paths(Some("cert.pem".to_string()), Some(String::new())),
+            Err(Error::IncompleteTls { missing: "TLS_KEY" })
+        ));
+        assert!(matches!(
+            parse_tls_paths(None, Some("key.pem".to_string())),
+            Err(Error::IncompleteTls {
+                missing: "TLS_CERT"
+            })
+        ));
+    }
+
+    #[test]
+    fn weight_histogram_bins() {
+        let histogram = weight_histogram(&[0.5, 1.5, 1.9, 9.9, 10.0], 0.0, 10.0, 10);
+
+        let catches: Vec<_> = histogram.iter().map(|bin| bin.catches).collect();
+
+        assert_eq!(histogram.len(), 10);
+        assert_eq!(histogram[1].start, 1.0);
+        assert_eq!(histogram[1].end, 2.0);
+        assert_eq!(catches, vec![1, 2, 0, 0, 0, 0, 0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn weight_histogram_clamps_outliers() {
+        let histogram = weight_histogram(&[-1.0, 12.0], 0.0, 10.0, 10);
+
+        assert_eq!(histogram[0].catches, 1);
+        assert_eq!(histogram[9].catches, 1);
+    }
+
+    #[test]
+    fn weight_histogram_without_range() {
+        assert!(weight_histogram(&[1.0], 0.0, 0.0, 10).is_empty());
+    }
+
+    fn fish_catches(name: &str, count: i32, catches: i64) -> FishCatches {
+        FishCatches {
+            name: name.to_string(),
+            html_name: name.to_string(),
+            count,
+            base_value: 10.0,
+            catches,
+        }
+    }
+
+    #[test]
+    fn fish_performance_most_caught_first() {
+        let performances = fish_performance(
+            vec![fish_catches("🐡", 10, 10), fish_catches("🐟", 30, 40)],
+            50,
+        );
+
+        let names: Vec<_> = performances.iter().map(|fish| fish.name.as_str()).collect();
+        assert_eq!(names, vec!["🐟", "🐡"]);
+
+        let fish = &performances[0];
+        assert!((fish.ideal_chance - 0.75).abs() < 1e-6);
+        assert!((fish.real_chance - 0.8).abs() < 1e-6);
+        assert!((fish.performance - 0.8 / 0.75).abs() < 1e-6);
+
+        let fish = &performances[1];
+        assert!((fish.ideal_chance - 0.25).abs() < 1e-6);
+        assert!((fish.real_chance - 0.2).abs() < 1e-6);
+        assert!((fish.performance - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn fish_performance_without_gifts() {
+        let performances = fish_performance(
+            vec![fish_catches("🐟", 10, 4), fish_catches(GIFT_FISH, 0, 2)],
+            4,
+        );
+
+        assert_eq!(performances.len(), 1);
+        assert_eq!(performances[0].name, "🐟");
+        assert_eq!(performances[0].performance, 1.0);
+    }
+
+    #[tokio::test]
+    async fn fish_catches_skips_uncaught_and_gifts() {
+        let db = database::memory_connection().await.unwrap();
+        let season = first_season(&db).await;
+        let gift = gift_fish(&db).await;
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        let boot = insert_fish(&db, "👢", 5, true).await;
+        insert_fish(&db, "🐳", 1, false).await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        insert_catch(&db, season.id, &alice, &fish, 10.0, None).await;
+        insert_catch(&db, season.id, &bob, &fish, 10.0, None).await;
+        insert_catch(&db, season.id, &bob, &boot, 0.0, None).await;
+        insert_catch(&db, season.id, &alice, &gift, 50.0, None).await;
+
+        let mut catches: Vec<_> = fish_catches_query()
+            .into_model::<FishCatches>()
+            .all(&db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fish| (fish.name, fish.count, fish.catches))
+            .collect();
+        catches.sort();
+
+        assert_eq!(
+            catches,
+            [("🐟".to_string(), 10, 2), ("👢".to_string(), 5, 1)]
+        );
+    }
+
+    #[test]
+    fn performance_report_json() {
+        let report = PerformanceReport {
+            total_catches: 4,
+            fishes: fish_performance(vec![fish_catches("🐟", 1, 4)], 4),
+        };
+
+        let json = serde_json::to_value(report).unwrap();
+
+        assert_eq!(json["total_catches"], 4);
+        assert_eq!(json["fishes"][0]["name"], "🐟");
+        assert_eq!(json["fishes"][0]["performance"], 1.0);
+        assert_eq!(json["fishes"][0]["real_chance"], 1.0);
+    }
+}
@@ -0,0 +1,208 @@
+//! Push a summary of the stats page to clients connected to `/stats/stream`.
+//!
+//! The snapshot is computed once every [`REFRESH_INTERVAL`] and shared with
+//! all clients through a watch channel, so the load on the database does not
+//! grow with the number of open stats pages. Nothing is queried while nobody
+//! is connected.
+
+use std::{sync::Arc, time::Duration};
+
+use database::{
+    entities::{catches, fishes, prelude::*, users},
+    GIFT_FISH,
+};
+use log::{debug, error};
+use rocket::{
+    get,
+    response::stream::{Event, EventStream},
+    Shutdown, State,
+};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, JoinType, QueryFilter,
+    QueryOrder, QuerySelect, RelationTrait, Select,
+};
+use serde::Serialize;
+use tokio::{select, sync::watch};
+
+/// How often the snapshot is recomputed while clients are connected
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatsSnapshot {
+    total_catches: i64,
+    total_score: f32,
+    top_catch: Option<TopCatch>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, FromQueryResult)]
+struct TopCatch {
+    fish_name: String,
+    weight: Option<f32>,
+    value: f32,
+    user_name: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Totals {
+    score: Option<f32>,
+    catches: i64,
+}
+
+/// The latest snapshot, `None` until the first one was computed
+pub struct StatsFeed(Arc<watch::Sender<Option<StatsSnapshot>>>);
+
+impl StatsFeed {
+    pub fn new() -> Self {
+        Self(Arc::new(watch::channel(None).0))
+    }
+
+    pub fn sender(&self) -> Arc<watch::Sender<Option<StatsSnapshot>>> {
+        self.0.clone()
+    }
+}
+
+fn totals_query() -> Select<Catches> {
+    Catches::find()
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .select_only()
+        .column_as(catches::Column::Value.sum(), "score")
+        .column_as(catches::Column::Id.count(), "catches")
+}
+
+/// The most valuable catch of all time. Gifts are not catches.
+fn top_catch_query() -> Select<Catches> {
+    Catches::find()
+        .order_by_desc(catches::Column::Value)
+        .join(JoinType::InnerJoin, catches::Relation::Fishes.def())
+        .join(JoinType::InnerJoin, catches::Relation::Users.def())
+        .filter(fishes::Column::Name.ne(GIFT_FISH))
+        .select_only()
+        .column_as(fishes::Column::Name, "fish_name")
+        .column_as(users::Column::Name, "user_name")
+        .column(catches::Column::Value)
+        .column(catches::Column::Weight)
+}
+
+async fn snapshot(db: &DatabaseConnection) -> Result<StatsSnapshot, DbErr> {
+    let totals = totals_query().into_model::<Totals>().one(db).await?;
+    let top_catch = top_catch_query().into_model::<TopCatch>().one(db).await?;
+
+    Ok(StatsSnapshot {
+        total_catches: totals.as_ref().map_or(0, |totals| totals.catches),
+        total_score: totals.and_then(|totals| totals.score).unwrap_or_default(),
+        top_catch,
+    })
+}
+
+/// Recompute the snapshot every [`REFRESH_INTERVAL`] while someone listens.
+pub async fn refresh(db: DatabaseConnection, sender: Arc<watch::Sender<Option<StatsSnapshot>>>) {
+    let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if sender.receiver_count() == 0 {
+            continue;
+        }
+
+        match snapshot(&db).await {
+            Ok(snapshot) => {
+                debug!("Refreshed stats snapshot");
+                sender.send_replace(Some(snapshot));
+            }
+            Err(err) => error!("Error querying stats snapshot: {err}"),
+        }
+    }
+}
+
+#[get("/stats/stream")]
+pub fn stream(feed: &State<StatsFeed>, mut shutdown: Shutdown) -> EventStream![] {
+    let mut receiver = feed.0.subscribe();
+
+    EventStream! {
+        loop {
+            let snapshot = receiver.borrow_and_update().clone();
+            if let Some(snapshot) = snapshot {
+                yield Event::json(&snapshot);
+            }
+
+            select! {
+                changed = receiver.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{first_season, gift_fish, insert_catch, insert_fish, insert_user};
+
+    #[tokio::test]
+    async fn snapshot_without_catches() {
+        let db = database::memory_connection().await.unwrap();
+
+        assert_eq!(
+            snapshot(&db).await.unwrap(),
+            StatsSnapshot {
+                total_catches: 0,
+                total_score: 0.0,
+                top_catch: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn snapshot_skips_gifts() {
+        let db = database::memory_connection().await.unwrap();
+        let season = first_season(&db).await;
+        let gift = gift_fish(&db).await;
+        let fish = insert_fish(&db, "🐟", 10, false).await;
+        let whale = insert_fish(&db, "🐳", 1, false).await;
+        let alice = insert_user(&db, "alice", false).await;
+        let bob = insert_user(&db, "bob", false).await;
+        insert_catch(&db, season.id, &alice, &fish, 20.0, Some(1.5)).await;
+        insert_catch(&db, season.id, &bob, &whale, 100.5, Some(90000.0)).await;
+        insert_catch(&db, season.id, &alice, &fish, -5.0, None).await;
+        insert_catch(&db, season.id, &alice, &gift, 1000.0, None).await;
+
+        assert_eq!(
+            snapshot(&db).await.unwrap(),
+            StatsSnapshot {
+                total_catches: 3,
+                total_score: 115.5,
+                top_catch: Some(TopCatch {
+                    fish_name: "🐳".to_string(),
+                    weight: Some(90000.0),
+                    value: 100.5,
+                    user_name: "bob".to_string(),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn snapshot_json() {
+        let snapshot = StatsSnapshot {
+            total_catches: 3,
+            total_score: 120.5,
+            top_catch: Some(TopCatch {
+                fish_name: "🐳".to_string(),
+                weight: Some(90000.0),
+                value: 100.0,
+                user_name: "alice".to_string(),
+            }),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&snapshot).unwrap(),
+            r#"{"total_catches":3,"total_score":120.5,"top_catch":{"fish_name":"🐳","weight":90000.0,"value":100.0,"user_name":"alice"}}"#
+        );
+    }
+}
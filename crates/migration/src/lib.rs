@@ -9,6 +9,13 @@ mod m20220828_143304_create_seasons_data_table;
 mod m20220829_150037_create_accounts_table;
 mod m20230426_115812_integrate_seasons;
 mod m20230525_135103_rename_to_fish_set;
+mod m20230601_120000_seed_cooldown_messages;
+mod m20230612_120000_add_gift_fish;
+mod m20230615_120000_add_catches_indexes;
+mod m20230620_120000_add_weight_distribution;
+mod m20230622_120000_add_catch_messages;
+mod m20230626_120000_create_inventory_table;
+mod m20230628_120000_close_legacy_season;
 
 pub struct Migrator;
 
@@ -25,6 +32,28 @@ impl MigratorTrait for Migrator {
             Box::new(m20220829_150037_create_accounts_table::Migration),
             Box::new(m20230426_115812_integrate_seasons::Migration),
             Box::new(m20230525_135103_rename_to_fish_set::Migration),
+            Box::new(m20230601_120000_seed_cooldown_messages::Migration),
+            Box::new(m20230612_120000_add_gift_fish::Migration),
+            Box::new(m20230615_120000_add_catches_indexes::Migration),
+            Box::new(m20230620_120000_add_weight_distribution::Migration),
+            Box::new(m20230622_120000_add_catch_messages::Migration),
+            Box::new(m20230626_120000_create_inventory_table::Migration),
+            Box::new(m20230628_120000_close_legacy_season::Migration),
         ]
     }
 }
+
+/// An in-memory sqlite database with every migration applied
+#[cfg(test)]
+async fn test_db() -> sea_orm_migration::sea_orm::DatabaseConnection {
+    use sea_orm_migration::sea_orm::{ConnectOptions, Database};
+
+    let mut opt = ConnectOptions::new("sqlite::memory:".to_owned());
+    // every connection would get its own in-memory database
+    opt.max_connections(1);
+    let db = Database::connect(opt).await.unwrap();
+
+    Migrator::up(&db, None).await.unwrap();
+
+    db
+}
@@ -1,4 +1,4 @@
-use sea_orm_migration::{prelude::*, sea_query::extension::postgres::Type};
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend, sea_query::extension::postgres::Type};
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
@@ -6,14 +6,37 @@ pub struct Migration;
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        manager
-            .create_type(
-                Type::create()
-                    .as_enum(MessageType::Type)
-                    .values(vec![MessageType::Cooldown])
-                    .to_owned(),
-            )
-            .await?;
+        // The message type is stored differently depending on the backend:
+        // - Postgres uses the native `message_type` enum type
+        // - MySQL uses an inline `ENUM` column
+        // - SQLite uses a text column with a check constraint
+        // In every case the column holds the string values of
+        // `sea_orm_active_enums::MessageType`.
+        let mut message_type = ColumnDef::new(Messages::Type);
+        match manager.get_database_backend() {
+            DbBackend::Postgres => {
+                manager
+                    .create_type(
+                        Type::create()
+                            .as_enum(MessageType::Type)
+                            .values(vec![MessageType::Cooldown])
+                            .to_owned(),
+                    )
+                    .await?;
+
+                message_type.custom(MessageType::Type);
+            }
+            DbBackend::MySql => {
+                message_type.enumeration(MessageType::Type, [MessageType::Cooldown]);
+            }
+            DbBackend::Sqlite => {
+                message_type.string().extra(format!(
+                    "CHECK ({} IN ('{}'))",
+                    Messages::Type.to_string(),
+                    MessageType::Cooldown.to_string()
+                ));
+            }
+        }
 
         manager
             .create_table(
@@ -28,11 +51,7 @@ impl MigrationTrait for Migration {
                             .primary_key(),
                     )
                     .col(ColumnDef::new(Messages::Text).string().not_null())
-                    .col(
-                        ColumnDef::new(Messages::Type)
-                            .custom(MessageType::Type)
-                            .not_null(),
-                    )
+                    .col(message_type.not_null())
                     .to_owned(),
             )
             .await
@@ -43,9 +62,13 @@ impl MigrationTrait for Migration {
             .drop_table(Table::drop().table(Messages::Table).to_owned())
             .await?;
 
-        manager
-            .drop_type(Type::drop().name(MessageType::Type).to_owned())
-            .await
+        if manager.get_database_backend() == DbBackend::Postgres {
+            manager
+                .drop_type(Type::drop().name(MessageType::Type).to_owned())
+                .await?;
+        }
+
+        Ok(())
     }
 }
 
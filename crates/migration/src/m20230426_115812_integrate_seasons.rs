@@ -1,29 +1,61 @@
 use chrono::{TimeZone, Utc};
-use sea_orm_migration::prelude::{Table, *};
+use sea_orm_migration::{
+    prelude::{Table, *},
+    sea_orm::DbBackend,
+};
 
 use crate::m20220828_125955_create_fishes_table::Fishes;
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
-const LEGACY_SEASON_ID: i32 = 0;
+pub const LEGACY_SEASON_ID: i32 = 0;
 
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(Seasons::Table)
-                    .add_column(
-                        ColumnDef::new(Seasons::Start)
-                            .not_null()
-                            .timestamp_with_time_zone(),
-                    )
-                    .add_column(ColumnDef::new(Seasons::End).timestamp_with_time_zone())
-                    .to_owned(),
-            )
-            .await?;
+        let is_sqlite = manager.get_database_backend() == DbBackend::Sqlite;
+        let legacy_start = Utc.with_ymd_and_hms(2022, 8, 31, 12, 0, 0).unwrap();
+
+        if is_sqlite {
+            // sqlite can only add one column per statement and requires a
+            // default for new columns that are not null
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Seasons::Table)
+                        .add_column(
+                            ColumnDef::new(Seasons::Start)
+                                .not_null()
+                                .timestamp_with_time_zone()
+                                .default(legacy_start),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Seasons::Table)
+                        .add_column(ColumnDef::new(Seasons::End).timestamp_with_time_zone())
+                        .to_owned(),
+                )
+                .await?;
+        } else {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Seasons::Table)
+                        .add_column(
+                            ColumnDef::new(Seasons::Start)
+                                .not_null()
+                                .timestamp_with_time_zone(),
+                        )
+                        .add_column(ColumnDef::new(Seasons::End).timestamp_with_time_zone())
+                        .to_owned(),
+                )
+                .await?;
+        }
 
         manager
             .exec_stmt(
@@ -33,33 +65,35 @@ impl MigrationTrait for Migration {
                     .values_panic(vec![
                         LEGACY_SEASON_ID.into(),
                         "Legacy".into(),
-                        Utc.with_ymd_and_hms(2022, 8, 31, 12, 0, 0).unwrap().into(),
+                        legacy_start.into(),
                     ])
                     .to_owned(),
             )
             .await?;
 
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(Catches::Table)
-                    .add_column(
-                        ColumnDef::new(Catches::SeasonId)
-                            .integer()
-                            .not_null()
-                            .default(LEGACY_SEASON_ID),
-                    )
-                    .add_foreign_key(
-                        TableForeignKey::new()
-                            .name("FK_catches_season_id")
-                            .from_tbl(Catches::Table)
-                            .from_col(Catches::SeasonId)
-                            .to_tbl(Seasons::Table)
-                            .to_col(Seasons::Id),
-                    )
-                    .to_owned(),
+        let mut add_season_id = Table::alter()
+            .table(Catches::Table)
+            .add_column(
+                ColumnDef::new(Catches::SeasonId)
+                    .integer()
+                    .not_null()
+                    .default(LEGACY_SEASON_ID),
             )
-            .await?;
+            .to_owned();
+
+        // sqlite can not add foreign keys to existing tables
+        if !is_sqlite {
+            add_season_id.add_foreign_key(
+                TableForeignKey::new()
+                    .name("FK_catches_season_id")
+                    .from_tbl(Catches::Table)
+                    .from_col(Catches::SeasonId)
+                    .to_tbl(Seasons::Table)
+                    .to_col(Seasons::Id),
+            );
+        }
+
+        manager.alter_table(add_season_id).await?;
 
         manager
             .create_table(
@@ -118,15 +152,29 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(Seasons::Table)
-                    .drop_column(Seasons::Start)
-                    .drop_column(Seasons::End)
-                    .to_owned(),
-            )
-            .await?;
+        if manager.get_database_backend() == DbBackend::Sqlite {
+            // sqlite can only drop one column per statement
+            for column in [Seasons::Start, Seasons::End] {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Seasons::Table)
+                            .drop_column(column)
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+        } else {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Seasons::Table)
+                        .drop_column(Seasons::Start)
+                        .drop_column(Seasons::End)
+                        .to_owned(),
+                )
+                .await?;
+        }
 
         manager
             .drop_table(Table::drop().table(FishesSeasons::Table).to_owned())
@@ -1,4 +1,6 @@
-use sea_orm_migration::prelude::*;
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend};
+
+use crate::m20220828_125955_create_fishes_table::Fishes;
 
 #[derive(DeriveMigrationName)]
 pub struct Migration;
@@ -6,6 +8,8 @@ pub struct Migration;
 #[async_trait::async_trait]
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let is_sqlite = manager.get_database_backend() == DbBackend::Sqlite;
+
         // create bundle table
         manager
             .create_table(
@@ -33,113 +37,211 @@ impl MigrationTrait for Migration {
             )
             .await?;
 
-        // rename fishesseason to fishset
-        manager
-            .rename_table(
-                Table::rename()
-                    .table(FishesSeasons::Table, FishBundle::Table)
-                    .take(),
-            )
-            .await?;
+        if is_sqlite {
+            // sqlite can not change foreign keys, so recreate the table instead
+            manager
+                .create_table(
+                    Table::create()
+                        .table(FishBundle::Table)
+                        .col(ColumnDef::new(FishBundle::FishId).integer().not_null())
+                        .col(ColumnDef::new(FishBundle::BundleId).integer().not_null())
+                        .primary_key(
+                            Index::create()
+                                .col(FishBundle::FishId)
+                                .col(FishBundle::BundleId),
+                        )
+                        .foreign_key(
+                            ForeignKey::create()
+                                .name("FK_fishbundle_fish_id")
+                                .from(FishBundle::Table, FishBundle::FishId)
+                                .to(Fishes::Table, Fishes::Id),
+                        )
+                        .foreign_key(
+                            ForeignKey::create()
+                                .name("FK_fishbundle_bundle_id")
+                                .from(FishBundle::Table, FishBundle::BundleId)
+                                .to(Bundle::Table, Bundle::Id),
+                        )
+                        .take(),
+                )
+                .await?;
+            manager
+                .exec_stmt(
+                    Query::insert()
+                        .into_table(FishBundle::Table)
+                        .columns([FishBundle::FishId, FishBundle::BundleId])
+                        .select_from(
+                            Query::select()
+                                .columns([FishesSeasons::FishId, FishesSeasons::SeasonId])
+                                .from(FishesSeasons::Table)
+                                .take(),
+                        )
+                        .unwrap()
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .drop_table(Table::drop().table(FishesSeasons::Table).take())
+                .await?;
+        } else {
+            // rename fishesseason to fishset
+            manager
+                .rename_table(
+                    Table::rename()
+                        .table(FishesSeasons::Table, FishBundle::Table)
+                        .take(),
+                )
+                .await?;
 
-        // rename fishset.season_id to set_id
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(FishBundle::Table)
-                    .rename_column(FishBundle::SeasonId, FishBundle::BundleId)
-                    .take(),
-            )
-            .await?;
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(FishBundle::Table)
-                    .drop_foreign_key(Alias::new("FK_fishes_seasons_season_id"))
-                    .add_foreign_key(
-                        TableForeignKey::new()
-                            .name("FK_fishbundle_bundle_id")
-                            .from_tbl(FishBundle::Table)
-                            .from_col(FishBundle::BundleId)
-                            .to_tbl(Bundle::Table)
-                            .to_col(Bundle::Id),
-                    )
-                    .take(),
-            )
-            .await?;
+            // rename fishset.season_id to set_id
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(FishBundle::Table)
+                        .rename_column(FishBundle::SeasonId, FishBundle::BundleId)
+                        .take(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(FishBundle::Table)
+                        .drop_foreign_key(Alias::new("FK_fishes_seasons_season_id"))
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name("FK_fishbundle_bundle_id")
+                                .from_tbl(FishBundle::Table)
+                                .from_col(FishBundle::BundleId)
+                                .to_tbl(Bundle::Table)
+                                .to_col(Bundle::Id),
+                        )
+                        .take(),
+                )
+                .await?;
+        }
 
         // add bundle_id column to season
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(Seasons::Table)
-                    .add_column(
-                        ColumnDef::new(Seasons::BundleId)
-                            .integer()
-                            .not_null()
-                            .default(0),
-                    )
-                    .add_foreign_key(
-                        TableForeignKey::new()
-                            .name("FK_seasons_bundle_id")
-                            .from_tbl(Seasons::Table)
-                            .from_col(Seasons::BundleId)
-                            .to_tbl(Bundle::Table)
-                            .to_col(Bundle::Id),
-                    )
-                    .take(),
+        let mut add_bundle_id = Table::alter()
+            .table(Seasons::Table)
+            .add_column(
+                ColumnDef::new(Seasons::BundleId)
+                    .integer()
+                    .not_null()
+                    .default(0),
             )
-            .await?;
+            .take();
+
+        // sqlite can not add foreign keys to existing tables
+        if !is_sqlite {
+            add_bundle_id.add_foreign_key(
+                TableForeignKey::new()
+                    .name("FK_seasons_bundle_id")
+                    .from_tbl(Seasons::Table)
+                    .from_col(Seasons::BundleId)
+                    .to_tbl(Bundle::Table)
+                    .to_col(Bundle::Id),
+            );
+        }
+
+        manager.alter_table(add_bundle_id).await?;
 
         Ok(())
     }
 
     async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let is_sqlite = manager.get_database_backend() == DbBackend::Sqlite;
+
         // revert add bundle_id column to season
+        let mut drop_bundle_id = Table::alter().table(Seasons::Table).take();
+        if !is_sqlite {
+            drop_bundle_id.drop_foreign_key(Alias::new("FK_seasons_bundle_id"));
+        }
         manager
-            .alter_table(
-                Table::alter()
-                    .table(Seasons::Table)
-                    .drop_foreign_key(Alias::new("FK_seasons_bundle_id"))
-                    .drop_column(Seasons::BundleId)
-                    .take(),
-            )
+            .alter_table(drop_bundle_id.drop_column(Seasons::BundleId).take())
             .await?;
 
-        // revert rename fishset.season_id to set_id and make it a foreign key
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(FishBundle::Table)
-                    .rename_column(FishBundle::BundleId, FishBundle::SeasonId)
-                    .take(),
-            )
-            .await?;
-        manager
-            .alter_table(
-                Table::alter()
-                    .table(FishBundle::Table)
-                    .drop_foreign_key(Alias::new("FK_fishbundle_bundle_id"))
-                    .add_foreign_key(
-                        TableForeignKey::new()
-                            .name("FK_fishes_seasons_season_id")
-                            .from_tbl(FishBundle::Table)
-                            .from_col(FishBundle::SeasonId)
-                            .to_tbl(Seasons::Table)
-                            .to_col(Seasons::Id),
-                    )
-                    .take(),
-            )
-            .await?;
+        if is_sqlite {
+            // revert recreating the table
+            manager
+                .create_table(
+                    Table::create()
+                        .table(FishesSeasons::Table)
+                        .col(ColumnDef::new(FishesSeasons::FishId).integer().not_null())
+                        .col(ColumnDef::new(FishesSeasons::SeasonId).integer().not_null())
+                        .primary_key(
+                            Index::create()
+                                .col(FishesSeasons::FishId)
+                                .col(FishesSeasons::SeasonId),
+                        )
+                        .foreign_key(
+                            ForeignKey::create()
+                                .name("FK_fishes_seasons_fish_id")
+                                .from(FishesSeasons::Table, FishesSeasons::FishId)
+                                .to(Fishes::Table, Fishes::Id),
+                        )
+                        .foreign_key(
+                            ForeignKey::create()
+                                .name("FK_fishes_seasons_season_id")
+                                .from(FishesSeasons::Table, FishesSeasons::SeasonId)
+                                .to(Seasons::Table, Seasons::Id),
+                        )
+                        .take(),
+                )
+                .await?;
+            manager
+                .exec_stmt(
+                    Query::insert()
+                        .into_table(FishesSeasons::Table)
+                        .columns([FishesSeasons::FishId, FishesSeasons::SeasonId])
+                        .select_from(
+                            Query::select()
+                                .columns([FishBundle::FishId, FishBundle::BundleId])
+                                .from(FishBundle::Table)
+                                .take(),
+                        )
+                        .unwrap()
+                        .to_owned(),
+                )
+                .await?;
+            manager
+                .drop_table(Table::drop().table(FishBundle::Table).take())
+                .await?;
+        } else {
+            // revert rename fishset.season_id to set_id and make it a foreign key
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(FishBundle::Table)
+                        .rename_column(FishBundle::BundleId, FishBundle::SeasonId)
+                        .take(),
+                )
+                .await?;
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(FishBundle::Table)
+                        .drop_foreign_key(Alias::new("FK_fishbundle_bundle_id"))
+                        .add_foreign_key(
+                            TableForeignKey::new()
+                                .name("FK_fishes_seasons_season_id")
+                                .from_tbl(FishBundle::Table)
+                                .from_col(FishBundle::SeasonId)
+                                .to_tbl(Seasons::Table)
+                                .to_col(Seasons::Id),
+                        )
+                        .take(),
+                )
+                .await?;
 
-        // revert rename fishesseason to fishbundle
-        manager
-            .rename_table(
-                Table::rename()
-                    .table(FishBundle::Table, FishesSeasons::Table)
-                    .to_owned(),
-            )
-            .await?;
+            // revert rename fishesseason to fishbundle
+            manager
+                .rename_table(
+                    Table::rename()
+                        .table(FishBundle::Table, FishesSeasons::Table)
+                        .to_owned(),
+                )
+                .await?;
+        }
 
         // revert create empty bundle
         manager
@@ -164,11 +266,14 @@ impl MigrationTrait for Migration {
 #[derive(Iden)]
 enum FishesSeasons {
     Table,
+    FishId,
+    SeasonId,
 }
 
 #[derive(Iden)]
 enum FishBundle {
     Table,
+    FishId,
     SeasonId,
     BundleId,
 }
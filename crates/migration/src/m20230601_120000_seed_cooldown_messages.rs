@@ -0,0 +1,82 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+const COOLDOWN_MESSAGES: [&str; 7] = [
+    "you can't fish yet. try again in {cooldown}.",
+    "your fishing rod is still broken. come back in {cooldown}.",
+    "the fish are still scared of you. wait {cooldown}.",
+    "you are still recovering from your last trip. try again in {cooldown}.",
+    "the lake is frozen for another {cooldown}.",
+    "you ran out of bait. the shop restocks in {cooldown}.",
+    "your boat is being repaired. it will be ready in {cooldown}.",
+];
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = manager.get_database_backend();
+
+        // keep existing cooldown messages
+        let existing = db
+            .query_one(
+                builder.build(
+                    &Query::select()
+                        .column(Messages::Id)
+                        .from(Messages::Table)
+                        .and_where(Expr::col(Messages::Type).eq(cooldown()))
+                        .limit(1)
+                        .to_owned(),
+                ),
+            )
+            .await?;
+
+        if existing.is_some() {
+            return Ok(());
+        }
+
+        let mut insert = Query::insert()
+            .into_table(Messages::Table)
+            .columns([Messages::Text, Messages::Type])
+            .to_owned();
+
+        for text in COOLDOWN_MESSAGES {
+            insert.values_panic([text.into(), cooldown()]);
+        }
+
+        manager.exec_stmt(insert).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Messages::Table)
+                    .and_where(Expr::col(Messages::Type).eq(cooldown()))
+                    .and_where(Expr::col(Messages::Text).is_in(COOLDOWN_MESSAGES))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+fn cooldown() -> SimpleExpr {
+    Expr::val("cooldown").as_enum(MessageType::Type)
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Messages {
+    Table,
+    Id,
+    Text,
+    Type,
+}
+
+#[derive(Iden)]
+enum MessageType {
+    #[iden = "message_type"]
+    Type,
+}
@@ -0,0 +1,104 @@
+use sea_orm_migration::{prelude::*, sea_orm::ConnectionTrait};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Name of the fish gifted score is recorded with. It has a count of zero, so
+/// it can never be caught.
+const GIFT_FISH: &str = "🎁";
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+        let builder = manager.get_database_backend();
+
+        let existing = db
+            .query_one(
+                builder.build(
+                    &Query::select()
+                        .column(Fishes::Id)
+                        .from(Fishes::Table)
+                        .and_where(Expr::col(Fishes::Name).eq(GIFT_FISH))
+                        .to_owned(),
+                ),
+            )
+            .await?;
+
+        if existing.is_some() {
+            return Ok(());
+        }
+
+        manager
+            .exec_stmt(
+                Query::insert()
+                    .into_table(Fishes::Table)
+                    .columns([
+                        Fishes::Name,
+                        Fishes::HtmlName,
+                        Fishes::Count,
+                        Fishes::BaseValue,
+                        Fishes::MaxWeight,
+                        Fishes::MinWeight,
+                        Fishes::IsTrash,
+                    ])
+                    .values_panic([
+                        GIFT_FISH.into(),
+                        GIFT_FISH.into(),
+                        0.into(),
+                        0.0_f32.into(),
+                        0.0_f32.into(),
+                        0.0_f32.into(),
+                        false.into(),
+                    ])
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let gift_fish = Query::select()
+            .column(Fishes::Id)
+            .from(Fishes::Table)
+            .and_where(Expr::col(Fishes::Name).eq(GIFT_FISH))
+            .to_owned();
+
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Catches::Table)
+                    .and_where(Expr::col(Catches::FishId).in_subquery(gift_fish))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Fishes::Table)
+                    .and_where(Expr::col(Fishes::Name).eq(GIFT_FISH))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Fishes {
+    Table,
+    Id,
+    Name,
+    HtmlName,
+    Count,
+    BaseValue,
+    MaxWeight,
+    MinWeight,
+    IsTrash,
+}
+
+#[derive(Iden)]
+enum Catches {
+    Table,
+    FishId,
+}
@@ -0,0 +1,102 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // catches of a user ordered by time, used by the user page
+        manager
+            .create_index(
+                Index::create()
+                    .name("catches_user_id_caught_at_idx")
+                    .table(Catches::Table)
+                    .col(Catches::UserId)
+                    .col(Catches::CaughtAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        // catches of a fish in a season
+        manager
+            .create_index(
+                Index::create()
+                    .name("catches_season_id_fish_id_idx")
+                    .table(Catches::Table)
+                    .col(Catches::SeasonId)
+                    .col(Catches::FishId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("catches_season_id_fish_id_idx")
+                    .table(Catches::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("catches_user_id_caught_at_idx")
+                    .table(Catches::Table)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Catches {
+    Table,
+    UserId,
+    FishId,
+    SeasonId,
+    CaughtAt,
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+
+    use super::*;
+    use crate::test_db;
+
+    async fn index_names(db: &DatabaseConnection) -> Vec<String> {
+        db.query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'catches'"
+                .to_owned(),
+        ))
+        .await
+        .unwrap()
+        .iter()
+        .map(|row| row.try_get("", "name").unwrap())
+        .collect()
+    }
+
+    #[tokio::test]
+    async fn applies_and_rolls_back() {
+        let db = test_db().await;
+
+        let indexes = index_names(&db).await;
+        assert!(indexes.contains(&"catches_user_id_caught_at_idx".to_string()));
+        assert!(indexes.contains(&"catches_season_id_fish_id_idx".to_string()));
+
+        let manager = SchemaManager::new(&db);
+        Migration.down(&manager).await.unwrap();
+
+        let indexes = index_names(&db).await;
+        assert!(!indexes.contains(&"catches_user_id_caught_at_idx".to_string()));
+        assert!(!indexes.contains(&"catches_season_id_fish_id_idx".to_string()));
+
+        Migration.up(&manager).await.unwrap();
+    }
+}
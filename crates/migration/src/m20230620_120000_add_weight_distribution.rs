@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // one column per statement, as sqlite can only add one at a time
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Fishes::Table)
+                    .add_column(
+                        ColumnDef::new(Fishes::WeightDistribution)
+                            .string()
+                            .not_null()
+                            .default("uniform"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // only used by the normal distribution
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Fishes::Table)
+                    .add_column(ColumnDef::new(Fishes::WeightMean).float().null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Fishes::Table)
+                    .add_column(ColumnDef::new(Fishes::WeightStddev).float().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in [
+            Fishes::WeightStddev,
+            Fishes::WeightMean,
+            Fishes::WeightDistribution,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Fishes::Table)
+                        .drop_column(column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Fishes {
+    Table,
+    WeightDistribution,
+    WeightMean,
+    WeightStddev,
+}
@@ -0,0 +1,236 @@
+use sea_orm_migration::{prelude::*, sea_orm::DbBackend, sea_query::extension::postgres::Type};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        match manager.get_database_backend() {
+            DbBackend::Postgres => {
+                manager
+                    .alter_type(
+                        Type::alter()
+                            .name(MessageType::Type)
+                            .add_value(MessageType::Catch)
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+            DbBackend::MySql => {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Messages::Table)
+                            .modify_column(
+                                ColumnDef::new(Messages::Type)
+                                    .enumeration(
+                                        MessageType::Type,
+                                        [MessageType::Cooldown, MessageType::Catch],
+                                    )
+                                    .not_null(),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+            DbBackend::Sqlite => {
+                // SQLite can't alter check constraints, so the table is
+                // rebuilt including the new column
+                return rebuild_sqlite_table(
+                    manager,
+                    &[MessageType::Cooldown, MessageType::Catch],
+                    true,
+                )
+                .await;
+            }
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .add_column(ColumnDef::new(Messages::Channel).string().null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(Messages::Table)
+                    .and_where(
+                        Expr::col(Messages::Type).eq(Expr::val("catch").as_enum(MessageType::Type)),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        match manager.get_database_backend() {
+            // Postgres can't drop values from an enum type. The unused value
+            // is left in place.
+            DbBackend::Postgres => {}
+            DbBackend::MySql => {
+                manager
+                    .alter_table(
+                        Table::alter()
+                            .table(Messages::Table)
+                            .modify_column(
+                                ColumnDef::new(Messages::Type)
+                                    .enumeration(MessageType::Type, [MessageType::Cooldown])
+                                    .not_null(),
+                            )
+                            .to_owned(),
+                    )
+                    .await?;
+            }
+            DbBackend::Sqlite => {
+                return rebuild_sqlite_table(manager, &[MessageType::Cooldown], false).await;
+            }
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .drop_column(Messages::Channel)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Recreate the messages table allowing only `types`, copying all messages.
+async fn rebuild_sqlite_table(
+    manager: &SchemaManager<'_>,
+    types: &[MessageType],
+    with_channel: bool,
+) -> Result<(), DbErr> {
+    let rebuilt = Alias::new("messages_rebuilt");
+
+    let mut table = Table::create()
+        .table(rebuilt.clone())
+        .col(
+            ColumnDef::new(Messages::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(Messages::Text).string().not_null())
+        .col(
+            ColumnDef::new(Messages::Type)
+                .string()
+                .not_null()
+                .extra(format!(
+                    "CHECK ({} IN ({}))",
+                    Messages::Type.to_string(),
+                    types
+                        .iter()
+                        .map(|message_type| format!("'{}'", message_type.to_string()))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+        )
+        .to_owned();
+    if with_channel {
+        table.col(ColumnDef::new(Messages::Channel).string().null());
+    }
+    manager.create_table(table).await?;
+
+    let columns = [Messages::Id, Messages::Text, Messages::Type];
+    let mut copy = Query::insert()
+        .into_table(rebuilt.clone())
+        .columns(columns)
+        .to_owned();
+    copy.select_from(
+        Query::select()
+            .columns(columns)
+            .from(Messages::Table)
+            .to_owned(),
+    )
+    .map_err(|err| DbErr::Migration(err.to_string()))?;
+    manager.exec_stmt(copy).await?;
+
+    manager
+        .drop_table(Table::drop().table(Messages::Table).to_owned())
+        .await?;
+    manager
+        .rename_table(Table::rename().table(rebuilt, Messages::Table).to_owned())
+        .await
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden, Clone, Copy)]
+enum Messages {
+    Table,
+    Id,
+    Text,
+    Type,
+    Channel,
+}
+
+#[derive(Iden)]
+enum MessageType {
+    #[iden = "message_type"]
+    Type,
+    Cooldown,
+    Catch,
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+
+    use super::*;
+    use crate::test_db;
+
+    async fn insert(db: &DatabaseConnection, message_type: &str) -> Result<(), DbErr> {
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            format!("INSERT INTO messages (text, type) VALUES ('text', '{message_type}')"),
+        ))
+        .await
+        .map(|_| ())
+    }
+
+    async fn count(db: &DatabaseConnection) -> i64 {
+        db.query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT COUNT(*) AS count FROM messages".to_owned(),
+        ))
+        .await
+        .unwrap()
+        .unwrap()
+        .try_get("", "count")
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn applies_and_rolls_back() {
+        let db = test_db().await;
+        let cooldown_messages = count(&db).await;
+
+        insert(&db, "catch").await.unwrap();
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "UPDATE messages SET channel = 'chronophylos' WHERE type = 'catch'".to_owned(),
+        ))
+        .await
+        .unwrap();
+        assert!(insert(&db, "unknown").await.is_err());
+
+        let manager = SchemaManager::new(&db);
+        Migration.down(&manager).await.unwrap();
+
+        assert_eq!(count(&db).await, cooldown_messages);
+        assert!(insert(&db, "catch").await.is_err());
+
+        Migration.up(&manager).await.unwrap();
+        insert(&db, "catch").await.unwrap();
+
+        assert_eq!(count(&db).await, cooldown_messages + 1);
+    }
+}
@@ -0,0 +1,71 @@
+use sea_orm_migration::prelude::*;
+
+use super::m20220828_131908_create_users_table::Users;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Inventory::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Inventory::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Inventory::UserId).integer().not_null())
+                    .col(ColumnDef::new(Inventory::Item).string().not_null())
+                    .col(
+                        ColumnDef::new(Inventory::Amount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk-inventory-user_id")
+                            .from(Inventory::Table, Inventory::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // one row per item a user holds
+        manager
+            .create_index(
+                Index::create()
+                    .name("inventory_user_id_item_idx")
+                    .table(Inventory::Table)
+                    .col(Inventory::UserId)
+                    .col(Inventory::Item)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Inventory::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Inventory {
+    Table,
+    Id,
+    UserId,
+    Item,
+    Amount,
+}
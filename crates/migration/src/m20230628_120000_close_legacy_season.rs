@@ -0,0 +1,95 @@
+use sea_orm_migration::prelude::*;
+
+use crate::m20230426_115812_integrate_seasons::{Seasons, LEGACY_SEASON_ID};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // The legacy season has no end, so it stays active next to the
+        // quarter seasons. It ends when the first of them starts. Without
+        // another season the end stays NULL.
+        //
+        // MySQL does not allow selecting from the updated table in a
+        // subquery, unless it is wrapped in a derived table.
+        let first_season_start = Expr::cust(&format!(
+            "(SELECT start FROM \
+             (SELECT MIN(start) AS start FROM seasons WHERE id <> {LEGACY_SEASON_ID}) \
+             AS first_season)"
+        ));
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Seasons::Table)
+                    .value(Seasons::End, first_season_start)
+                    .and_where(Expr::col(Seasons::Id).eq(LEGACY_SEASON_ID))
+                    .and_where(Expr::col(Seasons::End).is_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(Seasons::Table)
+                    .value(Seasons::End, Option::<String>::None)
+                    .and_where(Expr::col(Seasons::Id).eq(LEGACY_SEASON_ID))
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm_migration::sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+
+    use super::*;
+    use crate::test_db;
+
+    /// Seasons that are active on 2023-05-01, like the bot looks them up
+    async fn active_seasons(db: &DatabaseConnection) -> i64 {
+        db.query_one(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT COUNT(*) AS count FROM seasons \
+             WHERE start < '2023-05-01' AND (\"end\" > '2023-05-01' OR \"end\" IS NULL)"
+                .to_owned(),
+        ))
+        .await
+        .unwrap()
+        .unwrap()
+        .try_get("", "count")
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn closes_legacy_season() {
+        let db = test_db().await;
+        // nothing to close without another season
+        assert_eq!(active_seasons(&db).await, 1);
+
+        db.execute(Statement::from_string(
+            DbBackend::Sqlite,
+            "INSERT INTO seasons (name, start, \"end\", bundle_id) \
+             SELECT '2023Q2', '2023-04-01', '2023-07-01', bundle_id FROM seasons"
+                .to_owned(),
+        ))
+        .await
+        .unwrap();
+        assert_eq!(active_seasons(&db).await, 2);
+
+        let manager = SchemaManager::new(&db);
+        Migration.up(&manager).await.unwrap();
+
+        assert_eq!(active_seasons(&db).await, 1);
+
+        Migration.down(&manager).await.unwrap();
+
+        assert_eq!(active_seasons(&db).await, 2);
+    }
+}
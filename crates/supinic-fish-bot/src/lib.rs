@@ -1,19 +1,27 @@
 mod parser;
+mod sell_name;
 
-use std::time::Duration;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
 use bot_framework::runner::{Client, IrcError};
 use exponential_backoff::Backoff;
-use log::{debug, error, info, trace};
-use miette::{Diagnostic, IntoDiagnostic, Result, WrapErr};
+use log::{debug, error, info, trace, warn};
+use miette::{miette, Diagnostic, IntoDiagnostic, Result, WrapErr};
 use sea_orm::DatabaseConnection;
 use tokio::{
-    sync::mpsc::{Receiver, Sender},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        oneshot,
+    },
+    task::JoinSet,
     time::timeout,
 };
 use twitch_irc::message::ServerMessage;
 
-use crate::parser::fish_response::{FishResponse, FishResponseKind};
+use crate::{
+    parser::fish_response::{FishResponse, FishResponseKind},
+    sell_name::sell_name,
+};
 
 const BOT_LOGIN: &str = "supibot";
 
@@ -30,62 +38,170 @@ pub enum Error {
     #[error("timed out waiting for response")]
     #[diagnostic(code(supinic_fish_bot::receive_message_timeout))]
     ReceiveMessageTimeout,
+
+    #[error("failed to parse fish response from {text}")]
+    #[diagnostic(code(supinic_fish_bot::parse_response))]
+    ParseResponse {
+        text: String,
+        #[source]
+        source: crate::parser::fish_response::Error,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Message {
     Bot(String),
     Ready,
 }
 
+/// Passes messages to the task of the channel they were sent in
+#[derive(Debug, Clone)]
+pub struct Router {
+    tasks: Arc<HashMap<String, Sender<Message>>>,
+}
+
+impl Router {
+    /// A router for `channels` and the receiving ends of their tasks
+    pub fn new(
+        channels: impl IntoIterator<Item = String>,
+    ) -> (Self, Vec<(String, Receiver<Message>)>) {
+        let mut tasks = HashMap::new();
+        let mut receivers = Vec::new();
+
+        for channel in channels {
+            let (tx, rx) = mpsc::channel(1);
+            tasks.insert(channel.clone(), tx);
+            receivers.push((channel, rx));
+        }
+
+        let router = Self {
+            tasks: Arc::new(tasks),
+        };
+
+        (router, receivers)
+    }
+
+    /// Pass `message` to the task of `channel`, or to every task if it
+    /// belongs to no channel. Tasks that stopped are skipped when passing to
+    /// every task, so the others still get the message.
+    async fn route(&self, channel: Option<&str>, message: Message) -> Result<()> {
+        let broadcast = channel.is_none();
+        let tasks: Vec<_> = match channel {
+            Some(channel) => self.tasks.get_key_value(channel).into_iter().collect(),
+            None => self.tasks.iter().collect(),
+        };
+
+        for (channel, tx) in tasks {
+            trace!("[{channel}] passing message to main task: {message:?}");
+            match tx.send(message.clone()).await {
+                Ok(()) => {}
+                Err(_) if broadcast => {
+                    warn!("[{channel}] main task stopped, skipping message: {message:?}");
+                }
+                Err(err) => {
+                    return Err(err).into_diagnostic().wrap_err_with(|| {
+                        format!("failed to pass message to main task of {channel}")
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub async fn handle_server_message(
     _conn: DatabaseConnection,
     _client: Client,
     server_message: ServerMessage,
     username: String,
-    tx: Sender<Message>,
+    router: Router,
 ) -> Result<()> {
     trace!("handling message: {:?}", server_message);
 
-    let message = match server_message {
-        ServerMessage::GlobalUserState(_) => Message::Ready,
+    match server_message {
+        // every channel shares the connection, so they are all ready at once
+        ServerMessage::GlobalUserState(_) => router.route(None, Message::Ready).await,
         ServerMessage::Privmsg(msg)
             if msg.sender.login == BOT_LOGIN && msg.message_text.starts_with(&username) =>
         {
-            Message::Bot(msg.message_text)
+            router
+                .route(Some(&msg.channel_login), Message::Bot(msg.message_text))
+                .await
         }
-        _ => return Ok(()),
-    };
-
-    trace!("passing message to main task: {message:?}");
-    tx.send(message)
-        .await
-        .into_diagnostic()
-        .wrap_err("failed to pass message to main task")?;
+        _ => Ok(()),
+    }
+}
 
-    Ok(())
+/// How many fish and sell cycles to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Fish until the bot is stopped
+    #[default]
+    Loop,
+    /// Fish and sell once, then exit. Useful to check the parser against live
+    /// responses.
+    Once,
 }
 
+/// Spawn a main task for each of the `channels`, receiving the messages
+/// [`Router`] passes to it.
+///
+/// In [`Mode::Once`] the outcome is sent to `finished` once every task is
+/// done, so the bot can shut down.
 pub async fn run_wrapper(
     _conn: DatabaseConnection,
     client: Client,
-    channel: String,
-    rx: Receiver<Message>,
+    channels: Vec<(String, Receiver<Message>)>,
+    mode: Mode,
+    finished: oneshot::Sender<Result<()>>,
 ) -> Result<()> {
+    let mut tasks = JoinSet::new();
+
+    for (channel, rx) in channels {
+        let client = client.clone();
+
+        tasks.spawn(async move {
+            let result = run(client, &channel, rx, mode).await;
+
+            if let Err(e) = &result {
+                error!("[{channel}] error in main task: {e}");
+            }
+
+            result.is_ok()
+        });
+    }
+
     tokio::spawn(async move {
-        if let Err(e) = run(client, channel, rx).await {
-            log::error!("error in main task: {}", e);
+        let mut failed = false;
+        while let Some(result) = tasks.join_next().await {
+            failed |= !result.unwrap_or(false);
+        }
+
+        if mode == Mode::Once {
+            let outcome = if failed {
+                Err(miette!("fishing failed in at least one channel"))
+            } else {
+                Ok(())
+            };
+            // main stopped waiting if it quit on a signal
+            finished.send(outcome).ok();
         }
     });
 
     Ok(())
 }
 
-async fn run(client: Client, channel: String, mut rx: Receiver<Message>) -> Result<(), Error> {
-    info!("Starting fish bot");
+async fn run(
+    client: Client,
+    channel: &str,
+    mut rx: Receiver<Message>,
+    mode: Mode,
+) -> Result<(), Error> {
+    info!("[{channel}] Starting fish bot");
 
     // wait for ready message
-    debug!("waiting for twitch to be ready");
+    debug!("[{channel}] waiting for twitch to be ready");
     loop {
         match rx.recv().await {
             Some(Message::Ready) => break,
@@ -97,47 +213,19 @@ async fn run(client: Client, channel: String, mut rx: Receiver<Message>) -> Resu
     }
 
     loop {
-        let message = send_command(
-            &client,
-            &mut rx,
-            channel.clone(),
-            "$fish skipStory:true".to_string(),
-        )
-        .await?;
-
-        debug!("parsing response");
-        let response = match FishResponse::parse(&message) {
+        let response = match fish_and_sell(&client, &mut rx, channel).await {
             Ok(response) => response,
-            Err(err) => {
-                error!("failed to parse fish response from {message}: {err}");
+            Err(err @ Error::ParseResponse { .. }) if mode == Mode::Loop => {
+                error!("[{channel}] {err}");
                 tokio::time::sleep(Duration::from_secs_f32(5.2)).await;
                 continue;
             }
+            Err(err) => return Err(err),
         };
 
-        debug!("fish response: {:?}", response);
-
-        match response.kind {
-            FishResponseKind::Success { catch, length } => {
-                trace!("caught fish: {catch} @ {length} cm");
-
-                tokio::time::sleep(Duration::from_secs_f32(5.2)).await;
-                sell(&client, &mut rx, channel.clone(), &catch).await?;
-            }
-            FishResponseKind::Failure {
-                junk: Some(junk), ..
-            } => {
-                trace!("caught junk: {junk}");
-
-                tokio::time::sleep(Duration::from_secs_f32(5.2)).await;
-                sell(&client, &mut rx, channel.clone(), &junk).await?;
-            }
-            FishResponseKind::Failure { .. } => {
-                trace!("no junk caught");
-            }
-            FishResponseKind::Cooldown => {
-                trace!("command is on cooldown");
-            }
+        if mode == Mode::Once {
+            info!("[{channel}] fish response: {response:?}");
+            return Ok(());
         }
 
         let cooldown = response
@@ -145,18 +233,66 @@ async fn run(client: Client, channel: String, mut rx: Receiver<Message>) -> Resu
             .clamp(Duration::from_secs(5), Duration::from_secs(60 * 60 * 24))
             + Duration::from_secs_f32(0.3);
 
-        info!("sleeping for {cooldown:?}");
+        info!("[{channel}] sleeping for {cooldown:?}");
         tokio::time::sleep(cooldown).await;
     }
 }
 
+/// Send `$fish` once and sell whatever was caught
+async fn fish_and_sell(
+    client: &Client,
+    rx: &mut Receiver<Message>,
+    channel: &str,
+) -> Result<FishResponse, Error> {
+    let message = send_command(
+        client,
+        rx,
+        channel.to_string(),
+        "$fish skipStory:true".to_string(),
+    )
+    .await?;
+
+    debug!("[{channel}] parsing response");
+    let response = FishResponse::parse(&message).map_err(|source| Error::ParseResponse {
+        text: message.clone(),
+        source,
+    })?;
+
+    debug!("[{channel}] fish response: {:?}", response);
+
+    match &response.kind {
+        FishResponseKind::Success { catch, length } => {
+            trace!("[{channel}] caught fish: {catch} @ {length} cm");
+
+            tokio::time::sleep(Duration::from_secs_f32(5.2)).await;
+            sell(client, rx, channel.to_string(), catch).await?;
+        }
+        FishResponseKind::Failure {
+            junk: Some(junk), ..
+        } => {
+            trace!("[{channel}] caught junk: {junk}");
+
+            tokio::time::sleep(Duration::from_secs_f32(5.2)).await;
+            sell(client, rx, channel.to_string(), junk).await?;
+        }
+        FishResponseKind::Failure { .. } => {
+            trace!("[{channel}] no junk caught");
+        }
+        FishResponseKind::Cooldown => {
+            trace!("[{channel}] command is on cooldown");
+        }
+    }
+
+    Ok(response)
+}
+
 async fn send_command(
     client: &Client,
     rx: &mut Receiver<Message>,
     channel: String,
     command: String,
 ) -> Result<String, Error> {
-    debug!("sending command: {command}");
+    debug!("[{channel}] sending command: {command}");
 
     let backoff = Backoff::new(3, Duration::from_secs_f32(5.2), Duration::from_secs(30));
 
@@ -185,10 +321,62 @@ async fn sell(
     channel: String,
     what: &str,
 ) -> Result<(), Error> {
-    let message = send_command(client, rx, channel, format!("$fish sell {what}")).await?;
+    let message = send_command(
+        client,
+        rx,
+        channel,
+        format!("$fish sell {}", sell_name(what)),
+    )
+    .await?;
 
     // TODO: parse sell response
     dbg!(message);
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn bot_messages_reach_their_channel() {
+        let (router, mut receivers) = Router::new(["forsen".to_string(), "pajlada".to_string()]);
+
+        router
+            .route(Some("pajlada"), Message::Bot("fish".to_string()))
+            .await
+            .unwrap();
+
+        for (channel, rx) in &mut receivers {
+            let message = rx.try_recv().ok();
+            if channel == "pajlada" {
+                assert!(matches!(message, Some(Message::Bot(text)) if text == "fish"));
+            } else {
+                assert!(message.is_none());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn every_channel_gets_ready() {
+        let (router, mut receivers) = Router::new(["forsen".to_string(), "pajlada".to_string()]);
+
+        router.route(None, Message::Ready).await.unwrap();
+
+        for (_, rx) in &mut receivers {
+            assert!(matches!(rx.try_recv(), Ok(Message::Ready)));
+        }
+    }
+
+    #[tokio::test]
+    async fn stopped_channels_are_skipped() {
+        let (router, mut receivers) = Router::new(["forsen".to_string(), "pajlada".to_string()]);
+        receivers.retain(|(channel, _)| channel != "forsen");
+
+        router.route(None, Message::Ready).await.unwrap();
+
+        let (_, rx) = &mut receivers[0];
+        assert!(matches!(rx.try_recv(), Ok(Message::Ready)));
+    }
+}
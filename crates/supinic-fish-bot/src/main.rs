@@ -1,10 +1,15 @@
-use std::collections::HashSet;
+use std::time::Duration;
 
-use bot_framework::runner::{start_bot, Client, Config};
+use bot_framework::{
+    drain::DEFAULT_DRAIN_TIMEOUT,
+    runner::{parse_channels, start_bot, Client, Config},
+};
+use database::secret::secret_var;
 use futures::future::FutureExt;
 use miette::{IntoDiagnostic, Result, WrapErr};
 use sea_orm::DatabaseConnection;
-use supinic_fish_bot::{handle_server_message, run_wrapper};
+use supinic_fish_bot::{handle_server_message, run_wrapper, Mode, Router};
+use tokio::{select, sync::oneshot};
 use twitch_irc::message::ServerMessage;
 
 #[inline]
@@ -14,35 +19,76 @@ fn env_var(name: &'static str) -> Result<String> {
         .wrap_err_with(|| format!("env var {name} is not set"))
 }
 
+/// Value of `--channel`, which takes precedence over `CHANNEL`
+fn channel_arg() -> Option<String> {
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        if arg == "--channel" {
+            return args.next();
+        }
+        if let Some(channel) = arg.strip_prefix("--channel=") {
+            return Some(channel.to_string());
+        }
+    }
+
+    None
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     pretty_env_logger::init_timed();
     dotenvy::dotenv().ok();
 
-    let (tx, rx) = tokio::sync::mpsc::channel(1);
-
-    let wanted_channel = env_var("CHANNEL")?;
+    let wanted_channels = match channel_arg() {
+        Some(channels) => parse_channels(&channels),
+        None => parse_channels(&env_var("CHANNEL")?),
+    };
+    if wanted_channels.is_empty() {
+        miette::bail!("no channel to fish in, set CHANNEL or --channel");
+    }
     let username = env_var("USERNAME")?;
-    let client_id = env_var("CLIENT_ID")?;
-    let client_secret = env_var("CLIENT_SECRET")?;
+    let client_id = secret_var("CLIENT_ID").into_diagnostic()?;
+    let client_secret = secret_var("CLIENT_SECRET").into_diagnostic()?;
+    let mode = if std::env::args().skip(1).any(|arg| arg == "--once") {
+        Mode::Once
+    } else {
+        Mode::Loop
+    };
+    let drain_timeout = match std::env::var("DRAIN_TIMEOUT") {
+        Ok(secs) => Duration::from_secs(
+            secs.parse()
+                .into_diagnostic()
+                .wrap_err("DRAIN_TIMEOUT is not a number of seconds")?,
+        ),
+        Err(_) => DEFAULT_DRAIN_TIMEOUT,
+    };
+    let (router, receivers) = Router::new(wanted_channels.iter().cloned());
     let config = Config {
-        wanted_channels: vec![wanted_channel.clone()]
-            .into_iter()
-            .collect::<HashSet<_>>(),
+        wanted_channels,
         username: username.clone(),
         client_id,
         client_secret,
+        drain_timeout,
+        // the router hands replies to the fishing loop in the order they arrive
+        concurrent: false,
     };
 
-    start_bot(
+    let (finished_tx, finished) = oneshot::channel();
+
+    let bot = start_bot(
         config,
         move |conn: DatabaseConnection, client: Client| {
-            run_wrapper(conn, client, wanted_channel, rx).boxed()
+            run_wrapper(conn, client, receivers, mode, finished_tx).boxed()
         },
         move |conn: DatabaseConnection, client: Client, message: ServerMessage| {
-            handle_server_message(conn, client, message, username.clone(), tx.clone()).boxed()
+            handle_server_message(conn, client, message, username.clone(), router.clone()).boxed()
         },
-    )
-    .await
-    .wrap_err("failed to run bot")
+    );
+
+    // the twitch connection only closes on a signal, so stop once fishing is done
+    select! {
+        result = bot => result.wrap_err("failed to run bot"),
+        Ok(outcome) = finished => outcome,
+    }
 }
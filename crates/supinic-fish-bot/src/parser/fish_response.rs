@@ -14,7 +14,7 @@ static FISH_RESPONSE_SUCCESS_REGEX: Lazy<Regex> = Lazy::new(|| {
 });
 const FISH_RESPONSE_FAILURE_PREFIX: &str = "No luck..";
 static FISH_RESPONSE_FAILURE_REGEX: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r#"No luck\.{3} \D+ (You reel out a (?P<junk>.)|(?P<distance>\d+) cm away\.) \(((?P<minutes>\d+)m, )?((?P<seconds>\d+)s )cooldown\)( This is your attempt #(?P<attempt>\d+) since your last catch\.)?"#).unwrap()
+    Regex::new(r#"No luck\.{3} \D+ (You reel out a (?P<junk>.)|(?P<distance>\d+) cm away\.) \((?:(?P<minutes>\d+)m(?:, | ))?(?:(?P<seconds>\d+)s )?cooldown\)( This is your attempt #(?P<attempt>\d+) since your last catch\.)?"#).unwrap()
 });
 
 #[derive(Debug, thiserror::Error, Diagnostic)]
@@ -109,10 +109,8 @@ impl FishResponse {
                     .unwrap_or(0);
                 let seconds = captures
                     .name("seconds")
-                    .unwrap()
-                    .as_str()
-                    .parse::<u64>()
-                    .unwrap();
+                    .map(|m| m.as_str().parse::<u64>().unwrap())
+                    .unwrap_or(0);
 
                 Ok(Self {
                     name,
@@ -280,6 +278,23 @@ mod tests {
 
                 assert_eq!(result, expected);
             }
+
+            #[test]
+            fn failure_response_with_minutes_only_cooldown() {
+                let input = r#"gargoyletec, No luck... Sadge Your fishing line landed 150 cm away. (1m cooldown) This is your attempt #8 since your last catch."#;
+                let result = FishResponse::parse(input).unwrap();
+                let expected = FishResponse {
+                    name: "gargoyletec".to_string(),
+                    kind: FishResponseKind::Failure {
+                        attempt: Some(8),
+                        distance: Some(150),
+                        junk: None,
+                    },
+                    cooldown: std::time::Duration::from_secs(60),
+                };
+
+                assert_eq!(result, expected);
+            }
         }
     }
 }
@@ -0,0 +1,84 @@
+use log::warn;
+
+/// Catches as they appear in `$fish` responses mapped to the name `$fish sell`
+/// expects. Add new catches here when supibot adds them.
+const SELL_NAMES: &[(&str, &str)] = &[
+    // fish
+    ("🐡", "🐡"),
+    ("🐠", "🐠"),
+    ("🐟", "🐟"),
+    ("🦈", "🦈"),
+    ("🐬", "🐬"),
+    ("🐳", "🐳"),
+    ("🐋", "🐋"),
+    ("🦀", "🦀"),
+    ("🦞", "🦞"),
+    ("🦐", "🦐"),
+    ("🦑", "🦑"),
+    ("🐙", "🐙"),
+    // junk
+    ("🥫", "🥫"),
+    ("💀", "💀"),
+    ("🥾", "🥾"),
+    ("🌿", "🌿"),
+    ("🍂", "🍂"),
+    ("🧦", "🧦"),
+    ("🦴", "🦴"),
+    ("🪵", "🪵"),
+    ("🩲", "🩲"),
+    ("🧻", "🧻"),
+    ("🔋", "🔋"),
+    ("🛒", "🛒"),
+    // shown with an emoji variation selector, sold without
+    ("☂️", "☂"),
+    ("🕸️", "🕸"),
+    ("🕷️", "🕷"),
+];
+
+/// Map a caught emote to the name it is sold as.
+///
+/// Unknown catches are logged and passed through unchanged, as supibot might
+/// still know them.
+pub fn sell_name(catch: &str) -> &str {
+    let catch = catch.trim();
+
+    match SELL_NAMES.iter().find(|(caught, _)| *caught == catch) {
+        Some((_, name)) => name,
+        None => {
+            warn!("selling unknown catch {catch:?}");
+            catch
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_fish() {
+        assert_eq!(sell_name("🦀"), "🦀");
+        assert_eq!(sell_name("🐡"), "🐡");
+    }
+
+    #[test]
+    fn known_junk() {
+        assert_eq!(sell_name("🌿"), "🌿");
+    }
+
+    #[test]
+    fn strips_variation_selector() {
+        assert_eq!(sell_name("🕷\u{fe0f}"), "🕷");
+        assert_eq!(sell_name("☂️"), "☂");
+    }
+
+    #[test]
+    fn trims_whitespace() {
+        assert_eq!(sell_name(" 🐟 "), "🐟");
+    }
+
+    #[test]
+    fn unknown_catch_is_passed_through() {
+        assert_eq!(sell_name("🦖"), "🦖");
+    }
+}